@@ -0,0 +1,423 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Comparison of two TL schemas, to audit the changes of a TDLib upgrade
+//! before regenerating the bindings.
+
+use crate::rustifier;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use tdlib_rs_parser::tl::{Category, Definition, Parameter};
+
+/// A change between two versions of a schema, concerning a function or a
+/// constructor (a variant of the enum generated for its type).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A definition that only exists in the new schema. `new_type` is whether
+    /// the type of a constructor is new too, so that no existing enum gains a
+    /// variant.
+    Added {
+        category: Category,
+        name: String,
+        ty: String,
+        new_type: bool,
+    },
+    /// A definition that only exists in the old schema.
+    Removed {
+        category: Category,
+        name: String,
+        ty: String,
+    },
+    /// A definition whose name changed while its parameters and type didn't.
+    Renamed {
+        category: Category,
+        old_name: String,
+        new_name: String,
+    },
+    /// A function whose result type changed, or a constructor that moved to
+    /// another type.
+    TypeChanged {
+        category: Category,
+        name: String,
+        old_ty: String,
+        new_ty: String,
+    },
+    /// A parameter that only exists in the new schema.
+    ParamAdded {
+        category: Category,
+        name: String,
+        param: String,
+        ty: String,
+    },
+    /// A parameter that only exists in the old schema.
+    ParamRemoved {
+        category: Category,
+        name: String,
+        param: String,
+    },
+    /// A parameter whose name changed while its position and type didn't.
+    ParamRenamed {
+        category: Category,
+        name: String,
+        old_param: String,
+        new_param: String,
+    },
+    /// A parameter whose type changed.
+    ParamTypeChanged {
+        category: Category,
+        name: String,
+        param: String,
+        old_ty: String,
+        new_ty: String,
+    },
+    /// A parameter that became optional (or required), which changes whether
+    /// it's wrapped in an `Option` by the generated code.
+    ParamOptionalityChanged {
+        category: Category,
+        name: String,
+        param: String,
+        optional: bool,
+    },
+}
+
+impl Change {
+    /// Returns whether the change breaks the code using the generated
+    /// bindings: everything but the new functions and the new types does,
+    /// since even a new variant or field breaks exhaustive `match`es and
+    /// struct literals.
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            Self::Added {
+                category, new_type, ..
+            } => *category == Category::Types && !new_type,
+            _ => true,
+        }
+    }
+}
+
+/// The description of a definition, e.g. "function `getChat`".
+struct Item<'a>(Category, &'a str);
+
+impl fmt::Display for Item<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Category::Types => write!(f, "constructor `{}`", self.1),
+            Category::Functions => write!(f, "function `{}`", self.1),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added {
+                category, name, ty, ..
+            } => write!(f, "+ {} = {ty}", Item(*category, name)),
+            Self::Removed { category, name, ty } => {
+                write!(f, "- {} = {ty}", Item(*category, name))
+            }
+            Self::Renamed {
+                category,
+                old_name,
+                new_name,
+            } => write!(f, "~ {} renamed to `{new_name}`", Item(*category, old_name)),
+            Self::TypeChanged {
+                category,
+                name,
+                old_ty,
+                new_ty,
+            } => write!(
+                f,
+                "~ {} changed type from {old_ty} to {new_ty}",
+                Item(*category, name)
+            ),
+            Self::ParamAdded {
+                category,
+                name,
+                param,
+                ty,
+            } => write!(f, "+ {} parameter `{param}:{ty}`", Item(*category, name)),
+            Self::ParamRemoved {
+                category,
+                name,
+                param,
+            } => write!(f, "- {} parameter `{param}`", Item(*category, name)),
+            Self::ParamRenamed {
+                category,
+                name,
+                old_param,
+                new_param,
+            } => write!(
+                f,
+                "~ {} parameter `{old_param}` renamed to `{new_param}`",
+                Item(*category, name)
+            ),
+            Self::ParamTypeChanged {
+                category,
+                name,
+                param,
+                old_ty,
+                new_ty,
+            } => write!(
+                f,
+                "~ {} parameter `{param}` changed type from {old_ty} to {new_ty}",
+                Item(*category, name)
+            ),
+            Self::ParamOptionalityChanged {
+                category,
+                name,
+                param,
+                optional,
+            } => write!(
+                f,
+                "~ {} parameter `{param}` became {}",
+                Item(*category, name),
+                if *optional { "optional" } else { "required" }
+            ),
+        }
+    }
+}
+
+/// Returns the parameters and the type of a definition, which don't change
+/// when it's only renamed.
+fn signature(def: &Definition) -> (Vec<String>, String) {
+    let params = def.params.iter().map(|p| p.to_string()).collect();
+    (params, def.ty.to_string())
+}
+
+/// Compares the parameters of a definition existing in both schemas.
+fn diff_params(old: &Definition, new: &Definition, changes: &mut Vec<Change>) {
+    let category = new.category;
+    let name = &new.name;
+    let find = |params: &'_ [Parameter], param: &Parameter| {
+        params.iter().position(|p| p.name == param.name)
+    };
+
+    let removed: Vec<(usize, &Parameter)> = old
+        .params
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| find(&new.params, p).is_none())
+        .collect();
+    let mut added: Vec<(usize, &Parameter)> = new
+        .params
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| find(&old.params, p).is_none())
+        .collect();
+
+    for (index, old_param) in removed {
+        // A parameter replaced by one of the same type in the same position
+        // has been renamed
+        match added
+            .iter()
+            .position(|(i, p)| *i == index && p.ty == old_param.ty)
+        {
+            Some(renamed) => {
+                let (_, new_param) = added.remove(renamed);
+                changes.push(Change::ParamRenamed {
+                    category,
+                    name: name.clone(),
+                    old_param: old_param.name.clone(),
+                    new_param: new_param.name.clone(),
+                });
+            }
+            None => changes.push(Change::ParamRemoved {
+                category,
+                name: name.clone(),
+                param: old_param.name.clone(),
+            }),
+        }
+    }
+
+    for old_param in &old.params {
+        let Some(new_param) = new.params.iter().find(|p| p.name == old_param.name) else {
+            continue;
+        };
+        if old_param.ty != new_param.ty {
+            changes.push(Change::ParamTypeChanged {
+                category,
+                name: name.clone(),
+                param: new_param.name.clone(),
+                old_ty: old_param.ty.to_string(),
+                new_ty: new_param.ty.to_string(),
+            });
+        }
+        let optional = rustifier::parameters::is_optional(new_param);
+        if rustifier::parameters::is_optional(old_param) != optional {
+            changes.push(Change::ParamOptionalityChanged {
+                category,
+                name: name.clone(),
+                param: new_param.name.clone(),
+                optional,
+            });
+        }
+    }
+
+    for (_, new_param) in added {
+        changes.push(Change::ParamAdded {
+            category,
+            name: name.clone(),
+            param: new_param.name.clone(),
+            ty: new_param.ty.to_string(),
+        });
+    }
+}
+
+/// Compares the `old` and the `new` definitions of a schema, returning the
+/// changes in the order of the old definitions, followed by the added ones in
+/// the order of the new definitions.
+///
+/// A definition removed and one added with the same category, parameters and
+/// type are reported as renamed, when no other added definition matches.
+pub fn diff_definitions(old: &[Definition], new: &[Definition]) -> Vec<Change> {
+    let old_map: HashMap<(Category, &str), &Definition> = old
+        .iter()
+        .map(|d| ((d.category, d.name.as_str()), d))
+        .collect();
+    let new_map: HashMap<(Category, &str), &Definition> = new
+        .iter()
+        .map(|d| ((d.category, d.name.as_str()), d))
+        .collect();
+    let old_types: HashSet<&str> = old
+        .iter()
+        .filter(|d| d.category == Category::Types)
+        .map(|d| d.ty.name.as_str())
+        .collect();
+
+    let mut added: Vec<&Definition> = new
+        .iter()
+        .filter(|d| !old_map.contains_key(&(d.category, d.name.as_str())))
+        .collect();
+
+    let mut changes = Vec::new();
+    for old_def in old {
+        let Some(new_def) = new_map.get(&(old_def.category, old_def.name.as_str())) else {
+            let mut candidates = added.iter().enumerate().filter(|(_, d)| {
+                d.category == old_def.category && signature(d) == signature(old_def)
+            });
+            match (candidates.next(), candidates.next()) {
+                (Some((index, _)), None) => {
+                    let new_def = added.remove(index);
+                    changes.push(Change::Renamed {
+                        category: old_def.category,
+                        old_name: old_def.name.clone(),
+                        new_name: new_def.name.clone(),
+                    });
+                }
+                _ => changes.push(Change::Removed {
+                    category: old_def.category,
+                    name: old_def.name.clone(),
+                    ty: old_def.ty.to_string(),
+                }),
+            }
+            continue;
+        };
+
+        if old_def.ty != new_def.ty {
+            changes.push(Change::TypeChanged {
+                category: new_def.category,
+                name: new_def.name.clone(),
+                old_ty: old_def.ty.to_string(),
+                new_ty: new_def.ty.to_string(),
+            });
+        }
+        diff_params(old_def, new_def, &mut changes);
+    }
+
+    for new_def in added {
+        changes.push(Change::Added {
+            category: new_def.category,
+            name: new_def.name.clone(),
+            ty: new_def.ty.to_string(),
+            new_type: !old_types.contains(new_def.ty.name.as_str()),
+        });
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tdlib_rs_parser::parse_tl_file;
+
+    fn parse(definitions: &[&str]) -> Vec<Definition> {
+        parse_tl_file(definitions.join(";\n") + ";")
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn diff(old: &[&str], new: &[&str]) -> Vec<String> {
+        diff_definitions(&parse(old), &parse(new))
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn check_diff_definitions() {
+        let changes = diff(
+            &[
+                "chatTypePrivate user_id:int53 = ChatType",
+                "chatTypeSecret secret_chat_id:int32 = ChatType",
+            ],
+            &[
+                "chatTypePrivate user_id:int53 = ChatType",
+                "chatTypeBasicGroup basic_group_id:int53 = ChatType",
+                "storyInfo story_id:int32 = StoryInfo",
+            ],
+        );
+        assert_eq!(
+            changes,
+            [
+                "- constructor `chatTypeSecret` = ChatType",
+                "+ constructor `chatTypeBasicGroup` = ChatType",
+                "+ constructor `storyInfo` = StoryInfo",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_diff_renames() {
+        let changes = diff(
+            &["---functions---\ngetChats limit:int32 = Chats"],
+            &["---functions---\nloadChats limit:int32 = Chats"],
+        );
+        assert_eq!(changes, ["~ function `getChats` renamed to `loadChats`"]);
+    }
+
+    #[test]
+    fn check_diff_params() {
+        let changes = diff(
+            &["message id:int32 chat_id:int53 date:int32 = Message"],
+            &["message id:int53 sender_id:int53 date:int32 is_pinned:Bool = Message"],
+        );
+        assert_eq!(
+            changes,
+            [
+                "~ constructor `message` parameter `chat_id` renamed to `sender_id`",
+                "~ constructor `message` parameter `id` changed type from int32 to int53",
+                "+ constructor `message` parameter `is_pinned:Bool`",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_is_breaking() {
+        let old = parse(&["chatTypePrivate user_id:int53 = ChatType"]);
+        let new = parse(&[
+            "chatTypePrivate user_id:int53 = ChatType",
+            "chatTypeSecret secret_chat_id:int32 = ChatType",
+            "storyInfo story_id:int32 = StoryInfo",
+        ]);
+        let changes = diff_definitions(&old, &new);
+        assert!(changes[0].is_breaking());
+        assert!(!changes[1].is_breaking());
+    }
+}
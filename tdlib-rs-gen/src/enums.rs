@@ -15,6 +15,7 @@ use crate::metadata::Metadata;
 use crate::rustifier;
 use crate::GeneratorConfig;
 use std::io::{self, Write};
+use std::path::Path;
 use tdlib_rs_parser::tl::{Category, Definition, Type};
 
 /// Writes an enumeration listing all types such as the following rust code:
@@ -30,10 +31,29 @@ fn write_enum<W: Write>(
     metadata: &Metadata,
     config: &GeneratorConfig,
 ) -> io::Result<()> {
+    writeln!(file, "{}", rustifier::types::doc_link(ty, "    "))?;
+
+    let enum_feature = metadata.type_api_group(ty);
+    if let Some(feature) = enum_feature {
+        writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+    }
     writeln!(
         file,
         "    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]",
     )?;
+    if config.derive_json_schema {
+        writeln!(file, "    #[derive(schemars::JsonSchema)]")?;
+    }
+    // Arbitrary can't be derived for enums without variants, like the ones
+    // whose variants are all for bots only
+    let has_variants = config.gen_unrecognized_variants
+        || metadata
+            .defs_with_type(ty)
+            .iter()
+            .any(|d| !rustifier::definitions::is_for_bots_only(d) || config.gen_bots_only_api);
+    if config.derive_arbitrary && has_variants {
+        writeln!(file, "    #[derive(arbitrary::Arbitrary)]")?;
+    }
     writeln!(file, "    #[serde(tag = \"@type\")]")?;
     writeln!(file, "    pub enum {} {{", rustifier::types::type_name(ty))?;
     for d in metadata.defs_with_type(ty) {
@@ -46,6 +66,12 @@ fn write_enum<W: Write>(
             "{}",
             rustifier::definitions::description(d, "        ")
         )?;
+        if let Some(feature) = metadata
+            .def_api_group(d)
+            .filter(|f| Some(*f) != enum_feature)
+        {
+            writeln!(file, "        #[cfg(feature = \"{feature}\")]")?;
+        }
         writeln!(
             file,
             "        #[serde(rename(serialize = \"{0}\", deserialize = \"{0}\"))]",
@@ -61,26 +87,227 @@ fn write_enum<W: Write>(
             write!(file, "(")?;
         }
 
-        if metadata.is_recursive_def(d) {
+        if metadata.is_boxed_def(d) {
             write!(file, "Box<")?;
         }
         write!(file, "{}", rustifier::definitions::qual_name(d))?;
-        if metadata.is_recursive_def(d) {
+        if metadata.is_boxed_def(d) {
             write!(file, ">")?;
         }
 
         writeln!(file, "),")?;
     }
+    if config.gen_unrecognized_variants {
+        writeln!(
+            file,
+            "        /// A variant unknown to these bindings, e.g. one added by a newer TDLib version"
+        )?;
+        writeln!(file, "        #[serde(other, skip_serializing)]")?;
+        writeln!(file, "        Unrecognized,")?;
+    }
+    writeln!(file, "    }}")?;
+
+    if config.gen_update_conversions && rustifier::types::type_name(ty) == "Update" {
+        for d in metadata.defs_with_type(ty) {
+            if rustifier::definitions::is_for_bots_only(d) && !config.gen_bots_only_api {
+                continue;
+            }
+            if !d.params.is_empty() {
+                let feature = metadata.def_api_group(d).or(enum_feature);
+                write_update_conversions(file, d, feature, metadata)?;
+            }
+        }
+        write_update_accessors(file, ty, enum_feature, metadata, config)?;
+    }
+    Ok(())
+}
+
+/// How an accessor of an `Update` reads its value from the data of a
+/// variant, or `None` if the variant doesn't have it.
+type AccessorField = fn(&Definition) -> Option<&'static str>;
+
+/// The id of the chat an update is about: its `chat_id`, or the chat of its
+/// message or of the chat it carries.
+fn chat_id_field(def: &Definition) -> Option<&'static str> {
+    let param = |name: &str, ty: &str| {
+        def.params
+            .iter()
+            .any(|p| p.name == name && p.ty.name == ty && !rustifier::parameters::is_optional(p))
+    };
+    if param("chat_id", "int53") {
+        Some("update.chat_id")
+    } else if param("message", "message") {
+        Some("update.message.chat_id")
+    } else if param("chat", "chat") {
+        Some("update.chat.id")
+    } else {
+        None
+    }
+}
+
+/// The message carried by an update.
+fn message_field(def: &Definition) -> Option<&'static str> {
+    def.params
+        .iter()
+        .any(|p| {
+            p.name == "message" && p.ty.name == "message" && !rustifier::parameters::is_optional(p)
+        })
+        .then_some("&update.message")
+}
+
+/// Writes the accessors of the fields shared by many updates:
+///
+/// ```ignore
+/// impl Update {
+///     pub fn chat_id(&self) -> Option<i64> {
+///         match self {
+///             Update::Name(update) => Some(update.chat_id),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+fn write_update_accessors<W: Write>(
+    file: &mut W,
+    ty: &Type,
+    enum_feature: Option<&str>,
+    metadata: &Metadata,
+    config: &GeneratorConfig,
+) -> io::Result<()> {
+    let accessors: [(&str, &str, &str, AccessorField); 2] = [
+        (
+            "The id of the chat the update is about, if any.",
+            "chat_id",
+            "i64",
+            chat_id_field,
+        ),
+        (
+            "The message carried by the update, if any.",
+            "message",
+            "&crate::types::Message",
+            message_field,
+        ),
+    ];
+
+    if let Some(feature) = enum_feature {
+        writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+    }
+    writeln!(file, "    impl Update {{")?;
+    for (doc, name, output, field) in accessors {
+        writeln!(file, "        /// {doc}")?;
+        writeln!(file, "        pub fn {name}(&self) -> Option<{output}> {{")?;
+        writeln!(file, "            match self {{")?;
+        for d in metadata.defs_with_type(ty) {
+            if rustifier::definitions::is_for_bots_only(d) && !config.gen_bots_only_api {
+                continue;
+            }
+            let Some(value) = field(d) else {
+                continue;
+            };
+            if let Some(feature) = metadata
+                .def_api_group(d)
+                .filter(|f| Some(*f) != enum_feature)
+            {
+                writeln!(file, "                #[cfg(feature = \"{feature}\")]")?;
+            }
+            writeln!(
+                file,
+                "                Update::{}(update) => Some({value}),",
+                rustifier::definitions::variant_name(d)
+            )?;
+        }
+        writeln!(file, "                #[allow(unreachable_patterns)]")?;
+        writeln!(file, "                _ => None,")?;
+        writeln!(file, "            }}")?;
+        writeln!(file, "        }}")?;
+    }
+    writeln!(file, "    }}")?;
+    Ok(())
+}
+
+/// Writes the conversions of an `Update` to the type of one of its variants,
+/// failing for the other variants:
+///
+/// ```ignore
+/// impl TryFrom<Update> for crate::types::UpdateName {
+///     type Error = Update;
+///     fn try_from(update: Update) -> Result<Self, Update> {
+///         match update {
+///             Update::Name(update) => Ok(update),
+///             update => Err(update),
+///         }
+///     }
+/// }
+/// ```
+///
+/// The conversions of the references are written as well.
+fn write_update_conversions<W: Write>(
+    file: &mut W,
+    def: &Definition,
+    feature: Option<&str>,
+    metadata: &Metadata,
+) -> io::Result<()> {
+    let qual_name = rustifier::definitions::qual_name(def);
+    let variant_name = rustifier::definitions::variant_name(def);
+    let (value, reference) = if metadata.is_boxed_def(def) {
+        ("*update", "&**update")
+    } else {
+        ("update", "update")
+    };
+
+    if let Some(feature) = feature {
+        writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+    }
+    writeln!(file, "    impl TryFrom<Update> for {qual_name} {{")?;
+    writeln!(file, "        type Error = Update;")?;
+    writeln!(
+        file,
+        "        fn try_from(update: Update) -> Result<Self, Update> {{"
+    )?;
+    writeln!(file, "            match update {{")?;
+    writeln!(
+        file,
+        "                Update::{variant_name}(update) => Ok({value}),"
+    )?;
+    writeln!(file, "                update => Err(update),")?;
+    writeln!(file, "            }}")?;
+    writeln!(file, "        }}")?;
+    writeln!(file, "    }}")?;
+
+    if let Some(feature) = feature {
+        writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+    }
+    writeln!(
+        file,
+        "    impl<'a> TryFrom<&'a Update> for &'a {qual_name} {{"
+    )?;
+    writeln!(file, "        type Error = ();")?;
+    writeln!(
+        file,
+        "        fn try_from(update: &'a Update) -> Result<Self, ()> {{"
+    )?;
+    writeln!(file, "            match update {{")?;
+    writeln!(
+        file,
+        "                Update::{variant_name}(update) => Ok({reference}),"
+    )?;
+    writeln!(file, "                _ => Err(()),")?;
+    writeln!(file, "            }}")?;
+    writeln!(file, "        }}")?;
     writeln!(file, "    }}")?;
     Ok(())
 }
 
 /// Write the entire module dedicated to enums.
+///
+/// When `split_dir` is set, the enums are written in one file per section
+/// inside `split_dir/enums`, and the module only includes them.
 pub(crate) fn write_enums_mod<W: Write>(
     mut file: &mut W,
     definitions: &[Definition],
     metadata: &Metadata,
     config: &GeneratorConfig,
+    split_dir: Option<&Path>,
 ) -> io::Result<()> {
     // Begin outermost mod
     writeln!(file, "#[allow(clippy::all)]")?;
@@ -94,8 +321,20 @@ pub(crate) fn write_enums_mod<W: Write>(
         .collect();
     enums.dedup();
 
-    for ty in enums {
-        write_enum(&mut file, ty, metadata, config)?;
+    match split_dir {
+        Some(dir) => crate::write_sections(
+            file,
+            dir,
+            "enums",
+            enums.into_iter(),
+            |ty| rustifier::types::section_name(ty),
+            |out, ty| write_enum(out, ty, metadata, config),
+        )?,
+        None => {
+            for ty in enums {
+                write_enum(&mut file, ty, metadata, config)?;
+            }
+        }
     }
 
     // End outermost mod
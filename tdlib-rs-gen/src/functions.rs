@@ -14,7 +14,53 @@ use crate::metadata::Metadata;
 use crate::rustifier;
 use crate::GeneratorConfig;
 use std::io::{self, Write};
-use tdlib_rs_parser::tl::{Category, Definition};
+use std::path::Path;
+use tdlib_rs_parser::tl::{Category, Definition, Parameter};
+
+/// Writes the parameters and the return type of the function corresponding to
+/// the definition:
+///
+/// ```ignore
+/// (field: Type, client_id: i32) -> Result<Return, crate::TdError>
+/// ```
+fn write_signature<W: Write>(
+    file: &mut W,
+    def: &Definition,
+    config: &GeneratorConfig,
+) -> io::Result<()> {
+    write!(file, "(")?;
+    for param in def.params.iter() {
+        if rustifier::parameters::is_for_bots_only(param) && !config.gen_bots_only_api {
+            continue;
+        }
+
+        write!(file, "{}: ", rustifier::parameters::attr_name(param))?;
+
+        let is_optional = rustifier::parameters::is_optional(param);
+        let vector_item = rustifier::parameters::vector_item_qual_name(param, config);
+        if let Some(item) = vector_item {
+            write!(file, "impl IntoIterator<Item = {item}>")?;
+        } else {
+            if is_optional {
+                write!(file, "Option<")?;
+            } else if rustifier::parameters::is_string(param) {
+                write!(file, "impl Into<")?;
+            }
+            write!(file, "{}", rustifier::parameters::qual_name(param, config))?;
+            if is_optional || rustifier::parameters::is_string(param) {
+                write!(file, ">")?;
+            }
+        }
+
+        write!(file, ", ")?;
+    }
+
+    write!(
+        file,
+        "client_id: i32) -> Result<{}, crate::TdError>",
+        rustifier::types::qual_name(&def.ty, false, config)
+    )
+}
 
 /// Defines the `function` corresponding to the definition:
 ///
@@ -26,7 +72,7 @@ use tdlib_rs_parser::tl::{Category, Definition};
 fn write_function<W: Write>(
     file: &mut W,
     def: &Definition,
-    _metadata: &Metadata,
+    metadata: &Metadata,
     config: &GeneratorConfig,
 ) -> io::Result<()> {
     if rustifier::definitions::is_for_bots_only(def) && !config.gen_bots_only_api {
@@ -35,6 +81,8 @@ fn write_function<W: Write>(
 
     // Documentation
     writeln!(file, "{}", rustifier::definitions::description(def, "    "))?;
+    writeln!(file, "    ///")?;
+    writeln!(file, "{}", rustifier::definitions::doc_link(def, "    "))?;
     writeln!(file, "    /// # Arguments")?;
     for param in def.params.iter() {
         if rustifier::parameters::is_for_bots_only(param) && !config.gen_bots_only_api {
@@ -54,69 +102,143 @@ fn write_function<W: Write>(
     )?;
 
     // Function
+    if let Some(feature) = metadata.def_api_group(def) {
+        writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+    }
+    if let Some(note) = rustifier::definitions::deprecation_note(def) {
+        writeln!(file, "    #[deprecated(note = {note:?})]")?;
+    }
     writeln!(file, "    #[allow(clippy::too_many_arguments)]")?;
     write!(
         file,
-        "    pub async fn {}(",
+        "    pub async fn {}",
         rustifier::definitions::function_name(def)
     )?;
+    write_signature(file, def, config)?;
+    writeln!(file, " {{")?;
+
+    // Convert the parameters taken as `impl Into<...>` or `impl IntoIterator<...>`
     for param in def.params.iter() {
         if rustifier::parameters::is_for_bots_only(param) && !config.gen_bots_only_api {
             continue;
         }
 
-        write!(file, "{}: ", rustifier::parameters::attr_name(param))?;
+        if rustifier::parameters::is_string(param) {
+            writeln!(
+                file,
+                "        let {0}: {1} = {0}.into();",
+                rustifier::parameters::attr_name(param),
+                rustifier::parameters::qual_name(param, config),
+            )?;
+        } else if rustifier::parameters::vector_item_qual_name(param, config).is_some() {
+            writeln!(
+                file,
+                "        let {0}: {1} = {0}.into_iter().collect();",
+                rustifier::parameters::attr_name(param),
+                rustifier::parameters::qual_name(param, config),
+            )?;
+        }
+    }
 
+    // Wait for the rate limits before sending messages
+    if config.gen_rate_limits {
+        if let Some(count) = rate_limited_count(def) {
+            writeln!(
+                file,
+                "        crate::rate_limit::acquire(chat_id, {count}, client_id).await;"
+            )?;
+        }
+    }
+
+    // Compose the request, serialized straight to a string by `send_request`
+    let params = def
+        .params
+        .iter()
+        .filter(|p| !rustifier::parameters::is_for_bots_only(p) || config.gen_bots_only_api)
+        .collect::<Vec<_>>();
+    // Binary data and dates must be sent to TDLib as base64 strings and Unix
+    // timestamps respectively
+    let serde_as = |param: &Parameter| {
+        rustifier::parameters::needs_serde_as_in_request(param, config)
+            .then(|| rustifier::parameters::serde_as(param, config).unwrap())
+    };
+    if params.iter().any(|p| serde_as(p).is_some()) {
+        writeln!(file, "        #[serde_with::serde_as]")?;
+    }
+    writeln!(file, "        #[derive(serde::Serialize)]")?;
+    writeln!(
+        file,
+        "        #[serde(tag = \"@type\", rename = \"{}\")]",
+        def.name
+    )?;
+    writeln!(file, "        struct Request {{")?;
+    for param in params.iter() {
+        let attr_name = rustifier::parameters::attr_name(param);
+        if attr_name.trim_start_matches("r#") != param.name {
+            writeln!(file, "            #[serde(rename = \"{}\")]", param.name)?;
+        }
+        if let Some(serde_as) = serde_as(param) {
+            writeln!(file, "            #[serde_as(as = \"{serde_as}\")]")?;
+        }
+
+        // Omitted optional parameters must not be sent as `null`
         let is_optional = rustifier::parameters::is_optional(param);
+        if is_optional {
+            writeln!(
+                file,
+                "            #[serde(skip_serializing_if = \"Option::is_none\")]"
+            )?;
+        }
+        write!(file, "            {attr_name}: ")?;
         if is_optional {
             write!(file, "Option<")?;
         }
-        write!(
-            file,
-            "{}",
-            rustifier::parameters::qual_name(param, config.use_shared_string)
-        )?;
+        write!(file, "{}", rustifier::parameters::qual_name(param, config))?;
         if is_optional {
             write!(file, ">")?;
         }
+        writeln!(file, ",")?;
+    }
+    writeln!(file, "        }}")?;
 
-        write!(file, ", ")?;
+    let wiped = params
+        .iter()
+        .filter(|p| is_wiped(p, config))
+        .collect::<Vec<_>>();
+    if wiped.is_empty() {
+        write!(file, "        let request = Request {{ ")?;
+    } else {
+        write!(file, "        let mut request = Request {{ ")?;
     }
+    for param in params.iter() {
+        write!(file, "{}, ", rustifier::parameters::attr_name(param))?;
+    }
+    writeln!(file, "}};")?;
 
+    // Send request, wipe the secrets, which are no longer needed, and
+    // deserialize response. The serialized request still holds a copy of the
+    // secrets, which isn't wiped
     writeln!(
         file,
-        "client_id: i32) -> Result<{}, crate::TdError> {{",
-        rustifier::types::qual_name(&def.ty, false, config.use_shared_string)
+        "        let response = send_request(client_id, &request);"
     )?;
-
-    // Compose request
-    writeln!(file, "        let request = json!({{")?;
-    writeln!(file, "            \"@type\": \"{}\",", def.name)?;
-    for param in def.params.iter() {
-        if rustifier::parameters::is_for_bots_only(param) && !config.gen_bots_only_api {
-            continue;
-        }
-
+    for param in wiped {
         writeln!(
             file,
-            "            \"{0}\": {1},",
-            param.name,
+            "        zeroize::Zeroize::zeroize(&mut request.{});",
             rustifier::parameters::attr_name(param),
         )?;
     }
-    writeln!(file, "        }});")?;
-
-    // Send request and deserialize response
-    writeln!(
-        file,
-        "        let response = send_request(client_id, request).await;"
-    )?;
+    writeln!(file, "        let response = response.await;")?;
 
     let return_type_name = rustifier::definitions::type_name(def);
     if rustifier::types::is_ok(&def.ty) {
         // For () return types, only check for API errors
         writeln!(file, "        if let Ok(api_error) = serde_json::from_str::<crate::types::Error>(&response) {{")?;
-        writeln!(file, "            return Err(crate::TdError::Api(api_error));")?;
+        writeln!(
+            file,
+            "            return Err(crate::TdError::Api(api_error));"
+        )?;
         writeln!(file, "        }}")?;
         writeln!(file, "        Ok(())")?;
     } else {
@@ -125,7 +247,10 @@ fn write_function<W: Write>(
         writeln!(file, "            Ok(result) => Ok(result),")?;
         writeln!(file, "            Err(e) => {{")?;
         writeln!(file, "                if let Ok(api_error) = serde_json::from_str::<crate::types::Error>(&response) {{")?;
-        writeln!(file, "                    Err(crate::TdError::Api(api_error))")?;
+        writeln!(
+            file,
+            "                    Err(crate::TdError::Api(api_error))"
+        )?;
         writeln!(file, "                }} else {{")?;
         writeln!(file, "                    Err(crate::TdError::Deserialization {{ expected_type: \"{return_type_name}\", payload: response, error: e }})")?;
         writeln!(file, "                }}")?;
@@ -137,6 +262,41 @@ fn write_function<W: Write>(
     Ok(())
 }
 
+/// The functions sending messages to the chat `chat_id`, with the vector
+/// parameter holding the messages for the ones sending several at once.
+const RATE_LIMITED_FUNCTIONS: [(&str, Option<&str>); 7] = [
+    ("sendMessage", None),
+    ("sendMessageAlbum", Some("input_message_contents")),
+    ("sendInlineQueryResultMessage", None),
+    ("sendBotStartMessage", None),
+    ("sendQuickReplyShortcutMessages", None),
+    ("forwardMessages", Some("message_ids")),
+    ("resendMessages", Some("message_ids")),
+];
+
+/// Returns the expression counting the messages sent by the function, if it
+/// sends messages to a chat.
+fn rate_limited_count(def: &Definition) -> Option<String> {
+    let (_, messages) = RATE_LIMITED_FUNCTIONS
+        .iter()
+        .find(|(name, _)| *name == def.name)?;
+    if !def.params.iter().any(|param| param.name == "chat_id") {
+        return None;
+    }
+    match messages.and_then(|name| def.params.iter().find(|param| param.name == name)) {
+        Some(param) => Some(format!("{}.len()", rustifier::parameters::attr_name(param))),
+        None => Some("1".into()),
+    }
+}
+
+/// Returns whether the parameter is a secret string to wipe once the request
+/// has been sent.
+fn is_wiped(param: &Parameter, config: &GeneratorConfig) -> bool {
+    config.derive_zeroize
+        && rustifier::parameters::is_string(param)
+        && rustifier::parameters::is_secret(param)
+}
+
 /// Writes an entire definition as Rust code (`fn`).
 fn write_definition<W: Write>(
     file: &mut W,
@@ -149,16 +309,19 @@ fn write_definition<W: Write>(
 }
 
 /// Write the entire module dedicated to functions.
+///
+/// When `split_dir` is set, the functions are written in one file per section
+/// inside `split_dir/functions`, and the module only includes them.
 pub(crate) fn write_functions_mod<W: Write>(
     mut file: &mut W,
     definitions: &[Definition],
     metadata: &Metadata,
     config: &GeneratorConfig,
+    split_dir: Option<&Path>,
 ) -> io::Result<()> {
     // Begin outermost mod
     writeln!(file, "#[allow(clippy::all)]")?;
     writeln!(file, "pub mod functions {{")?;
-    writeln!(file, "    use serde_json::json;")?;
     writeln!(file, "    use crate::send_request;")?;
     if config.use_shared_string {
         writeln!(file, "    use crate::TdString;")?;
@@ -168,8 +331,118 @@ pub(crate) fn write_functions_mod<W: Write>(
         .iter()
         .filter(|d| d.category == Category::Functions);
 
-    for definition in functions {
-        write_definition(&mut file, definition, metadata, config)?;
+    match split_dir {
+        Some(dir) => crate::write_sections(
+            file,
+            dir,
+            "functions",
+            functions,
+            |d| rustifier::definitions::function_section_name(d),
+            |out, d| write_definition(out, d, metadata, config),
+        )?,
+        None => {
+            for definition in functions {
+                write_definition(&mut file, definition, metadata, config)?;
+            }
+        }
+    }
+
+    // End outermost mod
+    writeln!(file, "}}")
+}
+
+/// Defines the blocking version of the `function` corresponding to the
+/// definition:
+///
+/// ```ignore
+/// pub fn name(field: Type, client_id: i32) -> Result {
+///     block_on(crate::functions::name(field, client_id))
+/// }
+/// ```
+fn write_blocking_function<W: Write>(
+    file: &mut W,
+    def: &Definition,
+    metadata: &Metadata,
+    config: &GeneratorConfig,
+) -> io::Result<()> {
+    if rustifier::definitions::is_for_bots_only(def) && !config.gen_bots_only_api {
+        return Ok(());
+    }
+
+    let name = rustifier::definitions::function_name(def);
+
+    // Documentation
+    writeln!(file, "{}", rustifier::definitions::description(def, "    "))?;
+    writeln!(file, "    ///")?;
+    writeln!(
+        file,
+        "    /// The blocking version of [`functions::{name}`](crate::functions::{name})."
+    )?;
+
+    // Function
+    if let Some(feature) = metadata.def_api_group(def) {
+        writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+    }
+    if let Some(note) = rustifier::definitions::deprecation_note(def) {
+        writeln!(file, "    #[deprecated(note = {note:?})]")?;
+        writeln!(file, "    #[allow(deprecated)]")?;
+    }
+    writeln!(file, "    #[allow(clippy::too_many_arguments)]")?;
+    write!(file, "    pub fn {name}")?;
+    write_signature(file, def, config)?;
+    writeln!(file, " {{")?;
+
+    write!(file, "        block_on(crate::functions::{name}(")?;
+    for param in def.params.iter() {
+        if rustifier::parameters::is_for_bots_only(param) && !config.gen_bots_only_api {
+            continue;
+        }
+
+        write!(file, "{}, ", rustifier::parameters::attr_name(param))?;
+    }
+    writeln!(file, "client_id))")?;
+
+    writeln!(file, "    }}")?;
+    Ok(())
+}
+
+/// Write the module dedicated to the blocking versions of the functions.
+///
+/// When `split_dir` is set, the functions are written in one file per section
+/// inside `split_dir/blocking`, and the module only includes them.
+pub(crate) fn write_blocking_mod<W: Write>(
+    mut file: &mut W,
+    definitions: &[Definition],
+    metadata: &Metadata,
+    config: &GeneratorConfig,
+    split_dir: Option<&Path>,
+) -> io::Result<()> {
+    // Begin outermost mod
+    writeln!(file, "#[allow(clippy::all)]")?;
+    writeln!(file, "pub mod blocking {{")?;
+    writeln!(file, "    use crate::block_on;")?;
+    if config.use_shared_string {
+        writeln!(file, "    use crate::TdString;")?;
+    }
+
+    let functions = definitions
+        .iter()
+        .filter(|d| d.category == Category::Functions);
+
+    match split_dir {
+        Some(dir) => crate::write_sections(
+            file,
+            dir,
+            "blocking",
+            functions,
+            |d| rustifier::definitions::function_section_name(d),
+            |out, d| write_blocking_function(out, d, metadata, config),
+        )?,
+        None => {
+            for definition in functions {
+                write_blocking_function(&mut file, definition, metadata, config)?;
+            }
+        }
     }
 
     // End outermost mod
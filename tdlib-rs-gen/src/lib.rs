@@ -10,13 +10,18 @@
 
 //! This module gathers all the code generation submodules and coordinates
 //! them, feeding them the right data.
+pub mod diff;
 mod enums;
 mod functions;
 mod metadata;
+mod roundtrip;
 mod rustifier;
 mod types;
 
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
 use std::io::{self, Write};
+use std::path::Path;
 use tdlib_rs_parser::tl::{Definition, Type};
 
 /// Don't generate types for definitions of this type,
@@ -27,13 +32,96 @@ fn ignore_type(ty: &Type) -> bool {
     SPECIAL_CASED_TYPES.iter().any(|&x| x == ty.name)
 }
 
+/// The Rust type used for the points in time (Unix timestamps) of the API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnixTimeType {
+    /// `time::OffsetDateTime`, from the `time` crate.
+    Time,
+    /// `chrono::DateTime<chrono::Utc>`, from the `chrono` crate.
+    Chrono,
+}
+
+impl UnixTimeType {
+    fn qual_name(self) -> &'static str {
+        match self {
+            Self::Time => "time::OffsetDateTime",
+            Self::Chrono => "chrono::DateTime<chrono::Utc>",
+        }
+    }
+
+    fn implements_default(self) -> bool {
+        match self {
+            Self::Time => false,
+            Self::Chrono => true,
+        }
+    }
+}
+
 /// Configuration options for code generation.
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GeneratorConfig {
     /// Generate bot-only API functions.
     pub gen_bots_only_api: bool,
-    /// Use gpui::SharedString instead of String for string types.
+    /// Use the `TdString` alias instead of String for string types, letting the
+    /// crate pick the string backend (e.g. `gpui::SharedString` or `Cow<'static, str>`).
     pub use_shared_string: bool,
+    /// Box enum variants whose payload is estimated to be larger than this
+    /// many bytes. `Some(0)` boxes every struct payload, while `None` only
+    /// boxes the variants that are recursive.
+    pub box_variants_threshold: Option<usize>,
+    /// Gate the heavy, rarely-used areas of the API (calls, passport,
+    /// payments, stories and business) behind `#[cfg(feature = "...")]`.
+    pub gate_api_groups: bool,
+    /// Add an `Unrecognized` fallback variant to every enum, so that an unknown
+    /// constructor doesn't fail the deserialization of the containing type.
+    pub gen_unrecognized_variants: bool,
+    /// Use `bytes::Bytes` instead of `String` for the binary fields, which are
+    /// (de)serialized from and to base64 strings.
+    pub use_bytes: bool,
+    /// Use `std::path::PathBuf` instead of `String` for the local file and
+    /// directory paths (e.g. `database_directory`).
+    pub use_path_buf: bool,
+    /// Use the given date-time type instead of `i32` for the points in time
+    /// documented as Unix timestamps (e.g. `Message::date`).
+    pub unix_time_type: Option<UnixTimeType>,
+    /// Use `std::time::Duration` instead of `i32` or `f64` for the amounts of
+    /// time documented as seconds (e.g. `mute_for` or `auto_delete_time`).
+    pub use_durations: bool,
+    /// Implement `Debug` by hand for the types with binary fields, printing
+    /// only the length of the ones longer than this many bytes (e.g. the
+    /// data of a `Minithumbnail`).
+    pub debug_bytes_limit: Option<usize>,
+    /// Implement `Debug` by hand for the types with sensitive fields (e.g.
+    /// passwords, tokens or phone numbers), printing `<redacted>` instead.
+    pub redact_sensitive_fields: bool,
+    /// Derive `Zeroize` and `ZeroizeOnDrop` for the types holding secrets
+    /// (e.g. passwords or encryption keys), and wipe the secret parameters of
+    /// the functions once the request has been serialized. The JSON of the
+    /// request itself isn't wiped.
+    pub derive_zeroize: bool,
+    /// Derive `schemars::JsonSchema` for the types and enums.
+    pub derive_json_schema: bool,
+    /// Derive `arbitrary::Arbitrary` for the types and enums, to generate
+    /// random values in fuzz and property-based tests.
+    pub derive_arbitrary: bool,
+    /// Emit a `#[cfg(test)]` module serializing and deserializing back the
+    /// default value of every type and enum variant, which requires
+    /// `serde_json` as a dependency or dev-dependency of the crate.
+    pub gen_roundtrip_tests: bool,
+    /// The version of TDLib whose schema is generated, emitted as the
+    /// `TDLIB_API_VERSION` constant.
+    pub api_version: Option<String>,
+    /// Emit a `blocking` module with a synchronous version of every function,
+    /// waiting for the response with the `crate::block_on` function.
+    pub gen_blocking_functions: bool,
+    /// Implement `TryFrom<enums::Update>` and `TryFrom<&enums::Update>` for
+    /// the types of the updates (e.g. `types::UpdateNewMessage`), to match an
+    /// update by its type, along with the `Update::chat_id` and
+    /// `Update::message` accessors of the fields shared by many updates.
+    pub gen_update_conversions: bool,
+    /// Wait for the `crate::rate_limit::acquire` function in the functions
+    /// sending messages (e.g. `sendMessage`), before sending the request.
+    pub gen_rate_limits: bool,
 }
 
 pub fn generate_rust_code(
@@ -46,16 +134,25 @@ pub fn generate_rust_code(
         definitions,
         GeneratorConfig {
             gen_bots_only_api,
-            use_shared_string: false,
+            ..Default::default()
         },
     )
 }
 
-pub fn generate_rust_code_with_config(
-    file: &mut impl Write,
-    definitions: &[Definition],
-    config: GeneratorConfig,
-) -> io::Result<()> {
+/// Write the constants describing the generated schema.
+fn write_constants(file: &mut impl Write, config: &GeneratorConfig) -> io::Result<()> {
+    if let Some(version) = &config.api_version {
+        writeln!(
+            file,
+            "/// The version of TDLib whose schema the bindings were generated from."
+        )?;
+        writeln!(file, "pub const TDLIB_API_VERSION: &str = {version:?};")?;
+    }
+    Ok(())
+}
+
+/// Write the license header shared by every generated root file.
+fn write_header(file: &mut impl Write) -> io::Result<()> {
     write!(
         file,
         "\
@@ -69,12 +166,323 @@ pub fn generate_rust_code_with_config(
          // option. This file may not be copied, modified, or distributed\n\
          // except according to those terms.\n\
          "
-    )?;
+    )
+}
+
+/// Write the generated `code` to `file`, formatted with `prettyplease` when
+/// the feature of the same name is enabled.
+fn write_formatted(file: &mut impl Write, code: Vec<u8>) -> io::Result<()> {
+    #[cfg(feature = "prettyplease")]
+    {
+        let code =
+            String::from_utf8(code).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let syntax =
+            syn::parse_file(&code).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        file.write_all(prettyplease::unparse(&syntax).as_bytes())
+    }
+    #[cfg(not(feature = "prettyplease"))]
+    file.write_all(&code)
+}
 
-    let metadata = metadata::Metadata::new(definitions);
-    types::write_types_mod(file, definitions, &metadata, &config)?;
-    enums::write_enums_mod(file, definitions, &metadata, &config)?;
-    functions::write_functions_mod(file, definitions, &metadata, &config)?;
+pub fn generate_rust_code_with_config(
+    file: &mut impl Write,
+    definitions: &[Definition],
+    config: GeneratorConfig,
+) -> io::Result<()> {
+    write_header(file)?;
+
+    let mut code = Vec::new();
+    write_constants(&mut code, &config)?;
+
+    let metadata = metadata::Metadata::new(definitions, &config);
+    types::write_types_mod(&mut code, definitions, &metadata, &config, None)?;
+    enums::write_enums_mod(&mut code, definitions, &metadata, &config, None)?;
+    functions::write_functions_mod(&mut code, definitions, &metadata, &config, None)?;
+    if config.gen_blocking_functions {
+        functions::write_blocking_mod(&mut code, definitions, &metadata, &config, None)?;
+    }
+    if config.gen_roundtrip_tests {
+        roundtrip::write_roundtrip_tests_mod(&mut code, definitions, &metadata, &config)?;
+    }
+
+    write_formatted(file, code)
+}
+
+/// Generates the code into `out_dir`, splitting the `types`, `enums` and
+/// `functions` modules, and the `blocking` one if any, into one file per TL
+/// section (e.g. `types/chat.rs`).
+///
+/// The sections are included by the small root module written to
+/// `out_dir/generated.rs`, which is the only file that has to be included
+/// by the crate. The `types`, `enums`, `functions` and `blocking` directories
+/// inside of `out_dir` are owned by the generator, which removes the stale
+/// files.
+///
+/// The output only depends on the definitions and the configuration, and the
+/// files whose content didn't change are not rewritten, so that regenerating
+/// the same code doesn't invalidate the incremental builds of the crate.
+pub fn generate_rust_code_split(
+    out_dir: &Path,
+    definitions: &[Definition],
+    config: GeneratorConfig,
+) -> io::Result<()> {
+    let mut code = Vec::new();
+    write_constants(&mut code, &config)?;
+
+    let metadata = metadata::Metadata::new(definitions, &config);
+    let split_dir = Some(out_dir);
+    types::write_types_mod(&mut code, definitions, &metadata, &config, split_dir)?;
+    enums::write_enums_mod(&mut code, definitions, &metadata, &config, split_dir)?;
+    functions::write_functions_mod(&mut code, definitions, &metadata, &config, split_dir)?;
+    if config.gen_blocking_functions {
+        functions::write_blocking_mod(&mut code, definitions, &metadata, &config, split_dir)?;
+    }
+    if config.gen_roundtrip_tests {
+        roundtrip::write_roundtrip_tests_mod(&mut code, definitions, &metadata, &config)?;
+    }
+
+    let mut file = Vec::new();
+    write_header(&mut file)?;
+    write_formatted(&mut file, code)?;
+    write_if_changed(&out_dir.join("generated.rs"), &file)
+}
+
+/// Writes `contents` to the file at `path`, unless it already holds them, to
+/// preserve its modification time.
+fn write_if_changed(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if fs::read(path).is_ok_and(|current| current == contents) {
+        return Ok(());
+    }
+    fs::write(path, contents)
+}
+
+/// Writes the `items` of a module into one file per section inside of
+/// `dir/module`, and includes each of those files from `file`.
+fn write_sections<T, W: Write>(
+    file: &mut W,
+    dir: &Path,
+    module: &str,
+    items: impl Iterator<Item = T>,
+    section_name: impl Fn(&T) -> String,
+    mut write_item: impl FnMut(&mut Vec<u8>, T) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut sections = BTreeMap::<String, Vec<T>>::new();
+    for item in items {
+        sections.entry(section_name(&item)).or_default().push(item);
+    }
+
+    let module_dir = dir.join(module);
+    fs::create_dir_all(&module_dir)?;
+
+    let mut written = HashSet::new();
+    for (section, items) in sections {
+        let mut code = Vec::new();
+        for item in items {
+            write_item(&mut code, item)?;
+        }
+        let mut section_file = Vec::new();
+        write_formatted(&mut section_file, code)?;
+        let file_name = format!("{section}.rs");
+        write_if_changed(&module_dir.join(&file_name), &section_file)?;
+        written.insert(file_name);
+
+        writeln!(file, "    include!(\"{module}/{section}.rs\");")?;
+    }
+
+    // Remove the sections that are no longer generated
+    for entry in fs::read_dir(&module_dir)? {
+        let entry = entry?;
+        if !written.contains(entry.file_name().to_string_lossy().as_ref()) {
+            fs::remove_file(entry.path())?;
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tdlib_rs_parser::parse_tl_file;
+
+    /// Generate the code of the definitions of `schema` with `config`,
+    /// without whitespace, since the code may be formatted.
+    fn generate(schema: &str, config: GeneratorConfig) -> String {
+        let definitions: Vec<Definition> =
+            parse_tl_file(schema.into()).map(Result::unwrap).collect();
+        let mut file = Vec::new();
+        generate_rust_code_with_config(&mut file, &definitions, config).unwrap();
+        String::from_utf8(file)
+            .unwrap()
+            .split_whitespace()
+            .collect()
+    }
+
+    /// Whether the generated `code` contains `expected`, whose whitespace is
+    /// ignored.
+    fn contains(code: &str, expected: &str) -> bool {
+        code.contains(&expected.replace(' ', ""))
+    }
+
+    #[test]
+    fn check_deterministic_output() {
+        let definitions: Vec<Definition> = parse_tl_file(
+            "
+            storyInfo story_id:int32 = StoryInfo;
+            callId id:int32 = CallId;
+            message id:int53 story:storyInfo = Message;
+            updateStory story:storyInfo = Update;
+            updateCall id:callId = Update;
+            ---functions---
+            getStory story_id:int32 = StoryInfo;
+            "
+            .into(),
+        )
+        .map(Result::unwrap)
+        .collect();
+        let config = GeneratorConfig {
+            gate_api_groups: true,
+            box_variants_threshold: Some(0),
+            gen_roundtrip_tests: true,
+            ..Default::default()
+        };
+
+        // Every run uses hash maps with different random keys
+        let generate = || {
+            let mut file = Vec::new();
+            generate_rust_code_with_config(&mut file, &definitions, config.clone()).unwrap();
+            file
+        };
+        let first = generate();
+        for _ in 0..8 {
+            assert!(generate() == first);
+        }
+    }
+
+    #[test]
+    fn check_blocking_functions() {
+        let code = generate(
+            "
+            storyInfo story_id:int32 = StoryInfo;
+            ---functions---
+            getStory story_id:int32 = StoryInfo;
+            setStoryCaption story_id:int32 caption:string = Ok;
+            ",
+            GeneratorConfig {
+                gen_blocking_functions: true,
+                ..Default::default()
+            },
+        );
+        assert!(contains(&code, "pub mod blocking {"));
+        assert!(contains(
+            &code,
+            "pub fn get_story(story_id: i32, client_id: i32"
+        ));
+        assert!(contains(
+            &code,
+            "-> Result<crate::enums::StoryInfo, crate::TdError> { \
+             block_on(crate::functions::get_story(story_id, client_id))"
+        ));
+        assert!(contains(
+            &code,
+            "block_on(crate::functions::set_story_caption(story_id, caption, client_id))"
+        ));
+    }
+
+    #[test]
+    fn check_update_conversions() {
+        let code = generate(
+            "
+            storyInfo story_id:int32 = StoryInfo;
+            updateStory story:storyInfo = Update;
+            updateStoryDeleted story_id:int32 = Update;
+            ",
+            GeneratorConfig {
+                box_variants_threshold: Some(0),
+                gen_update_conversions: true,
+                ..Default::default()
+            },
+        );
+        assert!(contains(
+            &code,
+            "impl TryFrom<Update> for crate::types::UpdateStory {"
+        ));
+        assert!(contains(&code, "Update::Story(update) => Ok(*update),"));
+        assert!(contains(
+            &code,
+            "impl<'a> TryFrom<&'a Update> for &'a crate::types::UpdateStoryDeleted {"
+        ));
+        assert!(contains(
+            &code,
+            "Update::StoryDeleted(update) => Ok(&**update),"
+        ));
+        assert!(!contains(&code, "for crate::types::StoryInfo"));
+    }
+
+    #[test]
+    fn check_update_accessors() {
+        let code = generate(
+            "
+            message id:int53 chat_id:int53 = Message;
+            chat id:int53 = Chat;
+            updateNewMessage message:message = Update;
+            updateNewChat chat:chat = Update;
+            updateChatTitle chat_id:int53 title:string = Update;
+            updateUser user_id:int53 = Update;
+            ",
+            GeneratorConfig {
+                gen_update_conversions: true,
+                ..Default::default()
+            },
+        );
+        assert!(contains(&code, "pub fn chat_id(&self) -> Option<i64> {"));
+        assert!(contains(
+            &code,
+            "Update::NewMessage(update) => Some(update.message.chat_id),"
+        ));
+        assert!(contains(
+            &code,
+            "Update::NewChat(update) => Some(update.chat.id),"
+        ));
+        assert!(contains(
+            &code,
+            "Update::ChatTitle(update) => Some(update.chat_id),"
+        ));
+        assert!(contains(
+            &code,
+            "pub fn message(&self) -> Option<&crate::types::Message> {"
+        ));
+        assert!(contains(
+            &code,
+            "Update::NewMessage(update) => Some(&update.message),"
+        ));
+        assert!(!contains(&code, "Update::User(update) => Some"));
+    }
+
+    #[test]
+    fn check_rate_limits() {
+        let code = generate(
+            "
+            message id:int53 = Message;
+            messages messages:vector<message> = Messages;
+            ---functions---
+            sendMessage chat_id:int53 text:string = Message;
+            forwardMessages chat_id:int53 from_chat_id:int53 message_ids:vector<int53> = Messages;
+            getMessage chat_id:int53 message_id:int53 = Message;
+            ",
+            GeneratorConfig {
+                gen_rate_limits: true,
+                ..Default::default()
+            },
+        );
+        assert!(contains(
+            &code,
+            "crate::rate_limit::acquire(chat_id, 1, client_id).await;"
+        ));
+        assert!(contains(
+            &code,
+            "crate::rate_limit::acquire(chat_id, message_ids.len(), client_id).await;"
+        ));
+        assert_eq!(code.matches("rate_limit::acquire").count(), 2);
+    }
+}
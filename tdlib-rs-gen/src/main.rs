@@ -0,0 +1,293 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `tdlib-rs-gen` command, generating the bindings from a `td_api.tl`
+//! file, e.g. the one of a TDLib fork or of a newer TDLib version.
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tdlib_rs_gen::diff::diff_definitions;
+use tdlib_rs_gen::{
+    generate_rust_code_split, generate_rust_code_with_config, GeneratorConfig, UnixTimeType,
+};
+use tdlib_rs_parser::parse_tl_file;
+use tdlib_rs_parser::tl::Definition;
+
+const USAGE: &str = "\
+Usage: tdlib-rs-gen --schema <FILE>... --out <PATH> [--features <FEATURES>]
+                    [--api-version <VERSION>]
+       tdlib-rs-gen diff <OLD> <NEW>
+
+Generate the Rust bindings of the TDLib API described by the schema files, or
+list the added, removed and renamed functions, constructors and parameters
+between two versions of a schema, marking the ones breaking the bindings.
+
+Options:
+  --schema <FILE>        A TL schema file (e.g. td_api.tl), can be repeated
+  --out <PATH>           The output file, or a directory to split the code
+                         into one file per section, included by
+                         <PATH>/generated.rs
+  --features <FEATURES>  A comma-separated list of generation features
+  --api-version <VERSION>
+                         The TDLib version of the schema, emitted as the
+                         `TDLIB_API_VERSION` constant
+  -h, --help             Print this help
+
+Features:
+  bots                   Generate the bot-only API
+  shared-string          Use the `crate::TdString` alias for strings
+  box-large-variants     Box the enum variants larger than 128 bytes
+  api-groups             Gate calls, passport, payments, stories and business
+                         behind the cargo features of the same name
+  unrecognized-variants  Add an `Unrecognized` variant to every enum
+  bytes                  Use `bytes::Bytes` for the binary fields
+  path-buf               Use `std::path::PathBuf` for the local paths
+  time, chrono           Use `time` or `chrono` for the Unix timestamps
+  duration               Use `std::time::Duration` for the amounts of seconds
+  elide-bytes            Print only the length of the large binary fields
+  redact                 Redact the sensitive fields in the `Debug` output
+  zeroize                Wipe the secrets with `zeroize`
+  schemars               Derive `schemars::JsonSchema`
+  arbitrary              Derive `arbitrary::Arbitrary`
+  roundtrip-tests        Emit the serde round-trip tests
+  blocking               Emit the `blocking` module of synchronous functions
+  update-conversions     Convert the updates to the types of their variants
+  rate-limits            Wait for the rate limits before sending messages
+
+The generated code expects the `serde`, `serde_with` and `serde_json` crates,
+the `crate::types`, `crate::enums` and `crate::functions` paths, and the
+`send_request` function of `tdlib-rs`, as well as its `block_on` function for
+the `blocking` feature and its `rate_limit` module for the `rate-limits`
+feature.
+";
+
+/// The estimated size in bytes above which enum variants are boxed by the
+/// `box-large-variants` feature, the same as `tdlib-rs`.
+const BOX_VARIANTS_THRESHOLD: usize = 128;
+
+/// The length above which binary fields are elided by the `elide-bytes`
+/// feature, the same as `tdlib-rs`.
+const DEBUG_BYTES_LIMIT: usize = 64;
+
+/// The parsed command line arguments.
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    schemas: Vec<PathBuf>,
+    out: PathBuf,
+    features: Vec<String>,
+    api_version: Option<String>,
+}
+
+/// Parse the command line arguments, returning `None` if the help was asked.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args::default();
+    let mut out = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.into())),
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for {name}"))
+        };
+        match name.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--schema" => parsed.schemas.push(value()?.into()),
+            "--out" => out = Some(value()?.into()),
+            "--api-version" => parsed.api_version = Some(value()?),
+            "--features" => parsed.features.extend(
+                value()?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .map(String::from),
+            ),
+            _ => return Err(format!("unexpected argument {name}")),
+        }
+    }
+
+    if parsed.schemas.is_empty() {
+        return Err("missing --schema".into());
+    }
+    parsed.out = out.ok_or("missing --out")?;
+    Ok(Some(parsed))
+}
+
+/// Build the generator configuration enabling the given features.
+fn config_with_features(features: &[String]) -> Result<GeneratorConfig, String> {
+    let mut config = GeneratorConfig::default();
+    for feature in features {
+        match feature.as_str() {
+            "bots" | "bots-only-api" => config.gen_bots_only_api = true,
+            "shared-string" => config.use_shared_string = true,
+            "box-large-variants" => config.box_variants_threshold = Some(BOX_VARIANTS_THRESHOLD),
+            "api-groups" => config.gate_api_groups = true,
+            "unrecognized-variants" => config.gen_unrecognized_variants = true,
+            "bytes" => config.use_bytes = true,
+            "path-buf" => config.use_path_buf = true,
+            "time" => config.unix_time_type = Some(UnixTimeType::Time),
+            "chrono" => config.unix_time_type = Some(UnixTimeType::Chrono),
+            "duration" => config.use_durations = true,
+            "elide-bytes" => config.debug_bytes_limit = Some(DEBUG_BYTES_LIMIT),
+            "redact" => config.redact_sensitive_fields = true,
+            "zeroize" => config.derive_zeroize = true,
+            "schemars" => config.derive_json_schema = true,
+            "arbitrary" => config.derive_arbitrary = true,
+            "roundtrip-tests" => config.gen_roundtrip_tests = true,
+            "blocking" => config.gen_blocking_functions = true,
+            "update-conversions" => config.gen_update_conversions = true,
+            "rate-limits" => config.gen_rate_limits = true,
+            _ => return Err(format!("unknown feature {feature}")),
+        }
+    }
+    Ok(config)
+}
+
+/// Load the definitions of a schema file, printing the parse errors to
+/// `stderr` and skipping the invalid definitions.
+fn load_tl(path: &Path) -> io::Result<Vec<Definition>> {
+    let contents = fs::read_to_string(path)?;
+    Ok(parse_tl_file(contents)
+        .filter_map(|d| match d {
+            Ok(d) => Some(d),
+            Err(e) => {
+                eprintln!("{}:{e}", path.display());
+                None
+            }
+        })
+        .collect())
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let mut config = config_with_features(&args.features)?;
+    config.api_version = args.api_version;
+
+    let mut definitions = Vec::new();
+    for schema in &args.schemas {
+        definitions.extend(load_tl(schema).map_err(|e| format!("{}: {e}", schema.display()))?);
+    }
+
+    let result = if args.out.is_dir() {
+        generate_rust_code_split(&args.out, &definitions, config)
+    } else {
+        File::create(&args.out).and_then(|file| {
+            let mut file = BufWriter::new(file);
+            generate_rust_code_with_config(&mut file, &definitions, config)?;
+            file.flush()
+        })
+    };
+    result.map_err(|e| format!("{}: {e}", args.out.display()))
+}
+
+/// Print the changes between the `old` and the `new` schema files.
+fn run_diff(old: &Path, new: &Path) -> Result<(), String> {
+    let old = load_tl(old).map_err(|e| format!("{}: {e}", old.display()))?;
+    let new = load_tl(new).map_err(|e| format!("{}: {e}", new.display()))?;
+
+    let changes = diff_definitions(&old, &new);
+    for change in &changes {
+        if change.is_breaking() {
+            println!("{change} (breaking)");
+        } else {
+            println!("{change}");
+        }
+    }
+    let breaking = changes.iter().filter(|c| c.is_breaking()).count();
+    println!("{} changes, {breaking} breaking", changes.len());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1).peekable();
+    if args.peek().map(String::as_str) == Some("diff") {
+        let (Some(old), Some(new), None) = (args.nth(1), args.next(), args.next()) else {
+            eprintln!("error: diff expects the old and the new schema files\n\n{USAGE}");
+            return ExitCode::from(2);
+        };
+        return match run_diff(Path::new(&old), Path::new(&new)) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let args = match parse_args(args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn check_parse_args() {
+        assert_eq!(
+            args(&[
+                "--schema",
+                "td_api.tl",
+                "--schema=tdlight.tl",
+                "--out",
+                "src/generated.rs",
+                "--features",
+                "bots, shared-string",
+            ]),
+            Ok(Some(Args {
+                schemas: vec!["td_api.tl".into(), "tdlight.tl".into()],
+                out: "src/generated.rs".into(),
+                features: vec!["bots".into(), "shared-string".into()],
+                api_version: None,
+            }))
+        );
+        assert_eq!(args(&["--schema", "td_api.tl", "--help"]), Ok(None));
+    }
+
+    #[test]
+    fn check_parse_args_errors() {
+        assert!(args(&["--out", "generated.rs"]).is_err());
+        assert!(args(&["--schema", "td_api.tl"]).is_err());
+        assert!(args(&["--schema"]).is_err());
+        assert!(args(&["--schema", "td_api.tl", "--out", "a", "b"]).is_err());
+    }
+
+    #[test]
+    fn check_config_with_features() {
+        let config = config_with_features(&["bots".into(), "chrono".into()]).unwrap();
+        assert!(config.gen_bots_only_api);
+        assert_eq!(config.unix_time_type, Some(UnixTimeType::Chrono));
+        assert!(config_with_features(&["unknown".into()]).is_err());
+    }
+}
@@ -9,22 +9,53 @@
 // except according to those terms.
 
 use crate::rustifier;
+use crate::GeneratorConfig;
 use std::collections::{HashMap, HashSet};
 use tdlib_rs_parser::tl::{Category, Definition, Type};
 
+/// Size in bytes of a pointer-sized value, used when estimating type sizes.
+const WORD_SIZE: usize = 8;
+
+/// Cargo features of the heavy, rarely-used areas of the API, together with
+/// the words that identify the definitions belonging to each of them.
+const API_GROUPS: [(&str, &[&str]); 5] = [
+    ("calls", &["call", "calls"]),
+    ("passport", &["passport"]),
+    ("payments", &["payment", "payments", "invoice", "shipping"]),
+    ("stories", &["story", "stories"]),
+    ("business", &["business"]),
+];
+
+/// Enums whose variants can be gated on their own, because unknown variants
+/// are simply skipped by the receive loop instead of breaking a parent type.
+const GATEABLE_VARIANT_ENUMS: [&str; 1] = ["Update"];
+
 /// Additional metadata required by several parts of the generation.
 pub(crate) struct Metadata<'a> {
     recursing_defs: HashSet<&'a String>,
+    boxed_defs: HashSet<&'a String>,
     default_impl_defs: HashSet<&'a String>,
     defs_with_type: HashMap<&'a String, Vec<&'a Definition>>,
+    api_groups: HashMap<Node<'a>, &'static str>,
+}
+
+/// A generated item that can reference or be referenced by other items.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Node<'a> {
+    /// A `struct` or a `fn`, identified by the definition name.
+    Def(&'a String),
+    /// An `enum`, identified by the type name.
+    Enum(&'a String),
 }
 
 impl<'a> Metadata<'a> {
-    pub fn new(definitions: &'a [Definition]) -> Self {
+    pub fn new(definitions: &'a [Definition], config: &GeneratorConfig) -> Self {
         let mut metadata = Self {
             recursing_defs: HashSet::new(),
+            boxed_defs: HashSet::new(),
             default_impl_defs: HashSet::new(),
             defs_with_type: HashMap::new(),
+            api_groups: HashMap::new(),
         };
 
         let type_definitions = definitions
@@ -52,11 +83,32 @@ impl<'a> Metadata<'a> {
         });
 
         type_definitions.iter().for_each(|d| {
-            if def_contains_only_bare_types(d, &type_definition_map) {
+            if def_contains_only_bare_types(d, &type_definition_map, config) {
                 metadata.default_impl_defs.insert(&d.name);
             }
         });
 
+        if let Some(threshold) = config.box_variants_threshold {
+            let mut estimator = SizeEstimator {
+                threshold,
+                type_definition_map: &type_definition_map,
+                defs_with_type: &metadata.defs_with_type,
+                recursing_defs: &metadata.recursing_defs,
+                def_sizes: HashMap::new(),
+                in_progress: HashSet::new(),
+            };
+            let boxed_defs = type_definitions
+                .iter()
+                .filter(|d| !d.params.is_empty() && estimator.def_size(d) > threshold)
+                .map(|d| &d.name)
+                .collect();
+            metadata.boxed_defs = boxed_defs;
+        }
+
+        if config.gate_api_groups {
+            metadata.api_groups = api_groups(definitions);
+        }
+
         metadata
     }
 
@@ -66,6 +118,13 @@ impl<'a> Metadata<'a> {
         self.recursing_defs.contains(&def.name)
     }
 
+    /// Returns `true` if the `Definition` must be wrapped in a `Box` when used
+    /// as an enum variant, either because it is recursive or because its
+    /// estimated size exceeds the configured threshold.
+    pub fn is_boxed_def(&self, def: &Definition) -> bool {
+        self.is_recursive_def(def) || self.boxed_defs.contains(&def.name)
+    }
+
     /// Returns `true` if the `Definition` can implement the trait `Default`
     pub fn can_def_implement_default(&self, def: &Definition) -> bool {
         self.default_impl_defs.contains(&def.name)
@@ -74,6 +133,112 @@ impl<'a> Metadata<'a> {
     pub fn defs_with_type(&self, ty: &'a Type) -> &Vec<&Definition> {
         &self.defs_with_type[&ty.name]
     }
+
+    /// Returns the cargo feature gating the `struct` or `fn` generated for the
+    /// `Definition`, if it belongs to one of the optional API groups.
+    pub fn def_api_group(&self, def: &Definition) -> Option<&'static str> {
+        self.api_groups.get(&Node::Def(&def.name)).copied()
+    }
+
+    /// Returns the cargo feature gating the `enum` generated for the `Type`,
+    /// if it belongs to one of the optional API groups.
+    pub fn type_api_group(&self, ty: &Type) -> Option<&'static str> {
+        self.api_groups.get(&Node::Enum(&ty.name)).copied()
+    }
+}
+
+/// Returns the API group of a TL name by looking at its camel case words.
+fn api_group_of_name(name: &str) -> Option<&'static str> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c.to_ascii_lowercase());
+    }
+    words.push(word);
+
+    API_GROUPS
+        .iter()
+        .find(|(_, keywords)| words.iter().any(|w| keywords.contains(&w.as_str())))
+        .map(|(feature, _)| *feature)
+}
+
+/// Returns the item generated for a type, looking through `vector`s.
+fn node_of_type(ty: &Type) -> Option<Node<'_>> {
+    if let Some(generic_arg) = &ty.generic_arg {
+        return node_of_type(generic_arg);
+    }
+    if rustifier::types::builtin_type(ty, &GeneratorConfig::default()).is_some() {
+        return None;
+    }
+    if ty.bare {
+        Some(Node::Def(&ty.name))
+    } else {
+        Some(Node::Enum(&ty.name))
+    }
+}
+
+/// Assigns the API group to every generated item that can be excluded
+/// without breaking the items that are always generated.
+///
+/// An item starts in the group of its name, and it is taken out of it if
+/// any of the items referencing it does not belong to the same group.
+fn api_groups(definitions: &[Definition]) -> HashMap<Node<'_>, &'static str> {
+    let mut groups = HashMap::new();
+    let mut referrers: HashMap<Node, Vec<Node>> = HashMap::new();
+
+    for def in definitions {
+        let def_node = Node::Def(&def.name);
+        if let Some(group) = api_group_of_name(&def.name) {
+            groups.insert(def_node, group);
+        }
+
+        for param in def.params.iter() {
+            if let Some(node) = node_of_type(&param.ty) {
+                referrers.entry(node).or_default().push(def_node);
+            }
+        }
+
+        match def.category {
+            Category::Types => {
+                let enum_node = Node::Enum(&def.ty.name);
+                if let Some(group) = api_group_of_name(&def.ty.name) {
+                    groups.insert(enum_node, group);
+                }
+                if !GATEABLE_VARIANT_ENUMS.contains(&def.ty.name.as_str()) {
+                    referrers.entry(def_node).or_default().push(enum_node);
+                }
+            }
+            Category::Functions => {
+                if let Some(node) = node_of_type(&def.ty) {
+                    referrers.entry(node).or_default().push(def_node);
+                }
+            }
+        }
+    }
+
+    loop {
+        let ungated = groups
+            .iter()
+            .filter(|(node, group)| {
+                referrers
+                    .get(*node)
+                    .into_iter()
+                    .flatten()
+                    .any(|referrer| groups.get(referrer) != Some(*group))
+            })
+            .map(|(node, _)| *node)
+            .collect::<Vec<_>>();
+
+        if ungated.is_empty() {
+            break groups;
+        }
+        for node in ungated {
+            groups.remove(&node);
+        }
+    }
 }
 
 fn def_self_references<'a>(
@@ -106,14 +271,21 @@ fn def_self_references<'a>(
 fn def_contains_only_bare_types<'a>(
     check: &'a Definition,
     definition_map: &'a HashMap<&String, &&Definition>,
+    config: &GeneratorConfig,
 ) -> bool {
     for param in check.params.iter() {
-        if !rustifier::parameters::is_builtin_type(param, false) && !param.ty.bare {
+        if !rustifier::parameters::is_builtin_type(param, &GeneratorConfig::default())
+            && !param.ty.bare
+        {
+            return false;
+        }
+
+        if !rustifier::parameters::implements_default(param, config) {
             return false;
         }
 
         if let Some(def) = definition_map.get(&param.ty.name) {
-            if !def_contains_only_bare_types(def, definition_map) {
+            if !def_contains_only_bare_types(def, definition_map, config) {
                 return false;
             }
         }
@@ -121,3 +293,165 @@ fn def_contains_only_bare_types<'a>(
 
     true
 }
+
+/// Rough estimation of the in-memory size of the generated Rust types.
+///
+/// The numbers do not need to be exact, they only have to be good enough to
+/// tell apart the small variants from the ones worth boxing.
+struct SizeEstimator<'a, 'b> {
+    threshold: usize,
+    type_definition_map: &'b HashMap<&'a String, &'b &'a Definition>,
+    defs_with_type: &'b HashMap<&'a String, Vec<&'a Definition>>,
+    recursing_defs: &'b HashSet<&'a String>,
+    def_sizes: HashMap<&'a String, usize>,
+    in_progress: HashSet<&'a String>,
+}
+
+impl<'a> SizeEstimator<'a, '_> {
+    /// Estimated size of the `struct` generated for the definition.
+    fn def_size(&mut self, def: &'a Definition) -> usize {
+        if let Some(size) = self.def_sizes.get(&def.name) {
+            return *size;
+        }
+        // A cycle means the definition is reached through a `Box` somewhere
+        if !self.in_progress.insert(&def.name) {
+            return WORD_SIZE;
+        }
+
+        let size = def
+            .params
+            .iter()
+            .map(|p| self.type_size(&p.ty))
+            .sum::<usize>()
+            .next_multiple_of(WORD_SIZE);
+
+        self.in_progress.remove(&def.name);
+        self.def_sizes.insert(&def.name, size);
+        size
+    }
+
+    fn type_size(&mut self, ty: &'a Type) -> usize {
+        match ty.name.as_ref() {
+            "Bool" => 1,
+            "int32" => 4,
+            "int53" | "int64" | "double" => WORD_SIZE,
+            "string" | "bytes" | "vector" => 3 * WORD_SIZE,
+            "Ok" => 0,
+            _ if ty.bare => match self.type_definition_map.get(&ty.name) {
+                Some(def) => self.def_size(def),
+                None => WORD_SIZE,
+            },
+            _ => self.enum_size(ty),
+        }
+    }
+
+    /// Estimated size of the `enum` generated for the type, taking into
+    /// account the variants that end up boxed.
+    fn enum_size(&mut self, ty: &'a Type) -> usize {
+        let Some(defs) = self.defs_with_type.get(&ty.name) else {
+            return WORD_SIZE;
+        };
+
+        let largest_variant = defs
+            .iter()
+            .map(|d| {
+                if d.params.is_empty() {
+                    0
+                } else if self.recursing_defs.contains(&d.name) {
+                    WORD_SIZE
+                } else {
+                    let size = self.def_size(d);
+                    if size > self.threshold {
+                        WORD_SIZE
+                    } else {
+                        size
+                    }
+                }
+            })
+            .max()
+            .unwrap_or(0);
+
+        largest_variant + WORD_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(definitions: &[&str]) -> Vec<Definition> {
+        definitions.iter().map(|d| d.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn check_api_group_of_name() {
+        assert_eq!(api_group_of_name("createCall"), Some("calls"));
+        assert_eq!(api_group_of_name("groupCallParticipant"), Some("calls"));
+        assert_eq!(api_group_of_name("callbackQueryPayload"), None);
+        assert_eq!(api_group_of_name("PassportElement"), Some("passport"));
+        assert_eq!(api_group_of_name("getStory"), Some("stories"));
+        assert_eq!(api_group_of_name("historyStatistics"), None);
+    }
+
+    #[test]
+    fn check_api_groups_keep_referenced_types() {
+        let definitions = parse(&[
+            "storyView id:int32 = StoryView",
+            "storyInfo id:int32 = StoryInfo",
+            "message story:storyInfo = Message",
+            "updateStoryView view:storyView = Update",
+        ]);
+        let config = GeneratorConfig {
+            gate_api_groups: true,
+            ..Default::default()
+        };
+        let metadata = Metadata::new(&definitions, &config);
+        assert_eq!(metadata.def_api_group(&definitions[0]), Some("stories"));
+        assert_eq!(metadata.def_api_group(&definitions[1]), None);
+        assert_eq!(metadata.def_api_group(&definitions[2]), None);
+        assert_eq!(metadata.def_api_group(&definitions[3]), Some("stories"));
+        assert_eq!(metadata.type_api_group(&definitions[3].ty), None);
+    }
+
+    #[test]
+    fn check_no_threshold_boxes_only_recursive() {
+        let definitions = parse(&[
+            "small a:int32 = Item",
+            "large a:string b:string c:string d:string = Item",
+            "nested child:Item = Item",
+        ]);
+        let metadata = Metadata::new(&definitions, &GeneratorConfig::default());
+        assert!(!metadata.is_boxed_def(&definitions[0]));
+        assert!(!metadata.is_boxed_def(&definitions[1]));
+        assert!(metadata.is_boxed_def(&definitions[2]));
+    }
+
+    #[test]
+    fn check_threshold_boxes_large_variants() {
+        let definitions = parse(&[
+            "small a:int32 = Item",
+            "large a:string b:string c:string d:string = Item",
+            "wrapper item:Item = Wrapper",
+        ]);
+        let config = GeneratorConfig {
+            box_variants_threshold: Some(64),
+            ..Default::default()
+        };
+        let metadata = Metadata::new(&definitions, &config);
+        assert!(!metadata.is_boxed_def(&definitions[0]));
+        assert!(metadata.is_boxed_def(&definitions[1]));
+        assert!(!metadata.is_boxed_def(&definitions[2]));
+    }
+
+    #[test]
+    fn check_zero_threshold_boxes_every_payload() {
+        let definitions = parse(&["empty = Item", "small a:int32 = Item"]);
+        let config = GeneratorConfig {
+            box_variants_threshold: Some(0),
+            ..Default::default()
+        };
+        let metadata = Metadata::new(&definitions, &config);
+        assert!(!metadata.is_boxed_def(&definitions[0]));
+        assert!(metadata.is_boxed_def(&definitions[1]));
+    }
+}
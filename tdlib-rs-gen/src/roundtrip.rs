@@ -0,0 +1,202 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Code to generate the tests serializing and deserializing back a sample of
+//! every generated type and enum variant.
+
+use crate::ignore_type;
+use crate::metadata::Metadata;
+use crate::rustifier;
+use crate::GeneratorConfig;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use tdlib_rs_parser::tl::{Category, Definition, Type};
+
+/// Writes the `cfg` attributes gating a statement on the given features.
+fn write_cfgs<W: Write>(file: &mut W, features: &[Option<&str>]) -> io::Result<()> {
+    let mut features: Vec<&str> = features.iter().flatten().copied().collect();
+    features.dedup();
+    for feature in features {
+        writeln!(file, "        #[cfg(feature = \"{feature}\")]")?;
+    }
+    Ok(())
+}
+
+/// Writes the test round-tripping the default value of every `struct` that
+/// implements `Default`.
+fn write_types_test<W: Write>(
+    file: &mut W,
+    definitions: &[Definition],
+    metadata: &Metadata,
+    config: &GeneratorConfig,
+) -> io::Result<()> {
+    writeln!(file, "    #[test]")?;
+    writeln!(file, "    fn roundtrip_types() {{")?;
+    for def in definitions.iter().filter(|d| {
+        d.category == Category::Types
+            && !ignore_type(&d.ty)
+            && !d.params.is_empty()
+            && metadata.can_def_implement_default(d)
+            && (!rustifier::definitions::is_for_bots_only(d) || config.gen_bots_only_api)
+    }) {
+        write_cfgs(file, &[metadata.def_api_group(def)])?;
+        writeln!(
+            file,
+            "        roundtrip({}::default());",
+            rustifier::definitions::qual_name(def)
+        )?;
+    }
+    writeln!(file, "    }}")
+}
+
+/// The name of the test round-tripping the variants of the enum `ty`, e.g.
+/// `roundtrip_chat_type` for `ChatType`.
+fn enum_test_name(ty: &Type) -> String {
+    let mut name = String::from("roundtrip");
+    for c in rustifier::types::type_name(ty).chars() {
+        if c.is_ascii_uppercase() {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+/// Writes one test per enum, round-tripping every variant without data or
+/// whose data implements `Default`.
+///
+/// The enums get a test each, since a single test round-tripping all of them
+/// overflows the stack of the test threads.
+fn write_enums_tests<W: Write>(
+    file: &mut W,
+    definitions: &[Definition],
+    metadata: &Metadata,
+    config: &GeneratorConfig,
+) -> io::Result<()> {
+    let mut enums: Vec<&Type> = definitions
+        .iter()
+        .filter(|d| d.category == Category::Types && !ignore_type(&d.ty))
+        .map(|d| &d.ty)
+        .collect();
+    let mut seen = HashSet::new();
+    enums.retain(|ty| seen.insert(&ty.name));
+
+    for ty in enums {
+        let enum_feature = metadata.type_api_group(ty);
+        let mut body = Vec::new();
+        for def in metadata.defs_with_type(ty) {
+            if rustifier::definitions::is_for_bots_only(def) && !config.gen_bots_only_api {
+                continue;
+            }
+
+            let variant = format!(
+                "crate::enums::{}::{}",
+                rustifier::types::type_name(ty),
+                rustifier::definitions::variant_name(def)
+            );
+            if def.params.is_empty() {
+                write_cfgs(&mut body, &[metadata.def_api_group(def)])?;
+                writeln!(body, "        roundtrip({variant});")?;
+            } else if metadata.can_def_implement_default(def) {
+                write_cfgs(&mut body, &[metadata.def_api_group(def)])?;
+                writeln!(body, "        roundtrip({variant}(Default::default()));")?;
+            }
+        }
+        if body.is_empty() {
+            continue;
+        }
+
+        writeln!(file)?;
+        if let Some(feature) = enum_feature {
+            writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+        }
+        writeln!(file, "    #[test]")?;
+        writeln!(file, "    fn {}() {{", enum_test_name(ty))?;
+        file.write_all(&body)?;
+        writeln!(file, "    }}")?;
+    }
+    Ok(())
+}
+
+/// Write the test module, compiled only by `cargo test`, which checks that the
+/// `serde` attributes of the generated code (renames, `serde_as` conversions and
+/// optional fields) let every value be deserialized back from its JSON.
+pub(crate) fn write_roundtrip_tests_mod<W: Write>(
+    file: &mut W,
+    definitions: &[Definition],
+    metadata: &Metadata,
+    config: &GeneratorConfig,
+) -> io::Result<()> {
+    // Begin outermost mod
+    writeln!(file, "#[cfg(test)]")?;
+    writeln!(file, "#[allow(clippy::all, deprecated)]")?;
+    writeln!(file, "mod roundtrip_tests {{")?;
+    writeln!(file, "    use serde::de::DeserializeOwned;")?;
+    writeln!(file, "    use serde::Serialize;")?;
+    writeln!(file, "    use std::fmt::Debug;")?;
+    writeln!(file)?;
+    writeln!(
+        file,
+        "    fn roundtrip<T: Debug + PartialEq + Serialize + DeserializeOwned>(value: T) {{"
+    )?;
+    writeln!(
+        file,
+        "        let json = serde_json::to_string(&value).unwrap();"
+    )?;
+    writeln!(
+        file,
+        "        let deserialized: T = serde_json::from_str(&json).unwrap_or_else(|e| {{"
+    )?;
+    writeln!(
+        file,
+        "            panic!(\"{{}} from {{json}}: {{e}}\", std::any::type_name::<T>())"
+    )?;
+    writeln!(file, "        }});")?;
+    writeln!(
+        file,
+        "        assert_eq!(value, deserialized, \"{{}} from {{json}}\", std::any::type_name::<T>());"
+    )?;
+    writeln!(file, "    }}")?;
+    writeln!(file)?;
+
+    write_types_test(file, definitions, metadata, config)?;
+    write_enums_tests(file, definitions, metadata, config)?;
+
+    // End outermost mod
+    writeln!(file, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(definitions: &[&str]) -> String {
+        let definitions: Vec<Definition> = definitions.iter().map(|d| d.parse().unwrap()).collect();
+        let config = GeneratorConfig::default();
+        let metadata = Metadata::new(&definitions, &config);
+        let mut file = Vec::new();
+        write_roundtrip_tests_mod(&mut file, &definitions, &metadata, &config).unwrap();
+        String::from_utf8(file).unwrap()
+    }
+
+    #[test]
+    fn check_roundtrip_samples() {
+        let code = generate(&[
+            "textEntity offset:int32 length:int32 = TextEntity",
+            "formattedText text:string entities:vector<textEntity> = FormattedText",
+            "chatTypePrivate user_id:int53 = ChatType",
+            "chatTypeSecret = ChatType",
+            "messageText text:formattedText = MessageContent",
+        ]);
+        assert!(code.contains("roundtrip(crate::types::FormattedText::default());"));
+        assert!(code.contains("roundtrip(crate::enums::ChatType::Private(Default::default()));"));
+        assert!(code.contains("roundtrip(crate::enums::ChatType::Secret);"));
+        assert!(code.contains("fn roundtrip_chat_type() {"));
+        assert!(code.contains("fn roundtrip_message_content() {"));
+    }
+}
@@ -19,6 +19,7 @@
 //! * `item_path` for use as a qualified item path (`Vec::<u8>`).
 //! * `attr_name` for use as an attribute name (`foo_bar: ()`).
 
+use crate::GeneratorConfig;
 use tdlib_rs_parser::tl::{Definition, Parameter, Type};
 
 /// Get the rusty type name for a certain definition, excluding namespace.
@@ -80,6 +81,46 @@ fn rusty_doc(indent: &str, doc: &str) -> String {
     )
 }
 
+/// Base URL of the official TDLib documentation of the `td_api` classes.
+const TDLIB_DOCS_URL: &str = "https://core.telegram.org/tdlib/docs/classtd_1_1td__api_1_1";
+
+/// Get the link to the official TDLib documentation of a TL name.
+///
+/// The documentation is generated by Doxygen, which escapes uppercase
+/// letters as `_` followed by the lowercase letter and doubles underscores.
+/// For example, `"sendMessage"` links to `classtd_1_1td__api_1_1send_message.html`.
+fn rusty_doc_link(indent: &str, name: &str) -> String {
+    let mut page = String::with_capacity(name.len() * 2);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            page.push('_');
+            page.push(c.to_ascii_lowercase());
+        } else if c == '_' {
+            page.push_str("__");
+        } else {
+            page.push(c);
+        }
+    }
+
+    format!("{indent}/// [TDLib documentation]({TDLIB_DOCS_URL}{page}.html)")
+}
+
+/// Get the deprecation note from a documentation string, if any.
+///
+/// A definition is considered deprecated when its documentation contains a
+/// sentence mentioning that it is deprecated or obsolete. The sentences only
+/// pointing to a better alternative (`"Use updateFoo instead"`) don't count,
+/// since TDLib uses them for supported functions too, e.g. `getChats`.
+fn rusty_deprecation(doc: &str) -> Option<String> {
+    doc.split(". ")
+        .map(|sentence| sentence.split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|sentence| {
+            let lowercase = sentence.to_ascii_lowercase();
+            lowercase.contains("deprecated") || lowercase.contains("obsolete")
+        })
+        .map(|sentence| sentence.trim_end_matches('.').to_string())
+}
+
 pub mod definitions {
     use super::*;
 
@@ -151,22 +192,42 @@ pub mod definitions {
         rusty_doc(indent, &def.description)
     }
 
+    pub fn doc_link(def: &Definition, indent: &str) -> String {
+        rusty_doc_link(indent, &def.name)
+    }
+
     pub fn is_for_bots_only(def: &Definition) -> bool {
         def.description.contains("; for bots only")
     }
+
+    pub fn deprecation_note(def: &Definition) -> Option<String> {
+        rusty_deprecation(&def.description)
+    }
+
+    /// The section a function belongs to, that is the first word after its
+    /// verb (e.g. `"chat"` for `setChatTitle`).
+    pub fn function_section_name(def: &Definition) -> String {
+        let name = function_name(def);
+        let mut words = name.split('_');
+        let verb = words.next().unwrap_or_default();
+        words.next().unwrap_or(verb).to_string()
+    }
 }
 
 pub mod types {
     use super::*;
 
     /// Returns the builtin Rust type for a TL type, if applicable.
-    /// When `use_shared_string` is true, string types map to `TdString` (a type alias
-    /// that resolves to either `String` or `gpui::SharedString` based on feature flags).
-    pub(super) fn builtin_type(ty: &Type, use_shared_string: bool) -> Option<&'static str> {
+    /// When `config.use_shared_string` is true, string types map to `TdString` (a type alias
+    /// that resolves to `String`, `gpui::SharedString`, `Cow<'static, str>` or `Arc<str>` based on feature flags).
+    /// When `config.use_bytes` is true, bytes types map to `bytes::Bytes`.
+    pub(crate) fn builtin_type(ty: &Type, config: &GeneratorConfig) -> Option<&'static str> {
         Some(match ty.name.as_ref() {
             "Bool" => "bool",
             "bytes" => {
-                if use_shared_string {
+                if config.use_bytes {
+                    "bytes::Bytes"
+                } else if config.use_shared_string {
                     "TdString"
                 } else {
                     "String"
@@ -177,7 +238,7 @@ pub mod types {
             "int53" => "i64",
             "int64" => "i64",
             "string" => {
-                if use_shared_string {
+                if config.use_shared_string {
                     "TdString"
                 } else {
                     "String"
@@ -189,8 +250,8 @@ pub mod types {
         })
     }
 
-    fn get_base_path(ty: &Type, use_shared_string: bool) -> String {
-        if let Some(name) = builtin_type(ty, use_shared_string) {
+    fn get_base_path(ty: &Type, config: &GeneratorConfig) -> String {
+        if let Some(name) = builtin_type(ty, config) {
             name.to_string()
         } else {
             let mut result = String::new();
@@ -204,8 +265,8 @@ pub mod types {
         }
     }
 
-    fn get_path(ty: &Type, optional_generic_arg: bool, use_shared_string: bool) -> String {
-        let mut result = get_base_path(ty, use_shared_string);
+    fn get_path(ty: &Type, optional_generic_arg: bool, config: &GeneratorConfig) -> String {
+        let mut result = get_base_path(ty, config);
 
         if let Some(generic_ty) = &ty.generic_arg {
             result.push('<');
@@ -213,7 +274,7 @@ pub mod types {
                 result.push_str("Option<");
             }
 
-            result.push_str(&qual_name(generic_ty, false, use_shared_string));
+            result.push_str(&qual_name(generic_ty, false, config));
 
             if optional_generic_arg {
                 result.push('>');
@@ -228,22 +289,51 @@ pub mod types {
         rusty_type_name(&ty.name)
     }
 
-    pub fn qual_name(ty: &Type, optional_generic_arg: bool, use_shared_string: bool) -> String {
-        get_path(ty, optional_generic_arg, use_shared_string)
+    pub fn qual_name(ty: &Type, optional_generic_arg: bool, config: &GeneratorConfig) -> String {
+        get_path(ty, optional_generic_arg, config)
+    }
+
+    pub fn doc_link(ty: &Type, indent: &str) -> String {
+        rusty_doc_link(indent, &ty.name)
     }
 
     pub fn is_ok(ty: &Type) -> bool {
         ty.name == "Ok"
     }
 
-    pub(super) fn serde_as(ty: &Type, use_shared_string: bool) -> Option<String> {
+    /// The type of the items of a type, looking through any number of nested
+    /// generics (e.g. `int53` for `vector<vector<int53>>`).
+    pub fn item_type(ty: &Type) -> &Type {
+        match &ty.generic_arg {
+            Some(generic_arg) => item_type(generic_arg),
+            None => ty,
+        }
+    }
+
+    /// The section a type belongs to, that is the first word of its name
+    /// (e.g. `"message"` for `MessageContent`).
+    pub fn section_name(ty: &Type) -> String {
+        let name = type_name(ty);
+        let end = name
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| c.is_ascii_uppercase())
+            .map_or(name.len(), |(i, _)| i);
+        name[..end].to_ascii_lowercase()
+    }
+
+    pub(super) fn serde_as(ty: &Type, config: &GeneratorConfig) -> Option<String> {
         if ty.name == "int64" {
             return Some("DisplayFromStr".into());
         }
 
+        if ty.name == "bytes" && config.use_bytes {
+            return Some("serde_with::base64::Base64".into());
+        }
+
         if let Some(generic_arg) = &ty.generic_arg {
-            if let Some(serde_as) = serde_as(generic_arg, use_shared_string) {
-                let mut result = get_base_path(ty, use_shared_string);
+            if let Some(serde_as) = serde_as(generic_arg, config) {
+                let mut result = get_base_path(ty, config);
 
                 result.push('<');
                 result.push_str(&serde_as);
@@ -260,11 +350,47 @@ pub mod types {
 pub mod parameters {
     use super::*;
 
-    pub fn qual_name(param: &Parameter, use_shared_string: bool) -> String {
+    /// Names of the parameters holding credentials or personal data.
+    const SENSITIVE_PARAMS: [&str; 20] = [
+        "access_token",
+        "api_hash",
+        "authentication_tokens",
+        "code",
+        "database_encryption_key",
+        "device_token",
+        "encryption_key",
+        "formatted_phone_number",
+        "new_encryption_key",
+        "new_password",
+        "old_password",
+        "password",
+        "phone_number",
+        "provider_token",
+        "purchase_token",
+        "recovery_code",
+        "secret",
+        "stream_key",
+        "token",
+        "zoom_token",
+    ];
+
+    pub fn qual_name(param: &Parameter, config: &GeneratorConfig) -> String {
+        if config.use_path_buf && is_path(param) {
+            return "std::path::PathBuf".into();
+        }
+
+        if let Some(unix_time_type) = config.unix_time_type.filter(|_| is_unix_time(param)) {
+            return unix_time_type.qual_name().into();
+        }
+
+        if config.use_durations && is_duration(param) {
+            return "std::time::Duration".into();
+        }
+
         // HACK: We're just matching against specific cases because there's not a
         // documented way for knowing optional generic arguments in the tl scheme
         let optional_generic_arg = param.description.contains("; messages may be null");
-        types::qual_name(&param.ty, optional_generic_arg, use_shared_string)
+        types::qual_name(&param.ty, optional_generic_arg, config)
     }
 
     pub fn attr_name(param: &Parameter) -> String {
@@ -282,8 +408,30 @@ pub mod parameters {
         }
     }
 
-    pub fn is_builtin_type(param: &Parameter, use_shared_string: bool) -> bool {
-        types::builtin_type(&param.ty, use_shared_string).is_some() || is_optional(param)
+    pub fn is_builtin_type(param: &Parameter, config: &GeneratorConfig) -> bool {
+        types::builtin_type(&param.ty, config).is_some() || is_optional(param)
+    }
+
+    /// Returns `true` if the parameter is a (non-optional) string, which can be
+    /// taken as `impl Into<String>` by the generated functions.
+    pub fn is_string(param: &Parameter) -> bool {
+        matches!(param.ty.name.as_ref(), "string" | "bytes") && !is_optional(param)
+    }
+
+    /// Returns the qualified type of the items of a (non-optional) vector
+    /// parameter, which can be taken as `impl IntoIterator<Item = ...>`.
+    pub fn vector_item_qual_name(param: &Parameter, config: &GeneratorConfig) -> Option<String> {
+        if param.ty.name != "vector" || is_optional(param) {
+            return None;
+        }
+
+        let generic_ty = param.ty.generic_arg.as_ref()?;
+        let item = types::qual_name(generic_ty, false, config);
+        if param.description.contains("; messages may be null") {
+            Some(format!("Option<{item}>"))
+        } else {
+            Some(item)
+        }
     }
 
     pub fn is_optional(param: &Parameter) -> bool {
@@ -298,14 +446,153 @@ pub mod parameters {
         rusty_doc(indent, &param.description)
     }
 
-    pub fn serde_as(param: &Parameter, use_shared_string: bool) -> Option<String> {
-        types::serde_as(&param.ty, use_shared_string)
+    pub fn deprecation_note(param: &Parameter) -> Option<String> {
+        rusty_deprecation(&param.description)
+    }
+
+    pub fn serde_as(param: &Parameter, config: &GeneratorConfig) -> Option<String> {
+        if config.unix_time_type.is_some() && is_unix_time(param) {
+            return Some("serde_with::TimestampSeconds<i64>".into());
+        }
+
+        if config.use_durations && is_duration(param) {
+            return Some(if param.ty.name == "double" {
+                "serde_with::DurationSecondsWithFrac<f64>".into()
+            } else {
+                "serde_with::DurationSeconds<u64>".into()
+            });
+        }
+
+        let serde_as = types::serde_as(&param.ty, config)?;
+        if is_optional(param) {
+            Some(format!("Option<{serde_as}>"))
+        } else {
+            Some(serde_as)
+        }
+    }
+
+    /// Returns whether the parameter is a local file or directory path, that
+    /// is a string named `path`, `directory` or ending with one of them
+    /// (e.g. `database_directory`).
+    pub fn is_path(param: &Parameter) -> bool {
+        let name = param.name.as_str();
+        param.ty.name == "string"
+            && ["path", "directory"]
+                .iter()
+                .any(|suffix| name == *suffix || name.ends_with(&format!("_{suffix}")))
+    }
+
+    /// Returns whether the parameter is a point in time, documented as a
+    /// Unix timestamp.
+    pub fn is_unix_time(param: &Parameter) -> bool {
+        param.ty.name == "int32"
+            && param
+                .description
+                .to_ascii_lowercase()
+                .contains("point in time (unix timestamp)")
+    }
+
+    /// Returns whether the parameter is an amount of time, documented as
+    /// being "in seconds" or "the number of seconds".
+    ///
+    /// The parameters accepting -1 for their default value (e.g. the
+    /// `immunity_delay` of `optimizeStorage`) aren't, since a `Duration`
+    /// can't hold it, nor are the ones whose unit isn't documented (e.g. the
+    /// `ttl` of `optimizeStorage`).
+    pub fn is_duration(param: &Parameter) -> bool {
+        let description = param.description.to_ascii_lowercase();
+        matches!(param.ty.name.as_ref(), "int32" | "double")
+            && !is_unix_time(param)
+            && (description.contains("in seconds") || description.contains("number of seconds"))
+            && !description.contains("-1")
+    }
+
+    /// Returns whether the parameter can't be sent to TDLib as is, and must be
+    /// serialized with its `serde_as` adapter instead (e.g. binary data, which
+    /// TDLib expects as base64 strings).
+    pub fn needs_serde_as_in_request(param: &Parameter, config: &GeneratorConfig) -> bool {
+        (config.use_bytes && is_bytes(param))
+            || (config.unix_time_type.is_some() && is_unix_time(param))
+            || (config.use_durations && is_duration(param))
+    }
+
+    /// Returns whether the parameter's type implements `Default`.
+    pub fn implements_default(param: &Parameter, config: &GeneratorConfig) -> bool {
+        match config.unix_time_type {
+            Some(unix_time_type) if is_unix_time(param) => unix_time_type.implements_default(),
+            _ => true,
+        }
+    }
+
+    /// Returns whether the parameter is (non-optional) binary data, whose
+    /// `Debug` output can be elided.
+    pub fn is_elidable_bytes(param: &Parameter) -> bool {
+        param.ty.name == "bytes" && !is_optional(param)
+    }
+
+    /// Returns whether the parameter holds a credential or personal data that
+    /// must not end up in logs (e.g. a password or a phone number).
+    pub fn is_sensitive(param: &Parameter) -> bool {
+        SENSITIVE_PARAMS.contains(&param.name.as_str())
+            && matches!(
+                types::item_type(&param.ty).name.as_ref(),
+                "string" | "bytes"
+            )
+    }
+
+    /// Returns whether the parameter holds a secret (e.g. a password, a token
+    /// or an encryption key), that is a sensitive parameter other than the
+    /// personal data like phone numbers.
+    pub fn is_secret(param: &Parameter) -> bool {
+        is_sensitive(param) && !param.name.ends_with("phone_number")
+    }
+
+    /// Returns whether the parameter holds binary data, either directly or
+    /// as the items of (nested) vectors.
+    pub fn is_bytes(param: &Parameter) -> bool {
+        types::item_type(&param.ty).name == "bytes"
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::UnixTimeType;
+
+    fn shared_string_config() -> GeneratorConfig {
+        GeneratorConfig {
+            use_shared_string: true,
+            ..Default::default()
+        }
+    }
+
+    fn path_buf_config() -> GeneratorConfig {
+        GeneratorConfig {
+            use_path_buf: true,
+            ..Default::default()
+        }
+    }
+
+    fn unix_time_config() -> GeneratorConfig {
+        GeneratorConfig {
+            unix_time_type: Some(UnixTimeType::Time),
+            ..Default::default()
+        }
+    }
+
+    fn durations_config() -> GeneratorConfig {
+        GeneratorConfig {
+            use_durations: true,
+            ..Default::default()
+        }
+    }
+
+    fn bytes_config() -> GeneratorConfig {
+        GeneratorConfig {
+            use_bytes: true,
+            ..Default::default()
+        }
+    }
 
     // Core methods
 
@@ -314,6 +601,38 @@ mod tests {
         assert_eq!(rusty_type_name("ns.some_OK_name"), "SomeOkName");
     }
 
+    #[test]
+    fn check_rusty_doc_link() {
+        assert_eq!(
+            rusty_doc_link("", "sendMessage"),
+            "/// [TDLib documentation](https://core.telegram.org/tdlib/docs/classtd_1_1td__api_1_1send_message.html)"
+        );
+        assert_eq!(
+            rusty_doc_link("    ", "MessageContent"),
+            "    /// [TDLib documentation](https://core.telegram.org/tdlib/docs/classtd_1_1td__api_1_1_message_content.html)"
+        );
+    }
+
+    #[test]
+    fn check_rusty_deprecation() {
+        assert_eq!(
+            rusty_deprecation(
+                "Returns the state. For informational purposes only. Use updateState instead to maintain it"
+            ),
+            None
+        );
+        assert_eq!(
+            rusty_deprecation("Sets the title. Deprecated, since\nit is no longer used."),
+            Some("Deprecated, since it is no longer used".into())
+        );
+        assert_eq!(
+            rusty_deprecation(
+                "Edits the media. If only the caption changes, use editCaption instead."
+            ),
+            None
+        );
+    }
+
     // Definition methods
 
     #[test]
@@ -337,6 +656,16 @@ mod tests {
         assert_eq!(name, "Created");
     }
 
+    #[test]
+    fn check_def_function_section_name() {
+        let def = "setChatTitle chat_id:int53 title:string = Ok"
+            .parse()
+            .unwrap();
+        assert_eq!(definitions::function_section_name(&def), "chat");
+        let def = "close = Ok".parse().unwrap();
+        assert_eq!(definitions::function_section_name(&def), "close");
+    }
+
     #[test]
     fn check_def_empty_variant_name() {
         let def = "true = True".parse().unwrap();
@@ -360,66 +689,91 @@ mod tests {
         assert_eq!(name, "FileType");
     }
 
+    #[test]
+    fn check_type_section_name() {
+        let ty = "MessageContent".parse().unwrap();
+        assert_eq!(types::section_name(&ty), "message");
+        let ty = "Ok".parse().unwrap();
+        assert_eq!(types::section_name(&ty), "ok");
+    }
+
     #[test]
     fn check_type_qual_name() {
         let ty = "InputPeer".parse().unwrap();
-        let name = types::qual_name(&ty, false, false);
+        let name = types::qual_name(&ty, false, &GeneratorConfig::default());
         assert_eq!(name, "crate::enums::InputPeer");
     }
 
     #[test]
     fn check_type_qual_bare_name() {
         let ty = "ipPort".parse().unwrap();
-        let name = types::qual_name(&ty, false, false);
+        let name = types::qual_name(&ty, false, &GeneratorConfig::default());
         assert_eq!(name, "crate::types::IpPort");
     }
 
     #[test]
     fn check_type_bytes_qual_name() {
         let ty = "bytes".parse().unwrap();
-        let name = types::qual_name(&ty, false, false);
+        let name = types::qual_name(&ty, false, &GeneratorConfig::default());
         assert_eq!(name, "String");
     }
 
     #[test]
     fn check_type_bytes_qual_name_shared_string() {
         let ty = "bytes".parse().unwrap();
-        let name = types::qual_name(&ty, false, true);
+        let name = types::qual_name(&ty, false, &shared_string_config());
         assert_eq!(name, "TdString");
     }
 
+    #[test]
+    fn check_type_bytes_qual_name_bytes() {
+        let ty = "bytes".parse().unwrap();
+        let name = types::qual_name(&ty, false, &bytes_config());
+        assert_eq!(name, "bytes::Bytes");
+    }
+
+    #[test]
+    fn check_type_bytes_serde_as() {
+        let ty = "vector<bytes>".parse().unwrap();
+        assert_eq!(types::serde_as(&ty, &GeneratorConfig::default()), None);
+        assert_eq!(
+            types::serde_as(&ty, &bytes_config()),
+            Some("Vec<serde_with::base64::Base64>".into())
+        );
+    }
+
     #[test]
     fn check_type_large_int_qual_name() {
         let ty = "int256".parse().unwrap();
-        let name = types::qual_name(&ty, false, false);
+        let name = types::qual_name(&ty, false, &GeneratorConfig::default());
         assert_eq!(name, "crate::types::Int256");
     }
 
     #[test]
     fn check_type_raw_vec_qual_name() {
         let ty = "vector<long>".parse().unwrap();
-        let name = types::qual_name(&ty, false, false);
+        let name = types::qual_name(&ty, false, &GeneratorConfig::default());
         assert_eq!(name, "Vec<crate::types::Long>");
     }
 
     #[test]
     fn check_type_opt_raw_vec_qual_name() {
         let ty = "vector<long>".parse().unwrap();
-        let name = types::qual_name(&ty, true, false);
+        let name = types::qual_name(&ty, true, &GeneratorConfig::default());
         assert_eq!(name, "Vec<Option<crate::types::Long>>");
     }
 
     #[test]
     fn check_type_vec_qual_name() {
         let ty = "Vector<Bool>".parse().unwrap();
-        let name = types::qual_name(&ty, false, false);
+        let name = types::qual_name(&ty, false, &GeneratorConfig::default());
         assert_eq!(name, "crate::enums::Vector<bool>");
     }
 
     #[test]
     fn check_type_opt_vec_qual_name() {
         let ty = "Vector<Bool>".parse().unwrap();
-        let name = types::qual_name(&ty, true, false);
+        let name = types::qual_name(&ty, true, &GeneratorConfig::default());
         assert_eq!(name, "crate::enums::Vector<Option<bool>>");
     }
 
@@ -428,10 +782,154 @@ mod tests {
     #[test]
     fn check_param_qual_name() {
         let param = "pts:int".parse().unwrap();
-        let name = parameters::qual_name(&param, false);
+        let name = parameters::qual_name(&param, &GeneratorConfig::default());
         assert_eq!(name, "crate::types::Int");
     }
 
+    #[test]
+    fn check_param_path_qual_name() {
+        let param = "database_directory:string".parse().unwrap();
+        assert!(parameters::is_path(&param));
+        let name = parameters::qual_name(&param, &GeneratorConfig::default());
+        assert_eq!(name, "String");
+        let name = parameters::qual_name(&param, &path_buf_config());
+        assert_eq!(name, "std::path::PathBuf");
+
+        let param = "paths:vector<closedVectorPath>".parse().unwrap();
+        assert!(!parameters::is_path(&param));
+        let param = "xpath:string".parse().unwrap();
+        assert!(!parameters::is_path(&param));
+    }
+
+    #[test]
+    fn check_param_unix_time_qual_name() {
+        let mut param: Parameter = "period:int32".parse().unwrap();
+        param.description = "The number of seconds between consecutive debits".into();
+        assert!(parameters::is_duration(&param));
+
+        let mut param: Parameter = "immunity_delay:int32".parse().unwrap();
+        param.description =
+            "The amount of time, in seconds. Pass -1 to use the default value".into();
+        assert!(!parameters::is_duration(&param));
+
+        let mut param: Parameter = "date:int32".parse().unwrap();
+        param.description = "Point in time (Unix timestamp) when the message was sent".into();
+        assert!(parameters::is_unix_time(&param));
+        let name = parameters::qual_name(&param, &GeneratorConfig::default());
+        assert_eq!(name, "i32");
+        let name = parameters::qual_name(&param, &unix_time_config());
+        assert_eq!(name, "time::OffsetDateTime");
+        assert_eq!(
+            parameters::serde_as(&param, &unix_time_config()),
+            Some("serde_with::TimestampSeconds<i64>".into())
+        );
+        assert!(!parameters::implements_default(&param, &unix_time_config()));
+
+        let mut param: Parameter = "duration:int32".parse().unwrap();
+        param.description = "Duration of the video, in seconds".into();
+        assert!(!parameters::is_unix_time(&param));
+    }
+
+    #[test]
+    fn check_param_duration_qual_name() {
+        let mut param: Parameter = "mute_for:int32".parse().unwrap();
+        param.description = "Time left before notifications will be unmuted, in seconds".into();
+        assert!(parameters::is_duration(&param));
+        let name = parameters::qual_name(&param, &GeneratorConfig::default());
+        assert_eq!(name, "i32");
+        let name = parameters::qual_name(&param, &durations_config());
+        assert_eq!(name, "std::time::Duration");
+        assert_eq!(
+            parameters::serde_as(&param, &durations_config()),
+            Some("serde_with::DurationSeconds<u64>".into())
+        );
+
+        param.ty = "double".parse().unwrap();
+        assert_eq!(
+            parameters::serde_as(&param, &durations_config()),
+            Some("serde_with::DurationSecondsWithFrac<f64>".into())
+        );
+
+        let mut param: Parameter = "period:int32".parse().unwrap();
+        param.description = "The number of seconds between consecutive debits".into();
+        assert!(parameters::is_duration(&param));
+
+        let mut param: Parameter = "immunity_delay:int32".parse().unwrap();
+        param.description =
+            "The amount of time, in seconds. Pass -1 to use the default value".into();
+        assert!(!parameters::is_duration(&param));
+
+        let mut param: Parameter = "date:int32".parse().unwrap();
+        param.description = "Point in time (Unix timestamp) when the message was sent".into();
+        assert!(!parameters::is_duration(&param));
+    }
+
+    #[test]
+    fn check_param_is_sensitive() {
+        let param = "password:string".parse().unwrap();
+        assert!(parameters::is_sensitive(&param));
+        let param = "database_encryption_key:bytes".parse().unwrap();
+        assert!(parameters::is_sensitive(&param));
+        let param = "password_hint:string".parse().unwrap();
+        assert!(!parameters::is_sensitive(&param));
+        let param = "code:int32".parse().unwrap();
+        assert!(!parameters::is_sensitive(&param));
+    }
+
+    #[test]
+    fn check_param_is_secret() {
+        let param = "recovery_code:string".parse().unwrap();
+        assert!(parameters::is_secret(&param));
+        let param = "phone_number:string".parse().unwrap();
+        assert!(parameters::is_sensitive(&param));
+        assert!(!parameters::is_secret(&param));
+    }
+
+    #[test]
+    fn check_param_is_string() {
+        let param = "title:string".parse().unwrap();
+        assert!(parameters::is_string(&param));
+        let param = "titles:vector<string>".parse().unwrap();
+        assert!(!parameters::is_string(&param));
+    }
+
+    #[test]
+    fn check_nested_vectors() {
+        let config = GeneratorConfig {
+            use_bytes: true,
+            ..Default::default()
+        };
+        let param: Parameter = "ids:vector<vector<int64>>".parse().unwrap();
+        assert_eq!(parameters::qual_name(&param, &config), "Vec<Vec<i64>>");
+        assert_eq!(
+            parameters::serde_as(&param, &config).as_deref(),
+            Some("Vec<Vec<DisplayFromStr>>")
+        );
+        let param: Parameter = "data:vector<vector<bytes>>".parse().unwrap();
+        assert!(parameters::is_bytes(&param));
+        assert!(parameters::needs_serde_as_in_request(&param, &config));
+        let param: Parameter = "rows:vector<vector<keyboardButton>>".parse().unwrap();
+        assert_eq!(
+            parameters::vector_item_qual_name(&param, &config).as_deref(),
+            Some("Vec<crate::types::KeyboardButton>")
+        );
+    }
+
+    #[test]
+    fn check_param_vector_item_qual_name() {
+        let param = "message_ids:vector<int53>".parse().unwrap();
+        let name = parameters::vector_item_qual_name(&param, &GeneratorConfig::default());
+        assert_eq!(name.as_deref(), Some("i64"));
+        let param = "matrix:vector<vector<string>>".parse().unwrap();
+        let name = parameters::vector_item_qual_name(&param, &GeneratorConfig::default());
+        assert_eq!(name.as_deref(), Some("Vec<String>"));
+        let param = "title:string".parse().unwrap();
+        assert_eq!(
+            parameters::vector_item_qual_name(&param, &GeneratorConfig::default()),
+            None
+        );
+    }
+
     #[test]
     fn check_param_attr_name() {
         let param = "access_hash:long".parse().unwrap();
@@ -15,6 +15,7 @@ use crate::metadata::Metadata;
 use crate::rustifier;
 use crate::GeneratorConfig;
 use std::io::{self, Write};
+use std::path::Path;
 use tdlib_rs_parser::tl::{Category, Definition};
 
 /// Defines the `struct` corresponding to the definition:
@@ -35,22 +36,49 @@ fn write_struct<W: Write>(
     }
 
     writeln!(file, "{}", rustifier::definitions::description(def, "    "))?;
+    writeln!(file, "    ///")?;
+    writeln!(file, "{}", rustifier::definitions::doc_link(def, "    "))?;
+
+    if let Some(feature) = metadata.def_api_group(def) {
+        writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+    }
 
     let serde_as = def
         .params
         .iter()
-        .any(|p| rustifier::parameters::serde_as(p, config.use_shared_string).is_some());
+        .any(|p| rustifier::parameters::serde_as(p, config).is_some());
 
     if serde_as {
         writeln!(file, "    #[serde_as]",)?;
     }
 
-    write!(file, "    #[derive(Clone, Debug, ",)?;
+    let custom_debug = needs_custom_debug(def, config);
+    if custom_debug {
+        write!(file, "    #[derive(Clone, ",)?;
+    } else {
+        write!(file, "    #[derive(Clone, Debug, ",)?;
+    }
     if metadata.can_def_implement_default(def) {
         write!(file, "Default, ",)?;
     }
     writeln!(file, "PartialEq, Deserialize, Serialize)]",)?;
 
+    if config.derive_json_schema {
+        writeln!(file, "    #[derive(schemars::JsonSchema)]")?;
+    }
+
+    if config.derive_arbitrary {
+        writeln!(file, "    #[derive(arbitrary::Arbitrary)]")?;
+    }
+
+    let zeroize = config.derive_zeroize && has_secrets(def, config);
+    if zeroize {
+        writeln!(
+            file,
+            "    #[derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop)]"
+        )?;
+    }
+
     writeln!(
         file,
         "    pub struct {} {{",
@@ -68,13 +96,24 @@ fn write_struct<W: Write>(
             rustifier::parameters::description(param, "        ")
         )?;
 
-        if let Some(serde_as) = rustifier::parameters::serde_as(param, config.use_shared_string) {
+        if let Some(note) = rustifier::parameters::deprecation_note(param) {
+            writeln!(file, "        #[deprecated(note = {note:?})]")?;
+        }
+
+        if let Some(serde_as) = rustifier::parameters::serde_as(param, config) {
             writeln!(file, "        #[serde_as(as = \"{serde_as}\")]")?;
         }
 
+        if zeroize && !rustifier::parameters::is_secret(param) {
+            writeln!(file, "        #[zeroize(skip)]")?;
+        }
+
         let is_optional = rustifier::parameters::is_optional(param);
         if is_optional {
-            writeln!(file, "        #[serde(default)]")?;
+            writeln!(
+                file,
+                "        #[serde(default, skip_serializing_if = \"Option::is_none\")]"
+            )?;
         }
         write!(
             file,
@@ -85,11 +124,7 @@ fn write_struct<W: Write>(
         if is_optional {
             write!(file, "Option<")?;
         }
-        write!(
-            file,
-            "{}",
-            rustifier::parameters::qual_name(param, config.use_shared_string)
-        )?;
+        write!(file, "{}", rustifier::parameters::qual_name(param, config))?;
         if is_optional {
             write!(file, ">")?;
         }
@@ -98,9 +133,105 @@ fn write_struct<W: Write>(
     }
 
     writeln!(file, "    }}")?;
+
+    if custom_debug {
+        write_debug_impl(file, def, metadata, config)?;
+    }
     Ok(())
 }
 
+/// Returns whether the `struct` has fields holding secrets.
+fn has_secrets(def: &Definition, config: &GeneratorConfig) -> bool {
+    def.params
+        .iter()
+        .filter(|p| !rustifier::parameters::is_for_bots_only(p) || config.gen_bots_only_api)
+        .any(rustifier::parameters::is_secret)
+}
+
+/// Returns whether the `struct` has binary fields whose `Debug` output must be
+/// elided when large, or sensitive fields to redact, which requires a custom
+/// `Debug` implementation.
+fn needs_custom_debug(def: &Definition, config: &GeneratorConfig) -> bool {
+    def.params
+        .iter()
+        .filter(|p| !rustifier::parameters::is_for_bots_only(p) || config.gen_bots_only_api)
+        .any(|p| {
+            (config.debug_bytes_limit.is_some() && rustifier::parameters::is_elidable_bytes(p))
+                || (config.redact_sensitive_fields && rustifier::parameters::is_sensitive(p))
+        })
+}
+
+/// Implements `Debug` for the `struct`, printing only the length of the
+/// binary fields larger than `GeneratorConfig::debug_bytes_limit` and masking
+/// the sensitive fields.
+fn write_debug_impl<W: Write>(
+    file: &mut W,
+    def: &Definition,
+    metadata: &Metadata,
+    config: &GeneratorConfig,
+) -> io::Result<()> {
+    let name = rustifier::definitions::type_name(def);
+    if let Some(feature) = metadata.def_api_group(def) {
+        writeln!(file, "    #[cfg(feature = \"{feature}\")]")?;
+    }
+    writeln!(file, "    impl std::fmt::Debug for {name} {{")?;
+    writeln!(
+        file,
+        "        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{"
+    )?;
+    writeln!(file, "            f.debug_struct(\"{name}\")")?;
+    for param in def.params.iter() {
+        if rustifier::parameters::is_for_bots_only(param) && !config.gen_bots_only_api {
+            continue;
+        }
+
+        let attr_name = rustifier::parameters::attr_name(param);
+        let field_name = attr_name.trim_start_matches("r#");
+        if config.redact_sensitive_fields && rustifier::parameters::is_sensitive(param) {
+            writeln!(
+                file,
+                "                .field(\"{field_name}\", &format_args!(\"<redacted>\"))"
+            )?;
+        } else if config.debug_bytes_limit.is_some()
+            && rustifier::parameters::is_elidable_bytes(param)
+        {
+            writeln!(
+                file,
+                "                .field(\"{field_name}\", &DebugBytes(self.{attr_name}.len(), &self.{attr_name}))"
+            )?;
+        } else {
+            writeln!(
+                file,
+                "                .field(\"{field_name}\", &self.{attr_name})"
+            )?;
+        }
+    }
+    writeln!(file, "                .finish()")?;
+    writeln!(file, "        }}")?;
+    writeln!(file, "    }}")
+}
+
+/// Writes the helper used by the custom `Debug` implementations to elide the
+/// large binary fields.
+fn write_debug_bytes<W: Write>(file: &mut W, limit: usize) -> io::Result<()> {
+    writeln!(
+        file,
+        "    struct DebugBytes<'a>(usize, &'a dyn std::fmt::Debug);"
+    )?;
+    writeln!(file, "    impl std::fmt::Debug for DebugBytes<'_> {{")?;
+    writeln!(
+        file,
+        "        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{"
+    )?;
+    writeln!(file, "            if self.0 > {limit} {{")?;
+    writeln!(file, "                write!(f, \"<{{}} bytes>\", self.0)")?;
+    writeln!(file, "            }} else {{")?;
+    writeln!(file, "                self.1.fmt(f)")?;
+    writeln!(file, "            }}")?;
+    writeln!(file, "        }}")?;
+    writeln!(file, "    }}")
+}
+
 /// Writes an entire definition as Rust code (`struct`).
 fn write_definition<W: Write>(
     file: &mut W,
@@ -113,27 +244,46 @@ fn write_definition<W: Write>(
 }
 
 /// Write the entire module dedicated to types.
+///
+/// When `split_dir` is set, the types are written in one file per section
+/// inside `split_dir/types`, and the module only includes them.
 pub(crate) fn write_types_mod<W: Write>(
     mut file: &mut W,
     definitions: &[Definition],
     metadata: &Metadata,
     config: &GeneratorConfig,
+    split_dir: Option<&Path>,
 ) -> io::Result<()> {
     // Begin outermost mod
-    writeln!(file, "#[allow(clippy::all)]")?;
+    writeln!(file, "#[allow(clippy::all, deprecated)]")?;
     writeln!(file, "pub mod types {{")?;
     writeln!(file, "    use serde::{{Deserialize, Serialize}};")?;
     writeln!(file, "    use serde_with::{{serde_as, DisplayFromStr}};")?;
     if config.use_shared_string {
         writeln!(file, "    use crate::TdString;")?;
     }
+    if let Some(limit) = config.debug_bytes_limit {
+        write_debug_bytes(file, limit)?;
+    }
 
     let types = definitions
         .iter()
         .filter(|d| d.category == Category::Types && !ignore_type(&d.ty) && !d.params.is_empty());
 
-    for definition in types {
-        write_definition(&mut file, definition, metadata, config)?;
+    match split_dir {
+        Some(dir) => crate::write_sections(
+            file,
+            dir,
+            "types",
+            types,
+            |d| rustifier::types::section_name(&d.ty),
+            |out, d| write_definition(out, d, metadata, config),
+        )?,
+        None => {
+            for definition in types {
+                write_definition(&mut file, definition, metadata, config)?;
+            }
+        }
     }
 
     // End outermost mod
@@ -0,0 +1,180 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The procedural macros of `tdlib-rs`, re-exported by its `macros` feature.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Error, FnArg, ItemFn, Type};
+
+/// Turn an async function taking an update, and optionally the `Client`
+/// which received it, into an update handler of the same name, to register
+/// with `UpdateDispatcher::handler`:
+///
+/// ```ignore
+/// #[td_handler]
+/// async fn on_new_message(update: types::UpdateNewMessage, client: Client) -> Result<(), TdError> {
+///     functions::view_messages(update.message.chat_id, vec![update.message.id], None, false, client.id()).await
+/// }
+///
+/// let dispatcher = UpdateDispatcher::new()
+///     .spawn_with(|future| drop(tokio::spawn(future)))
+///     .handler(on_new_message);
+/// ```
+///
+/// The function returns either nothing or a result, whose errors are logged.
+#[proc_macro_attribute]
+pub fn td_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = TokenStream2::from(attr);
+        let error = Error::new_spanned(attr, "`td_handler` doesn't take arguments");
+        return error.into_compile_error().into();
+    }
+    let function = parse_macro_input!(item as ItemFn);
+    expand_handler(function)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// The unit struct named after `function`, implementing `UpdateHandler` by
+/// calling it.
+fn expand_handler(function: ItemFn) -> syn::Result<TokenStream2> {
+    let sig = &function.sig;
+    if sig.asyncness.is_none() {
+        return Err(Error::new_spanned(
+            sig.fn_token,
+            "the update handlers must be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "the update handlers can't be generic",
+        ));
+    }
+
+    let mut arg_types = sig.inputs.iter().map(|input| match input {
+        FnArg::Typed(arg) => Ok::<&Type, Error>(&arg.ty),
+        FnArg::Receiver(receiver) => Err(Error::new_spanned(
+            receiver,
+            "the update handlers can't be methods",
+        )),
+    });
+    let Some(update_type) = arg_types.next().transpose()? else {
+        return Err(Error::new(
+            sig.paren_token.span.join(),
+            "the update handlers must take the update",
+        ));
+    };
+    let takes_client = arg_types.next().transpose()?.is_some();
+    if let Some(extra_arg) = sig.inputs.iter().nth(2) {
+        return Err(Error::new_spanned(
+            extra_arg,
+            "the update handlers only take the update and the client",
+        ));
+    }
+
+    let vis = &function.vis;
+    let name = &sig.ident;
+    let docs = function
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"));
+    let call = if takes_client {
+        quote!(#name(update, client))
+    } else {
+        quote!({
+            let _ = client;
+            #name(update)
+        })
+    };
+
+    Ok(quote! {
+        #(#docs)*
+        #[allow(non_camel_case_types)]
+        #vis struct #name;
+
+        impl ::tdlib_rs::dispatcher::UpdateHandler for #name {
+            type Update = #update_type;
+
+            fn handle(
+                &self,
+                update: #update_type,
+                client: ::tdlib_rs::Client,
+            ) -> ::tdlib_rs::dispatcher::HandlerFuture {
+                #function
+                ::std::boxed::Box::pin(async move {
+                    let output = #call.await;
+                    ::tdlib_rs::dispatcher::HandlerOutput::report(output, ::std::stringify!(#name));
+                })
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn expand(function: ItemFn) -> Result<String, String> {
+        expand_handler(function)
+            .map(|code| code.to_string().replace(' ', ""))
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn check_handler_with_client() {
+        let code = expand(parse_quote! {
+            pub async fn on_new_message(update: UpdateNewMessage, client: Client) {}
+        })
+        .unwrap();
+        assert!(code.contains("pubstructon_new_message;"));
+        assert!(code.contains("impl::tdlib_rs::dispatcher::UpdateHandlerforon_new_message"));
+        assert!(code.contains("typeUpdate=UpdateNewMessage;"));
+        assert!(code.contains("on_new_message(update,client).await"));
+    }
+
+    #[test]
+    fn check_handler_without_client() {
+        let code = expand(parse_quote! {
+            async fn on_user(update: UpdateUser) {}
+        })
+        .unwrap();
+        assert!(code.contains("structon_user;"));
+        assert!(code.contains("on_user(update)"));
+    }
+
+    #[test]
+    fn check_invalid_handlers() {
+        let errors = [
+            parse_quote!(
+                fn on_user(update: UpdateUser) {}
+            ),
+            parse_quote!(
+                async fn on_update<T>(update: T) {}
+            ),
+            parse_quote!(
+                async fn on_nothing() {}
+            ),
+            parse_quote!(
+                async fn on_user(update: UpdateUser, client: Client, extra: i32) {}
+            ),
+        ]
+        .map(|function| expand(function).unwrap_err());
+        assert_eq!(
+            errors,
+            [
+                "the update handlers must be async",
+                "the update handlers can't be generic",
+                "the update handlers must take the update",
+                "the update handlers only take the update and the client",
+            ]
+        );
+    }
+}
@@ -11,6 +11,8 @@
 //! Errors that can occur during the parsing of [Type Language] definitions.
 //!
 //! [Type Language]: https://core.telegram.org/mtproto/TL
+use std::error::Error;
+use std::fmt;
 
 /// The error type for the parsing operation of [`Definition`]s.
 ///
@@ -36,6 +38,52 @@ pub enum ParseError {
     UnknownSeparator,
 }
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty definition"),
+            Self::InvalidParam(error) => write!(f, "invalid parameter: {error}"),
+            Self::MissingName => write!(f, "missing definition name"),
+            Self::MissingType => write!(f, "missing or invalid type after `=`"),
+            Self::NotImplemented => write!(f, "unsupported definition"),
+            Self::UnknownSeparator => write!(f, "unknown separator"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// A [`ParseError`] found by [`parse_tl_file`], together with the position of
+/// the offending token in the file.
+///
+/// [`parse_tl_file`]: ../fn.parse_tl_file.html
+#[derive(Debug, PartialEq, Eq)]
+pub struct LocatedParseError {
+    /// The error itself.
+    pub error: ParseError,
+
+    /// The line of the offending token, starting from 1.
+    pub line: usize,
+
+    /// The column of the offending token in characters, starting from 1.
+    pub column: usize,
+
+    /// The offending token, e.g. the invalid parameter.
+    pub token: String,
+}
+
+impl fmt::Display for LocatedParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} at `{}`",
+            self.line, self.column, self.error, self.token
+        )
+    }
+}
+
+impl Error for LocatedParseError {}
+
 /// The error type for the parsing operation of [`Parameter`]s.
 ///
 /// [`Parameter`]: tl/struct.Parameter.html
@@ -50,3 +98,15 @@ pub enum ParamParseError {
     /// The parser does not know how to parse the parameter.
     NotImplemented,
 }
+
+impl fmt::Display for ParamParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty name or type"),
+            Self::InvalidGeneric => write!(f, "invalid generic argument"),
+            Self::NotImplemented => write!(f, "unsupported parameter"),
+        }
+    }
+}
+
+impl Error for ParamParseError {}
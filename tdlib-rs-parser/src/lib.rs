@@ -23,7 +23,7 @@ pub mod errors;
 pub mod tl;
 mod tl_iterator;
 
-use errors::ParseError;
+use errors::LocatedParseError;
 use tl::Definition;
 use tl_iterator::TlIterator;
 
@@ -49,7 +49,12 @@ use tl_iterator::TlIterator;
 /// }
 /// ```
 ///
+/// The errors hold the line, the column and the offending token of the
+/// invalid definitions, which are skipped.
+///
 /// [Type Language]: https://core.telegram.org/mtproto/TL
-pub fn parse_tl_file(contents: String) -> impl Iterator<Item = Result<Definition, ParseError>> {
+pub fn parse_tl_file(
+    contents: String,
+) -> impl Iterator<Item = Result<Definition, LocatedParseError>> {
     TlIterator::new(contents)
 }
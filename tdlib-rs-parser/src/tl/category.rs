@@ -9,7 +9,7 @@
 // except according to those terms.
 
 /// The category to which a definition belongs.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Category {
     /// The default category, a definition represents a type.
     Types,
@@ -64,6 +64,10 @@ impl FromStr for Type {
         if ty.is_empty() {
             return Err(ParamParseError::Empty);
         }
+        // Unbalanced brackets, e.g. `vector<int>>`
+        if ty.contains('>') {
+            return Err(ParamParseError::InvalidGeneric);
+        }
 
         // Safe to unwrap because we just checked is not empty
         let bare = ty.chars().next().unwrap().is_ascii_lowercase();
@@ -142,4 +146,25 @@ mod tests {
             _ => false,
         });
     }
+
+    #[test]
+    fn check_nested_generic_arg() {
+        let ty = Type::from_str("vector<vector<int53>>").unwrap();
+        assert_eq!(ty.to_string(), "vector<vector<int53>>");
+        let inner = ty.generic_arg.unwrap();
+        assert_eq!(inner.name, "vector");
+        assert_eq!(inner.generic_arg.unwrap().name, "int53");
+    }
+
+    #[test]
+    fn check_unbalanced_generic_arg() {
+        assert_eq!(
+            Type::from_str("vector<vector<int53>"),
+            Err(ParamParseError::InvalidGeneric)
+        );
+        assert_eq!(
+            Type::from_str("vector<int53>>"),
+            Err(ParamParseError::InvalidGeneric)
+        );
+    }
 }
@@ -7,8 +7,8 @@
 // <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
-use crate::errors::ParseError;
-use crate::tl::{Category, Definition};
+use crate::errors::{LocatedParseError, ParseError};
+use crate::tl::{Category, Definition, Parameter};
 
 const DEFINITION_SEP: char = ';';
 const FUNCTIONS_SEP: &str = "---functions---";
@@ -34,7 +34,7 @@ impl TlIterator {
 }
 
 impl Iterator for TlIterator {
-    type Item = Result<Definition, ParseError>;
+    type Item = Result<Definition, LocatedParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let definition = loop {
@@ -91,7 +91,11 @@ impl Iterator for TlIterator {
                 self.category = Category::Types;
                 definition.trim()
             } else {
-                return Some(Err(ParseError::UnknownSeparator));
+                return Some(Err(locate(
+                    &self.contents,
+                    definition,
+                    ParseError::UnknownSeparator,
+                )));
             }
         } else {
             definition
@@ -103,20 +107,116 @@ impl Iterator for TlIterator {
                 d.category = self.category;
                 Ok(d)
             }
-            x => x,
+            Err(error) => Err(locate(&self.contents, definition, error)),
         })
     }
 }
 
+/// Returns the whitespace-separated tokens of `s`, with their byte offsets.
+fn tokens(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.split_whitespace()
+        .map(move |token| (token.as_ptr() as usize - s.as_ptr() as usize, token))
+}
+
+/// Finds the token of `definition`, a slice of `contents`, that caused
+/// `error`, and returns the error located at it.
+fn locate(contents: &str, definition: &str, error: ParseError) -> LocatedParseError {
+    // Skip the leading comments, as `Definition::from_str` does
+    let mut code_start = 0;
+    if let Some(start) = definition.rfind("//") {
+        if let Some(end) = definition[start..].find('\n') {
+            code_start = start + end;
+        }
+    }
+    let code = &definition[code_start..];
+
+    let mut code_tokens = tokens(code).map(|(offset, token)| (code_start + offset, token));
+    let first = code_tokens.next();
+    let (offset, token) = match error {
+        ParseError::UnknownSeparator => tokens(definition).next(),
+        ParseError::MissingName => first,
+        ParseError::MissingType => match code.find('=') {
+            Some(equals) => Some((code_start + equals, &code[equals..])),
+            None => first.into_iter().chain(code_tokens).last(),
+        },
+        ParseError::InvalidParam(_) | ParseError::NotImplemented => code_tokens
+            .take_while(|(_, token)| *token != "=" && !token.starts_with('='))
+            .find(|(_, token)| token.parse::<Parameter>().is_err())
+            .or(first),
+        ParseError::Empty => None,
+    }
+    .unwrap_or((0, definition));
+
+    let offset = definition.as_ptr() as usize - contents.as_ptr() as usize + offset;
+    let before = &contents[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    LocatedParseError {
+        error,
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+        token: token.trim().to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::errors::ParseError;
+    use crate::errors::{ParamParseError, ParseError};
 
     #[test]
     fn parse_bad_separator() {
         let mut it = TlIterator::new("---foo---".into());
-        assert_eq!(it.next(), Some(Err(ParseError::UnknownSeparator)));
+        assert_eq!(
+            it.next(),
+            Some(Err(LocatedParseError {
+                error: ParseError::UnknownSeparator,
+                line: 1,
+                column: 1,
+                token: "---foo---".into(),
+            }))
+        );
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn parse_error_locations() {
+        let mut it = TlIterator::new(
+            "first = t;
+//@description A description
+second id:int32 bad:<t> = t;
+  third id:int32 =;
+fourth"
+                .into(),
+        );
+
+        assert_eq!(it.next().unwrap().unwrap().name, "first");
+        assert_eq!(
+            it.next(),
+            Some(Err(LocatedParseError {
+                error: ParseError::InvalidParam(ParamParseError::Empty),
+                line: 3,
+                column: 17,
+                token: "bad:<t>".into(),
+            }))
+        );
+        assert_eq!(
+            it.next(),
+            Some(Err(LocatedParseError {
+                error: ParseError::MissingType,
+                line: 4,
+                column: 18,
+                token: "=".into(),
+            }))
+        );
+        assert_eq!(
+            it.next(),
+            Some(Err(LocatedParseError {
+                error: ParseError::MissingType,
+                line: 5,
+                column: 1,
+                token: "fourth".into(),
+            }))
+        );
         assert_eq!(it.next(), None);
     }
 
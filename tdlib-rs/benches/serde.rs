@@ -0,0 +1,128 @@
+// cargo bench -p tdlib-rs --bench serde --features stub
+//
+// Measures the deserialization of representative updates, both alone and
+// through `receive`, and the serialization of the requests, to catch the
+// performance regressions of the generator and of the serde attributes.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tdlib_rs::{enums, types, Transport};
+
+/// The updates, as sent by TDLib, from the smallest and most frequent one to
+/// the largest.
+const PAYLOADS: &[(&str, &str)] = &[
+    (
+        "update_chat_read_inbox",
+        include_str!("payloads/update_chat_read_inbox.json"),
+    ),
+    ("update_file", include_str!("payloads/update_file.json")),
+    (
+        "update_new_message",
+        include_str!("payloads/update_new_message.json"),
+    ),
+    (
+        "update_new_chat",
+        include_str!("payloads/update_new_chat.json"),
+    ),
+];
+
+/// A transport receiving the payloads in turn, as updates of the client 1.
+struct Payloads {
+    payloads: Vec<String>,
+    next: AtomicUsize,
+}
+
+impl Transport for Payloads {
+    fn create_client(&self) -> i32 {
+        1
+    }
+
+    fn send(&self, _client_id: i32, _request: String) {}
+
+    fn receive(&self, _timeout: f64) -> Option<String> {
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        Some(self.payloads[next % self.payloads.len()].clone())
+    }
+}
+
+fn deserialize_updates(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deserialize");
+    for (name, payload) in PAYLOADS {
+        group.throughput(Throughput::Bytes(payload.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| serde_json::from_str::<enums::Update>(payload).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn receive_updates(c: &mut Criterion) {
+    let payloads = PAYLOADS
+        .iter()
+        .map(|(_, payload)| {
+            let mut update: serde_json::Value = serde_json::from_str(payload).unwrap();
+            update["@client_id"] = 1.into();
+            update.to_string()
+        })
+        .collect();
+    assert!(tdlib_rs::set_transport(Payloads {
+        payloads,
+        next: AtomicUsize::new(0),
+    }));
+
+    let mut group = c.benchmark_group("receive");
+    group.throughput(Throughput::Elements(PAYLOADS.len() as u64));
+    group.bench_function("updates", |b| {
+        b.iter(|| {
+            for _ in PAYLOADS {
+                tdlib_rs::receive().unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn text_message() -> enums::InputMessageContent {
+    let entity = |offset, length, r#type| types::TextEntity {
+        offset,
+        length,
+        r#type,
+    };
+    enums::InputMessageContent::InputMessageText(types::InputMessageText {
+        text: types::FormattedText {
+            text: "The release is out! Read the changelog at \
+                   https://github.com/fifteenlabs/tdlib-rs and let us know what you think."
+                .into(),
+            entities: vec![
+                entity(4, 7, enums::TextEntityType::Bold),
+                entity(42, 39, enums::TextEntityType::Url),
+            ],
+        },
+        clear_draft: true,
+        ..Default::default()
+    })
+}
+
+fn serialize_requests(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize");
+    group.bench_function("input_message_text", |b| {
+        b.iter_batched(
+            text_message,
+            |content| serde_json::to_string(&content).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("input_message_album", |b| {
+        let album = vec![text_message(); 10];
+        b.iter(|| serde_json::to_string(&album).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    deserialize_updates,
+    receive_updates,
+    serialize_requests
+);
+criterion_main!(benches);
@@ -7,35 +7,339 @@
 // <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
+use std::collections::hash_map::DefaultHasher;
 use std::env;
-use std::fs::File;
-use std::io::{BufWriter, Read, Write};
+use std::fs;
+use std::hash::{Hash, Hasher};
 use std::path::Path;
-use tdlib_rs_gen::{generate_rust_code_with_config, GeneratorConfig};
+use tdlib_rs_gen::{generate_rust_code_split, GeneratorConfig, UnixTimeType};
 use tdlib_rs_parser::parse_tl_file;
 use tdlib_rs_parser::tl::Definition;
 
-#[allow(dead_code)]
-#[cfg(not(any(feature = "docs", feature = "pkg-config")))]
 /// The version of the TDLib library.
 const TDLIB_VERSION: &str = "1.8.60";
 
-/// Load the type language definitions from a certain file.
+#[cfg(feature = "vendored")]
+/// The commit of the TDLib repository built by the `vendored` feature.
+const TDLIB_COMMIT: &str = "cb863c1600082404428f1a84e407b866b9d412a8";
+
+/// The estimated size in bytes above which enum variants are boxed when the
+/// `box-large-variants` feature is enabled.
+const BOX_VARIANTS_THRESHOLD: usize = 128;
+
+/// The length above which binary fields are printed as their length only by
+/// the `Debug` implementations.
+const DEBUG_BYTES_LIMIT: usize = 64;
+
+/// The TDLib version whose schema is selected by the `TDLIB_API_VERSION` environment variable or
+/// by an `api-*` feature, if any. The schema of every version is shipped in `tl/api_<version>.tl`.
+fn api_version() -> Option<String> {
+    println!("cargo:rerun-if-env-changed=TDLIB_API_VERSION");
+    match env::var("TDLIB_API_VERSION") {
+        Ok(version) => Some(version),
+        Err(_) if cfg!(feature = "api-1.8.19") => Some("1.8.19".to_string()),
+        Err(_) => None,
+    }
+}
+
+/// The path of the schema to generate the code from, `tl/api.tl` (the schema of `TDLIB_VERSION`)
+/// unless another version is selected.
+fn api_tl_file() -> String {
+    let Some(version) = api_version() else {
+        return "tl/api.tl".to_string();
+    };
+
+    let file = format!("tl/api_{version}.tl");
+    if !Path::new(&file).exists() {
+        panic!("There is no schema for TDLib {version}, the available versions are the ones of the tl/api_<version>.tl files");
+    }
+    file
+}
+
+/// Read a type language file, rerunning the build script when it changes.
+fn read_tl(path: &str) -> std::io::Result<(String, String)> {
+    println!("cargo:rerun-if-changed={path}");
+    Ok((path.to_string(), fs::read_to_string(path)?))
+}
+
+/// Load the type language definitions from the contents of a certain file.
 /// Parse errors will be printed to `stderr`, and only the
 /// valid results will be returned.
-fn load_tl(file: &str) -> std::io::Result<Vec<Definition>> {
-    let mut file = File::open(file)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    Ok(parse_tl_file(contents)
+fn load_tl((path, contents): &(String, String)) -> Vec<Definition> {
+    parse_tl_file(contents.clone())
         .filter_map(|d| match d {
             Ok(d) => Some(d),
             Err(e) => {
-                eprintln!("TL: parse error: {e:?}");
+                eprintln!("TL: parse error: {path}:{e}");
                 None
             }
         })
-        .collect())
+        .collect()
+}
+
+/// The hash of everything the generated code depends on: the schemas, the
+/// generator configuration and the generator itself, which is compiled into
+/// the build script.
+fn generation_hash(schemas: &[(String, String)], config: &GeneratorConfig) -> String {
+    let mut hasher = DefaultHasher::new();
+    schemas.hash(&mut hasher);
+    config.hash(&mut hasher);
+    env::current_exe()
+        .and_then(fs::metadata)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(not(any(
+    feature = "docs",
+    feature = "pkg-config",
+    feature = "download-tdlib",
+    feature = "local-tdlib",
+    feature = "vcpkg",
+    feature = "vendored",
+    feature = "dynamic-loading",
+    feature = "wasm"
+)))]
+/// Discover the tdlib installed in the system (e.g. by the distribution or nix) through its
+/// `tdjson.pc` file, so that the library path doesn't have to be set by hand.
+/// If the library is not found, the linker falls back to its default search paths.
+/// Setting the `TDLIB_NO_PKG_CONFIG` environment variable disables the discovery.
+fn probe_system_tdlib() {
+    println!("cargo:rerun-if-env-changed=TDLIB_NO_PKG_CONFIG");
+    if env::var_os("TDLIB_NO_PKG_CONFIG").is_some() {
+        return;
+    }
+
+    if let Ok(library) = pkg_config::Config::new()
+        .atleast_version(api_version().as_deref().unwrap_or(TDLIB_VERSION))
+        .probe("tdjson")
+    {
+        #[cfg(not(target_os = "windows"))]
+        for path in library.link_paths {
+            println!("cargo:rustc-link-arg=-Wl,-rpath,{}", path.display());
+        }
+    } else if let Some(prefix) = homebrew_tdlib_prefix() {
+        let lib_dir = prefix.join("lib");
+        println!("cargo:rustc-link-search=native={}", lib_dir.display());
+        println!("cargo:rustc-link-arg=-Wl,-rpath,{}", lib_dir.display());
+    }
+}
+
+#[cfg(not(any(
+    feature = "docs",
+    feature = "pkg-config",
+    feature = "download-tdlib",
+    feature = "vcpkg",
+    feature = "vendored",
+    feature = "dynamic-loading",
+    feature = "wasm"
+)))]
+/// Find the prefix of the tdlib installed with Homebrew when targeting macOS.
+/// The prefix is given by `brew --prefix tdlib`, or, if `brew` is not in the `PATH`, it is looked
+/// up in `$HOMEBREW_PREFIX/opt/tdlib` and in the default prefixes of Apple Silicon
+/// (`/opt/homebrew`) and Intel (`/usr/local`) Macs.
+fn homebrew_tdlib_prefix() -> Option<std::path::PathBuf> {
+    println!("cargo:rerun-if-env-changed=HOMEBREW_PREFIX");
+    if env::var("CARGO_CFG_TARGET_OS").ok()? != "macos" {
+        return None;
+    }
+
+    let is_tdlib = |prefix: &std::path::PathBuf| prefix.join("lib").is_dir();
+    if let Ok(output) = std::process::Command::new("brew")
+        .args(["--prefix", "tdlib"])
+        .output()
+    {
+        let prefix = std::path::PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        if output.status.success() && is_tdlib(&prefix) {
+            return Some(prefix);
+        }
+    }
+
+    env::var("HOMEBREW_PREFIX")
+        .ok()
+        .into_iter()
+        .chain(["/opt/homebrew".to_string(), "/usr/local".to_string()])
+        .map(|prefix| Path::new(&prefix).join("opt/tdlib"))
+        .find(is_tdlib)
+}
+
+#[cfg(feature = "vcpkg")]
+/// The vcpkg triplet matching the target, e.g. `x64-windows` or `x64-windows-static` when the
+/// C runtime is linked statically (`-C target-feature=+crt-static`).
+/// The `VCPKGRS_TRIPLET` environment variable overrides it; on the other OSes the triplet is
+/// left to the `vcpkg` crate.
+fn vcpkg_triplet() -> Option<String> {
+    if let Ok(triplet) = env::var("VCPKGRS_TRIPLET") {
+        return Some(triplet);
+    }
+    if env::var("CARGO_CFG_TARGET_OS").ok()? != "windows" {
+        return None;
+    }
+
+    let arch = match env::var("CARGO_CFG_TARGET_ARCH").ok()?.as_str() {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        "x86" => "x86",
+        _ => return None,
+    };
+    let crt_static = env::var("CARGO_CFG_TARGET_FEATURE")
+        .unwrap_or_default()
+        .split(',')
+        .any(|feature| feature == "crt-static");
+    if crt_static {
+        Some(format!("{arch}-windows-static"))
+    } else {
+        Some(format!("{arch}-windows"))
+    }
+}
+
+#[cfg(feature = "vcpkg")]
+/// Find the tdlib installed with `vcpkg install tdlib` in the `VCPKG_ROOT` folder.
+/// The `vcpkg` crate passes to `rustc` the link flags of the libraries of the port.
+fn probe_vcpkg() {
+    let mut config = vcpkg::Config::new();
+    if let Some(triplet) = vcpkg_triplet() {
+        config.target_triplet(triplet);
+    }
+    let library = config.find_package("tdlib").unwrap_or_else(|e| {
+        panic!("tdlib not found with vcpkg, try `vcpkg install tdlib`: {e}");
+    });
+
+    // The dlls are not copied next to the executable, so make them reachable as the
+    // `download-tdlib` and `local-tdlib` features do
+    for path in library.dll_paths {
+        println!("cargo:rustc-link-search=native={}", path.display());
+    }
+}
+
+#[cfg(feature = "vendored")]
+/// Build TDLib from source at [`TDLIB_COMMIT`] with cmake, and link it statically.
+/// The repository is cloned in the OUT_DIR/td folder, unless the `TDLIB_SOURCE_DIR` environment
+/// variable points to a checkout (e.g. to build offline), and installed in the OUT_DIR/tdlib
+/// folder.
+/// The build requires git, cmake, gperf, a C++ compiler, and the OpenSSL and zlib development
+/// files.
+fn build_vendored_tdlib() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let source_dir = match env::var("TDLIB_SOURCE_DIR") {
+        Ok(source_dir) => source_dir,
+        Err(_) => {
+            let source_dir = format!("{out_dir}/td");
+            checkout_tdlib(&source_dir);
+            source_dir
+        }
+    };
+
+    let mut config = cmake::Config::new(&source_dir);
+    config
+        .profile("Release")
+        .out_dir(format!("{out_dir}/tdlib"))
+        .define("TD_ENABLE_JNI", "OFF")
+        .define("TD_INSTALL_STATIC_LIBRARIES", "ON")
+        .define("TD_INSTALL_SHARED_LIBRARIES", "OFF");
+    #[cfg(feature = "static-tdlib")]
+    {
+        config
+            .define("OPENSSL_USE_STATIC_LIBS", "TRUE")
+            .define("ZLIB_USE_STATIC_LIBS", "ON");
+        if let Ok(openssl_dir) = env::var("OPENSSL_DIR") {
+            config.define("OPENSSL_ROOT_DIR", openssl_dir);
+        }
+    }
+    let prefix = config.build();
+
+    // The pkg-config files installed by TDLib list the static libraries in link order, together
+    // with their dependencies
+    let pkg_config_dir = prefix.join("lib").join("pkgconfig");
+    let pkg_config_path = match env::var_os("PKG_CONFIG_PATH") {
+        Some(path) => {
+            env::join_paths(std::iter::once(pkg_config_dir).chain(env::split_paths(&path))).unwrap()
+        }
+        None => pkg_config_dir.into_os_string(),
+    };
+    env::set_var("PKG_CONFIG_PATH", pkg_config_path);
+    #[cfg(not(feature = "static-tdlib"))]
+    pkg_config::Config::new()
+        .statik(true)
+        .probe("tdjson_static")
+        .unwrap();
+    #[cfg(feature = "static-tdlib")]
+    link_static_tdlib();
+
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+    let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap();
+    if target_os == "macos" || target_os == "ios" {
+        println!("cargo:rustc-link-lib=dylib=c++");
+    } else if target_env != "msvc" {
+        println!("cargo:rustc-link-lib=dylib=stdc++");
+    }
+}
+
+#[cfg(feature = "static-tdlib")]
+/// Link the TDLib built by [`build_vendored_tdlib`] together with OpenSSL and zlib statically.
+/// The other libraries listed by the pkg-config files are linked statically when their static
+/// version is found in the search paths, which on musl targets are the only ones linked anyway.
+/// The `OPENSSL_DIR` environment variable can point to the folder of a static OpenSSL build.
+fn link_static_tdlib() {
+    let library = pkg_config::Config::new()
+        .statik(true)
+        .cargo_metadata(false)
+        .probe("tdjson_static")
+        .unwrap();
+
+    let mut link_paths = library.link_paths;
+    if let Ok(openssl_dir) = env::var("OPENSSL_DIR") {
+        link_paths.push(Path::new(&openssl_dir).join("lib"));
+    }
+    for path in &link_paths {
+        println!("cargo:rustc-link-search=native={}", path.display());
+    }
+
+    let static_deps = ["ssl", "crypto", "z"];
+    let mut libs = library.libs;
+    for lib in static_deps {
+        if !libs.iter().any(|name| name == lib) {
+            libs.push(lib.to_string());
+        }
+    }
+    for lib in libs {
+        let is_static = static_deps.contains(&lib.as_str())
+            || link_paths
+                .iter()
+                .any(|path| path.join(format!("lib{lib}.a")).exists());
+        if is_static {
+            println!("cargo:rustc-link-lib=static={lib}");
+        } else {
+            println!("cargo:rustc-link-lib=dylib={lib}");
+        }
+    }
+}
+
+#[cfg(feature = "vendored")]
+/// Clone the TDLib repository in `source_dir`, if it is not already cloned, and check out
+/// [`TDLIB_COMMIT`].
+fn checkout_tdlib(source_dir: &str) {
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .args(args)
+            .status()
+            .map(|status| status.success())
+            .unwrap_or_else(|e| panic!("Failed to run git, which is required to build TDLib: {e}"))
+    };
+
+    if !Path::new(source_dir).join(".git").exists()
+        && !git(&["clone", "https://github.com/tdlib/td.git", source_dir])
+    {
+        panic!("Failed to clone the TDLib repository in {source_dir}");
+    }
+    if !git(&["-C", source_dir, "checkout", "--quiet", TDLIB_COMMIT])
+        && !(git(&["-C", source_dir, "fetch", "origin"])
+            && git(&["-C", source_dir, "checkout", "--quiet", TDLIB_COMMIT]))
+    {
+        panic!("Failed to check out the TDLib commit {TDLIB_COMMIT} in {source_dir}");
+    }
 }
 
 #[cfg(feature = "local-tdlib")]
@@ -44,7 +348,8 @@ fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> std::io::Result
     std::fs::create_dir_all(&dst)?;
     for entry in std::fs::read_dir(src)? {
         let entry = entry?;
-        let ty = entry.file_type()?;
+        // Follow the symlinks, like the `Versions/Current` ones of the macOS frameworks
+        let ty = std::fs::metadata(entry.path())?.file_type();
         if ty.is_dir() {
             copy_dir_all(entry.path(), dst.as_ref().join(entry.file_name()))?;
         } else {
@@ -55,18 +360,18 @@ fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> std::io::Result
 }
 
 #[cfg(feature = "local-tdlib")]
-/// Copy all the tdlib folder find in the LOCAL_TDLIB_PATH environment variable to the OUT_DIR/tdlib folder
+/// Copy all the tdlib folder find in the LOCAL_TDLIB_PATH environment variable to the OUT_DIR/tdlib folder.
+/// If the variable is not set, the tdlib installed with Homebrew is used on macOS.
 fn copy_local_tdlib() {
-    match env::var("LOCAL_TDLIB_PATH") {
-        Ok(tdlib_path) => {
-            let out_dir = env::var("OUT_DIR").unwrap();
-            let prefix = format!("{out_dir}/tdlib");
-            copy_dir_all(Path::new(&tdlib_path), Path::new(&prefix)).unwrap();
-        }
-        Err(_) => {
-            panic!("The LOCAL_TDLIB_PATH env variable must be set to the path of the tdlib folder");
-        }
+    let tdlib_path = match env::var("LOCAL_TDLIB_PATH") {
+        Ok(tdlib_path) => std::path::PathBuf::from(tdlib_path),
+        Err(_) => homebrew_tdlib_prefix().unwrap_or_else(|| {
+            panic!("The LOCAL_TDLIB_PATH env variable must be set to the path of the tdlib folder")
+        }),
     };
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let prefix = format!("{out_dir}/tdlib");
+    copy_dir_all(&tdlib_path, Path::new(&prefix)).unwrap();
 }
 
 #[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
@@ -78,9 +383,22 @@ fn copy_local_tdlib() {
 /// - Windows aarch64
 /// - MacOS x86_64
 /// - MacOS aarch64
+/// - Android arm64-v8a, armeabi-v7a, x86_64 and x86
+/// - iOS and MacOS, with an xcframework or static libraries
 fn generic_build() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let prefix = format!("{out_dir}/tdlib");
+    if env::var("CARGO_CFG_TARGET_OS").unwrap() == "android" {
+        android_build(&prefix);
+        return;
+    }
+    if env::var("CARGO_CFG_TARGET_OS").unwrap() == "ios"
+        || Path::new(&prefix).join("Info.plist").exists()
+    {
+        apple_build(&prefix);
+        return;
+    }
+
     let include_dir = format!("{prefix}/include");
     let lib_dir = format!("{prefix}/lib");
     let lib_path = {
@@ -126,54 +444,201 @@ fn generic_build() {
     println!("cargo:rustc-link-arg=-Wl,-rpath,{lib_dir}");
 }
 
+#[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+/// Build the project for Android, linking the `libs/<abi>/libtdjson.so` (or `lib/libtdjson.so`)
+/// library found in `prefix` and the shared C++ runtime of the NDK.
+fn android_build(prefix: &str) {
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let (abi, triple) = match target_arch.as_str() {
+        "aarch64" => ("arm64-v8a", "aarch64-linux-android"),
+        "arm" => ("armeabi-v7a", "arm-linux-androideabi"),
+        "x86_64" => ("x86_64", "x86_64-linux-android"),
+        "x86" => ("x86", "i686-linux-android"),
+        _ => panic!("Unsupported Android architecture: {target_arch}"),
+    };
+    let lib_dir = [format!("{prefix}/libs/{abi}"), format!("{prefix}/lib")]
+        .into_iter()
+        .find(|lib_dir| std::path::Path::new(&format!("{lib_dir}/libtdjson.so")).exists())
+        .unwrap_or_else(|| {
+            panic!("tdjson shared library not found at {prefix}/libs/{abi}/libtdjson.so")
+        });
+
+    println!("cargo:rustc-link-search=native={lib_dir}");
+    println!("cargo:include={prefix}/include");
+    println!("cargo:rustc-link-lib=dylib=tdjson");
+
+    if let Some(sysroot_lib_dir) = android_sysroot_lib_dir(triple) {
+        println!("cargo:rustc-link-search=native={sysroot_lib_dir}");
+    }
+    println!("cargo:rustc-link-lib=dylib=c++_shared");
+}
+
+#[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+/// The folder of the NDK sysroot with the libraries for the `triple` target, if `ANDROID_NDK_HOME`
+/// or `ANDROID_NDK_ROOT` is set.
+fn android_sysroot_lib_dir(triple: &str) -> Option<String> {
+    let ndk = env::var("ANDROID_NDK_HOME")
+        .or_else(|_| env::var("ANDROID_NDK_ROOT"))
+        .ok()?;
+    // The NDK only ships x86_64 toolchains, also for Apple Silicon hosts
+    let host_tag = if cfg!(target_os = "windows") {
+        "windows-x86_64"
+    } else if cfg!(target_os = "macos") {
+        "darwin-x86_64"
+    } else {
+        "linux-x86_64"
+    };
+    Some(format!(
+        "{ndk}/toolchains/llvm/prebuilt/{host_tag}/sysroot/usr/lib/{triple}"
+    ))
+}
+
+#[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+/// Build the project for iOS, or for MacOS with an xcframework.
+/// The libraries are looked up in the slice of the xcframework matching the target (e.g.
+/// `TDLib.xcframework/ios-arm64_x86_64-simulator`), or in the `lib` folder otherwise.
+/// A framework is linked in place of `tdjson`, while static libraries are all linked statically,
+/// so that they are bundled in the rlib and in the staticlib of an app embedding a Rust core.
+fn apple_build(prefix: &str) {
+    let lib_dir = if Path::new(prefix).join("Info.plist").exists() {
+        xcframework_slice(prefix)
+    } else {
+        Path::new(prefix).join("lib")
+    };
+    let entries = std::fs::read_dir(&lib_dir)
+        .unwrap_or_else(|e| panic!("tdjson library not found at {}: {e}", lib_dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    let framework = entries.iter().find(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == "framework")
+    });
+    if let Some(framework) = framework {
+        let name = framework.file_stem().unwrap().to_string_lossy();
+        println!("cargo:rustc-link-search=framework={}", lib_dir.display());
+        println!("cargo:rustc-link-lib=framework=tdjson:{name}");
+        if env::var("CARGO_CFG_TARGET_OS").unwrap() == "macos" {
+            println!("cargo:rustc-link-arg=-Wl,-rpath,{}", lib_dir.display());
+        }
+        return;
+    }
+
+    let static_libs = entries
+        .iter()
+        .filter(|path| path.extension().is_some_and(|extension| extension == "a"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.strip_prefix("lib")?;
+            Some(name.to_string())
+        })
+        .collect::<Vec<_>>();
+    // The JSON interface is either in `libtdjson.a` or in `libtdjson_static.a`, as installed by
+    // TDLib, and `ld64` resolves the symbols of the other libraries regardless of their order
+    let tdjson = ["tdjson", "tdjson_static"]
+        .into_iter()
+        .find(|tdjson| static_libs.iter().any(|name| name == tdjson))
+        .unwrap_or_else(|| panic!("tdjson static library not found at {}", lib_dir.display()));
+    println!("cargo:rustc-link-lib=static=tdjson:{tdjson}");
+    for name in static_libs.iter().filter(|name| *name != tdjson) {
+        println!("cargo:rustc-link-lib=static={name}");
+    }
+    println!("cargo:rustc-link-lib=dylib=c++");
+    println!("cargo:rustc-link-lib=dylib=z");
+}
+
+#[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+/// The slice of the xcframework matching the target platform, architecture and variant
+/// (simulator or Mac Catalyst).
+fn xcframework_slice(xcframework: &str) -> std::path::PathBuf {
+    let target = env::var("TARGET").unwrap();
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let platform = if target_os == "ios" { "ios" } else { "macos" };
+    let arch = match target_arch.as_str() {
+        "aarch64" => "arm64",
+        "x86_64" => "x86-64",
+        _ => panic!("Unsupported Apple architecture: {target_arch}"),
+    };
+    let variant = if target.ends_with("-macabi") {
+        "-maccatalyst"
+    } else if target_os == "ios" && (target.ends_with("-sim") || target_arch == "x86_64") {
+        "-simulator"
+    } else {
+        ""
+    };
+
+    // The slices are named after the platform, the architectures and the variant, e.g.
+    // `ios-arm64`, `ios-arm64_x86_64-simulator` or `macos-arm64_x86_64`
+    std::fs::read_dir(xcframework)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let name = path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .replace("x86_64", "x86-64");
+            name.strip_prefix(platform)
+                .and_then(|name| name.strip_prefix('-'))
+                .and_then(|name| name.strip_suffix(variant))
+                .is_some_and(|archs| {
+                    !archs.contains('-') && archs.split('_').any(|slice_arch| slice_arch == arch)
+                })
+        })
+        .unwrap_or_else(|| {
+            panic!("No slice for {target} found in the xcframework at {xcframework}")
+        })
+}
+
 #[cfg(feature = "download-tdlib")]
 fn download_tdlib() {
     let base_url = "https://github.com/fifteenlabs/tdlib-rs/releases/download";
-    let url = format!(
-        "{}/v{}/tdlib-{}-{}-{}.zip",
-        base_url,
-        env!("CARGO_PKG_VERSION"),
-        TDLIB_VERSION,
-        std::env::var("CARGO_CFG_TARGET_OS").unwrap(),
-        std::env::var("CARGO_CFG_TARGET_ARCH").unwrap(),
-    );
-    // let target_os = if cfg!(target_os = "windows") {
-    //     "Windows"
-    // } else if cfg!(target_os = "linux") {
-    //     "Linux"
-    // } else if cfg!(target_os = "macos") {
-    //     "macOS"
-    // } else {
-    //     ""
-    // };
-    // let target_arch = if cfg!(target_arch = "x86_64") {
-    //     "X64"
-    // } else if cfg!(target_arch = "aarch64") {
-    //     "ARM64"
-    // } else {
-    //     ""
-    // };
-    // let url = format!(
-    //     "{}/test/{}-{}-TDLib-{}.zip",
-    //     base_url, target_os, target_arch, "2589c3fd46925f5d57e4ec79233cd1bd0f5d0c09"
-    // );
+    let url = std::env::var("TDLIB_DOWNLOAD_URL").unwrap_or_else(|_| {
+        format!(
+            "{}/v{}/tdlib-{}-{}-{}.zip",
+            base_url,
+            env!("CARGO_PKG_VERSION"),
+            TDLIB_VERSION,
+            std::env::var("CARGO_CFG_TARGET_OS").unwrap(),
+            std::env::var("CARGO_CFG_TARGET_ARCH").unwrap(),
+        )
+    });
+    let expected_sha256 = std::env::var("TDLIB_DOWNLOAD_SHA256")
+        .ok()
+        .map(|sha256| sha256.trim().to_ascii_lowercase());
 
-    let out_dir = env::var("OUT_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
     let tdlib_dir = format!("{}/tdlib", &out_dir);
     let zip_path = format!("{}.zip", &tdlib_dir);
+    let stamp_path = format!("{}.stamp", &tdlib_dir);
+
+    // Reuse the artifact extracted by a previous build, if it comes from the
+    // same URL and matches the expected checksum
+    if let Ok(stamp) = std::fs::read_to_string(&stamp_path) {
+        if let Some((stamp_url, stamp_sha256)) = stamp.trim().split_once('\n') {
+            if stamp_url == url
+                && expected_sha256
+                    .as_ref()
+                    .is_none_or(|sha256| sha256 == stamp_sha256)
+                && std::path::Path::new(&tdlib_dir).exists()
+            {
+                return;
+            }
+        }
+    }
+    let _ = std::fs::remove_file(&stamp_path);
+    let _ = std::fs::remove_dir_all(&tdlib_dir);
 
-    // Create the request
-    let response = reqwest::blocking::get(&url).unwrap();
+    // Create the request with a longer timeout for large files
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(300))
+        .build()
+        .unwrap();
+    let response = client.get(&url).send().unwrap();
 
     // Check if the response status is successful
-    if response.status().is_success() {
-        // Create a file to write to
-        let mut dest = File::create(&zip_path).unwrap();
-
-        // Get the response bytes and write to the file
-        let content = response.bytes().unwrap();
-        std::io::copy(&mut content.as_ref(), &mut dest).unwrap();
-    } else {
+    if !response.status().is_success() {
         panic!(
             "[{}] Failed to download file: {}\n{}\n{}",
             "Your OS or architecture may be unsupported.",
@@ -182,8 +647,25 @@ fn download_tdlib() {
             &url
         )
     }
+    let content = response.bytes().unwrap();
+
+    // Verify the checksum of the artifact
+    let sha256 = sha256_hex(&content);
+    match &expected_sha256 {
+        Some(expected) if *expected != sha256 => panic!(
+            "Checksum mismatch for {url}: expected SHA-256 {expected}, got {sha256}"
+        ),
+        Some(_) => {}
+        None => println!(
+            "cargo:warning=Downloaded {url} with SHA-256 {sha256}; set TDLIB_DOWNLOAD_SHA256 to verify it"
+        ),
+    }
 
-    let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+    // Create a file to write to
+    let mut dest = fs::File::create(&zip_path).unwrap();
+    std::io::copy(&mut content.as_ref(), &mut dest).unwrap();
+
+    let mut archive = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
 
     for i in 0..archive.len() {
         let mut file = archive.by_index(i).unwrap();
@@ -197,7 +679,7 @@ fn download_tdlib() {
                     std::fs::create_dir_all(p).unwrap();
                 }
             }
-            let mut outfile = File::create(&outpath).unwrap();
+            let mut outfile = fs::File::create(&outpath).unwrap();
             std::io::copy(&mut file, &mut outfile).unwrap();
         }
 
@@ -212,6 +694,14 @@ fn download_tdlib() {
     }
 
     let _ = std::fs::remove_file(&zip_path);
+    std::fs::write(&stamp_path, format!("{url}\n{sha256}")).unwrap();
+}
+
+#[cfg(feature = "download-tdlib")]
+/// Compute the lowercase hexadecimal SHA-256 digest of `bytes`.
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
 }
 
 fn main() -> std::io::Result<()> {
@@ -227,15 +717,137 @@ fn main() -> std::io::Result<()> {
     compile_error!(
         "feature \"pkg-config\" and feature \"download-tdlib\" cannot be enabled at the same time"
     );
+    #[cfg(all(
+        feature = "vcpkg",
+        any(
+            feature = "docs",
+            feature = "pkg-config",
+            feature = "download-tdlib",
+            feature = "local-tdlib"
+        )
+    ))]
+    compile_error!(
+        "feature \"vcpkg\" cannot be enabled together with the \"docs\", \"pkg-config\", \"download-tdlib\" or \"local-tdlib\" features"
+    );
+    #[cfg(all(
+        feature = "vendored",
+        any(
+            feature = "docs",
+            feature = "pkg-config",
+            feature = "download-tdlib",
+            feature = "local-tdlib",
+            feature = "vcpkg",
+            feature = "dynamic-loading"
+        )
+    ))]
+    compile_error!(
+        "feature \"vendored\" cannot be enabled together with the \"docs\", \"pkg-config\", \"download-tdlib\", \"local-tdlib\", \"vcpkg\" or \"dynamic-loading\" features"
+    );
+    #[cfg(all(
+        feature = "wasm",
+        any(
+            feature = "pkg-config",
+            feature = "download-tdlib",
+            feature = "local-tdlib",
+            feature = "vcpkg",
+            feature = "vendored",
+            feature = "dynamic-loading",
+            feature = "stub"
+        )
+    ))]
+    compile_error!(
+        "feature \"wasm\" cannot be enabled together with the \"pkg-config\", \"download-tdlib\", \"local-tdlib\", \"vcpkg\", \"vendored\", \"dynamic-loading\" or \"stub\" features"
+    );
+    #[cfg(all(feature = "stub", feature = "dynamic-loading"))]
+    compile_error!(
+        "feature \"stub\" and feature \"dynamic-loading\" cannot be enabled at the same time"
+    );
+    #[cfg(all(
+        feature = "zeroize",
+        any(
+            feature = "gpui",
+            feature = "cow-string",
+            feature = "arc-str",
+            feature = "bytes"
+        )
+    ))]
+    compile_error!(
+        "feature \"zeroize\" cannot be enabled together with the \"gpui\", \"cow-string\", \"arc-str\" or \"bytes\" features"
+    );
+    #[cfg(all(feature = "time", feature = "chrono"))]
+    compile_error!("feature \"time\" and feature \"chrono\" cannot be enabled at the same time");
 
     println!("cargo:rerun-if-changed=build.rs");
 
     #[cfg(feature = "local-tdlib")]
     println!("cargo:rerun-if-env-changed=LOCAL_TDLIB_PATH");
 
+    #[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+    {
+        println!("cargo:rerun-if-env-changed=ANDROID_NDK_HOME");
+        println!("cargo:rerun-if-env-changed=ANDROID_NDK_ROOT");
+    }
+
+    #[cfg(feature = "download-tdlib")]
+    {
+        println!("cargo:rerun-if-env-changed=TDLIB_DOWNLOAD_URL");
+        println!("cargo:rerun-if-env-changed=TDLIB_DOWNLOAD_SHA256");
+    }
+
+    #[cfg(feature = "vcpkg")]
+    {
+        println!("cargo:rerun-if-env-changed=VCPKG_ROOT");
+        println!("cargo:rerun-if-env-changed=VCPKGRS_TRIPLET");
+    }
+
+    #[cfg(feature = "vendored")]
+    println!("cargo:rerun-if-env-changed=TDLIB_SOURCE_DIR");
+
+    #[cfg(feature = "static-tdlib")]
+    println!("cargo:rerun-if-env-changed=OPENSSL_DIR");
+
+    // The downloaded and the vendored TDLib are always the version of the default schema
+    #[cfg(any(feature = "download-tdlib", feature = "vendored"))]
+    if let Some(version) = api_version().filter(|version| version != TDLIB_VERSION) {
+        println!(
+            "cargo:warning=The bindings are generated for TDLib {version}, but TDLib {TDLIB_VERSION} is linked"
+        );
+    }
+
+    // The stub backend compiles the tdjson functions as panicking stubs, so that nothing has to
+    // be linked. It is enabled by the `stub` feature, and automatically on docs.rs
+    println!("cargo:rustc-check-cfg=cfg(tdjson_stub)");
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+    let stub = cfg!(feature = "stub")
+        || (env::var_os("DOCS_RS").is_some()
+            && !cfg!(any(feature = "dynamic-loading", feature = "wasm")));
+    if stub {
+        println!("cargo:rustc-cfg=tdjson_stub");
+    }
+
+    // The name of the tdjson library, which the builds of TDLight may change
+    #[cfg(feature = "tdlight")]
+    println!("cargo:rerun-if-env-changed=TDJSON_LIB_NAME");
+    let lib_name = match env::var("TDJSON_LIB_NAME") {
+        Ok(lib_name) if cfg!(feature = "tdlight") => lib_name,
+        _ => "tdjson".to_string(),
+    };
+    println!("cargo:rustc-env=TDJSON_LIB_NAME={lib_name}");
+    if lib_name != "tdjson"
+        && !stub
+        && !cfg!(any(
+            feature = "docs",
+            feature = "vendored",
+            feature = "dynamic-loading",
+            feature = "wasm"
+        ))
+    {
+        println!("cargo:rustc-link-lib=dylib=tdjson:{lib_name}");
+    }
+
     // Prevent linking libraries to avoid documentation failure
     #[cfg(not(feature = "docs"))]
-    {
+    if !stub {
         // It requires the following variables to be set:
         // - export PKG_CONFIG_PATH=$HOME/lib/tdlib/lib/pkgconfig/:$PKG_CONFIG_PATH
         // - export LD_LIBRARY_PATH=$HOME/lib/tdlib/lib/:$LD_LIBRARY_PATH
@@ -247,26 +859,90 @@ fn main() -> std::io::Result<()> {
 
         // It requires the following variable to be set:
         // - export LOCAL_TDLIB_PATH=$HOME/lib/tdlib
+        // unless the tdlib is installed with Homebrew on macOS
         #[cfg(feature = "local-tdlib")]
         copy_local_tdlib();
 
         #[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
         generic_build();
+
+        // It requires `vcpkg install tdlib` to be run, and the following variable to be set:
+        // - set VCPKG_ROOT=C:\vcpkg
+        #[cfg(feature = "vcpkg")]
+        probe_vcpkg();
+
+        #[cfg(feature = "vendored")]
+        build_vendored_tdlib();
+
+        #[cfg(not(any(
+            feature = "pkg-config",
+            feature = "download-tdlib",
+            feature = "local-tdlib",
+            feature = "vcpkg",
+            feature = "vendored",
+            feature = "dynamic-loading",
+            feature = "wasm"
+        )))]
+        probe_system_tdlib();
     }
 
     let out_dir = env::var("OUT_DIR").unwrap();
 
-    let definitions = load_tl("tl/api.tl")?;
-
-    let mut file = BufWriter::new(File::create(Path::new(&out_dir).join("generated.rs"))?);
+    #[allow(unused_mut)]
+    let mut schemas = vec![read_tl(&api_tl_file())?];
+    // The functions and types that TDLight adds on top of TDLib
+    #[cfg(feature = "tdlight")]
+    schemas.push(read_tl("tl/tdlight.tl")?);
 
     let config = GeneratorConfig {
         gen_bots_only_api: cfg!(feature = "bots-only-api"),
-        use_shared_string: cfg!(feature = "gpui"),
+        use_shared_string: cfg!(any(
+            feature = "gpui",
+            feature = "cow-string",
+            feature = "arc-str"
+        )),
+        box_variants_threshold: cfg!(feature = "box-large-variants")
+            .then_some(BOX_VARIANTS_THRESHOLD),
+        gate_api_groups: true,
+        gen_unrecognized_variants: cfg!(feature = "unrecognized-variants"),
+        use_bytes: cfg!(feature = "bytes"),
+        use_path_buf: cfg!(feature = "path-buf"),
+        unix_time_type: if cfg!(feature = "time") {
+            Some(UnixTimeType::Time)
+        } else if cfg!(feature = "chrono") {
+            Some(UnixTimeType::Chrono)
+        } else {
+            None
+        },
+        use_durations: cfg!(feature = "duration"),
+        debug_bytes_limit: Some(DEBUG_BYTES_LIMIT),
+        redact_sensitive_fields: !cfg!(feature = "unredacted-debug"),
+        derive_zeroize: cfg!(feature = "zeroize"),
+        derive_json_schema: cfg!(feature = "schemars"),
+        derive_arbitrary: cfg!(feature = "arbitrary"),
+        gen_roundtrip_tests: cfg!(feature = "roundtrip-tests"),
+        api_version: Some(api_version().unwrap_or_else(|| TDLIB_VERSION.to_string())),
+        gen_blocking_functions: cfg!(feature = "blocking"),
+        gen_update_conversions: true,
+        gen_rate_limits: true,
     };
-    generate_rust_code_with_config(&mut file, &definitions, config)?;
 
-    file.flush()?;
+    // Skip the generation when nothing it depends on changed since the last
+    // one, e.g. when the build script only reruns for the linking. The
+    // generator doesn't rewrite the unchanged files anyway, so that the crate
+    // isn't rebuilt when the code is regenerated
+    let out_dir = Path::new(&out_dir);
+    let hash_file = out_dir.join("generated.hash");
+    let hash = generation_hash(&schemas, &config);
+    if out_dir.join("generated.rs").exists()
+        && fs::read_to_string(&hash_file).is_ok_and(|previous| previous == hash)
+    {
+        return Ok(());
+    }
+
+    let definitions: Vec<Definition> = schemas.iter().flat_map(load_tl).collect();
+    generate_rust_code_split(out_dir, &definitions, config)?;
+    fs::write(hash_file, hash)?;
 
     Ok(())
 }
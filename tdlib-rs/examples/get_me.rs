@@ -35,7 +35,7 @@ async fn handle_authorization_state(
             AuthorizationState::WaitTdlibParameters => {
                 let response = functions::set_tdlib_parameters(
                     false,
-                    "get_me_db".into(),
+                    "get_me_db",
                     String::new(),
                     String::new(),
                     false,
@@ -43,17 +43,17 @@ async fn handle_authorization_state(
                     false,
                     false,
                     env!("API_ID").parse().unwrap(),
-                    env!("API_HASH").into(),
-                    "en".into(),
-                    "Desktop".into(),
+                    env!("API_HASH"),
+                    "en",
+                    "Desktop",
                     String::new(),
-                    env!("CARGO_PKG_VERSION").into(),
+                    env!("CARGO_PKG_VERSION"),
                     client_id,
                 )
                 .await;
 
                 if let Err(error) = response {
-                    println!("{}", error.message);
+                    println!("{error}");
                 }
             }
             AuthorizationState::WaitPhoneNumber => loop {
@@ -62,7 +62,7 @@ async fn handle_authorization_state(
                     functions::set_authentication_phone_number(input, None, client_id).await;
                 match response {
                     Ok(_) => break,
-                    Err(e) => println!("{}", e.message),
+                    Err(e) => println!("{e}"),
                 }
             },
             AuthorizationState::WaitOtherDeviceConfirmation(x) => {
@@ -77,7 +77,7 @@ async fn handle_authorization_state(
                     functions::set_authentication_email_address(email_address, client_id).await;
                 match response {
                     Ok(_) => break,
-                    Err(e) => println!("{}", e.message),
+                    Err(e) => println!("{e}"),
                 }
             }
             AuthorizationState::WaitEmailCode(_x) => {
@@ -91,7 +91,7 @@ async fn handle_authorization_state(
                 .await;
                 match response {
                     Ok(_) => break,
-                    Err(e) => println!("{}", e.message),
+                    Err(e) => println!("{e}"),
                 }
             }
 
@@ -100,7 +100,7 @@ async fn handle_authorization_state(
                 let response = functions::check_authentication_code(input, client_id).await;
                 match response {
                     Ok(_) => break,
-                    Err(e) => println!("{}", e.message),
+                    Err(e) => println!("{e}"),
                 }
             },
             AuthorizationState::WaitRegistration(_x) => {
@@ -180,7 +180,7 @@ async fn main() {
 
     // Run the get_me() method to get user information
     let User::User(me) = functions::get_me(client_id).await.unwrap();
-    println!("Hi, I'm {}", me.first_name);
+    println!("Hi, I'm {me}");
 
     // Tell the client to close
     functions::close(client_id).await.unwrap();
@@ -0,0 +1,35 @@
+//! Feeds arbitrary bytes to `receive` as if they were returned by TDLib, to
+//! check that no response or update makes it panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::sync::{Mutex, Once};
+use tdlib_rs::Transport;
+
+static INPUT: Mutex<Option<String>> = Mutex::new(None);
+static INSTALL: Once = Once::new();
+
+/// Returns the fuzz input as the next response.
+struct FuzzInput;
+
+impl Transport for FuzzInput {
+    fn create_client(&self) -> i32 {
+        1
+    }
+
+    fn send(&self, _client_id: i32, _request: String) {}
+
+    fn receive(&self, _timeout: f64) -> Option<String> {
+        INPUT.lock().unwrap().take()
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    INSTALL.call_once(|| {
+        tdlib_rs::set_transport(FuzzInput);
+    });
+    if let Ok(input) = std::str::from_utf8(data) {
+        *INPUT.lock().unwrap() = Some(input.to_string());
+        let _ = tdlib_rs::receive();
+    }
+});
@@ -0,0 +1,15 @@
+//! Deserializes arbitrary JSON as the major types returned by the functions.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tdlib_rs::{enums, types};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<types::Message>(data);
+    let _ = serde_json::from_slice::<types::Chat>(data);
+    let _ = serde_json::from_slice::<types::User>(data);
+    let _ = serde_json::from_slice::<enums::MessageContent>(data);
+    let _ = serde_json::from_slice::<enums::AuthorizationState>(data);
+    let _ = serde_json::from_slice::<types::FormattedText>(data);
+    let _ = serde_json::from_slice::<types::Error>(data);
+});
@@ -0,0 +1,9 @@
+//! Deserializes arbitrary JSON as an `Update`, the most received object.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tdlib_rs::enums::Update;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<Update>(data);
+});
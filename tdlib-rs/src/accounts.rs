@@ -0,0 +1,329 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The accounts of a multi-account application, each with its own client and
+//! its own database and files directories.
+//!
+//! [`AccountManager`] creates the clients, answers their authorization steps
+//! with an [`Authenticator`], and merges their updates into a single stream
+//! tagged with the name of the account.
+use crate::enums::{AuthorizationState, Update};
+use crate::{functions, updates, Client, TdError, TdString};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// The parameters shared by the clients of the accounts.
+#[derive(Clone, Debug)]
+pub struct AccountsConfig {
+    /// The directory of the accounts, each using the subdirectory named
+    /// after it.
+    pub base_directory: PathBuf,
+    /// The application identifier, from <https://my.telegram.org>.
+    pub api_id: i32,
+    /// The application identifier hash, from <https://my.telegram.org>.
+    pub api_hash: TdString,
+    /// The IETF language tag of the user's language, e.g. `en`.
+    pub system_language_code: TdString,
+    /// The model of the device the application runs on.
+    pub device_model: TdString,
+    /// The version of the application.
+    pub application_version: TdString,
+    /// Whether to use the Telegram test environment.
+    pub use_test_dc: bool,
+}
+
+impl AccountsConfig {
+    /// The parameters of the accounts stored in `base_directory`, using the
+    /// application `api_id` and `api_hash`.
+    pub fn new(
+        base_directory: impl Into<PathBuf>,
+        api_id: i32,
+        api_hash: impl Into<TdString>,
+    ) -> Self {
+        Self {
+            base_directory: base_directory.into(),
+            api_id,
+            api_hash: api_hash.into(),
+            system_language_code: TdString::from("en"),
+            device_model: TdString::from("Desktop"),
+            application_version: TdString::from(env!("CARGO_PKG_VERSION")),
+            use_test_dc: false,
+        }
+    }
+}
+
+/// The answers to the authorization steps of the accounts, e.g. asked to the
+/// user, each called with the name of the account.
+pub trait Authenticator {
+    /// The token of the bot to log in as, instead of a user, if any.
+    fn bot_token(&mut self, _account: &str) -> Option<String> {
+        None
+    }
+
+    /// The phone number of the user, with the country calling code.
+    fn phone_number(&mut self, account: &str) -> String;
+
+    /// The code sent to the user.
+    fn code(&mut self, account: &str) -> String;
+
+    /// The password of the user, when two-step verification is enabled.
+    fn password(&mut self, account: &str) -> String;
+}
+
+/// An account managed by [`AccountManager`].
+#[derive(Clone, Debug)]
+pub struct Account {
+    /// The name of the account, which is also the name of its directory.
+    pub name: Arc<str>,
+    /// The client of the account.
+    pub client: Client,
+}
+
+/// The accounts of the application, e.g.
+///
+/// ```ignore
+/// let mut accounts = AccountManager::new(AccountsConfig::new("accounts", api_id, api_hash));
+/// accounts.add("work").await?;
+/// accounts.add("personal").await?;
+/// accounts.authorize("work", &mut authenticator).await?;
+/// accounts.authorize("personal", &mut authenticator).await?;
+/// let mut updates = accounts.updates();
+/// while let Some(update) = updates.next().await {
+///     println!("{}: {:?}", update.account, update.update);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AccountManager {
+    config: AccountsConfig,
+    accounts: Vec<Account>,
+}
+
+impl AccountManager {
+    /// A manager without accounts.
+    pub fn new(config: AccountsConfig) -> Self {
+        Self {
+            config,
+            accounts: Vec::new(),
+        }
+    }
+
+    /// The accounts, in the order they were added.
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    /// The client of the account `name`, if added.
+    pub fn client(&self, name: &str) -> Option<Client> {
+        let account = self
+            .accounts
+            .iter()
+            .find(|account| &*account.name == name)?;
+        Some(account.client)
+    }
+
+    /// The directory of the account `name`.
+    pub fn directory(&self, name: &str) -> PathBuf {
+        self.config.base_directory.join(name)
+    }
+
+    /// Add the account `name`, creating its client and setting its
+    /// parameters, its database being stored in `<name>/database` and its
+    /// files in `<name>/files`.
+    pub async fn add(&mut self, name: &str) -> Result<Client, TdError> {
+        if self.client(name).is_some() {
+            return Err(TdError::invalid("Account already added"));
+        }
+        let client = Client::create();
+        // The first request starts the client
+        functions::get_authorization_state(client.id()).await?;
+        self.set_parameters(name, client.id()).await?;
+        self.accounts.push(Account {
+            name: name.into(),
+            client,
+        });
+        Ok(client)
+    }
+
+    async fn set_parameters(&self, name: &str, client_id: i32) -> Result<(), TdError> {
+        let directory = self.directory(name);
+        // A `String` is converted to both the `PathBuf` and the `TdString`
+        // paths
+        let path = |subdirectory: &str| directory.join(subdirectory).to_string_lossy().into_owned();
+        let config = &self.config;
+        functions::set_tdlib_parameters(
+            config.use_test_dc,
+            path("database"),
+            path("files"),
+            "",
+            true,
+            true,
+            true,
+            true,
+            config.api_id,
+            config.api_hash.clone(),
+            config.system_language_code.clone(),
+            config.device_model.clone(),
+            "",
+            config.application_version.clone(),
+            #[cfg(feature = "api-1.8.19")]
+            true,
+            #[cfg(feature = "api-1.8.19")]
+            false,
+            client_id,
+        )
+        .await
+    }
+
+    /// Answer the authorization steps of the account `name` with
+    /// `authenticator` until it's authorized.
+    ///
+    /// An error, e.g. for a wrong code, is returned as is, and calling the
+    /// function again resumes the authorization from the current step. The
+    /// steps of the new users, who must register, aren't supported.
+    pub async fn authorize(
+        &self,
+        name: &str,
+        authenticator: &mut impl Authenticator,
+    ) -> Result<(), TdError> {
+        let client_id = self
+            .client(name)
+            .ok_or_else(|| TdError::invalid("Account not found"))?
+            .id();
+        // Subscribe before getting the state, so that no step is missed
+        let mut states = updates::subscribe(client_id, |update| {
+            matches!(update, Update::AuthorizationState(_))
+        });
+        let mut state = functions::get_authorization_state(client_id).await?;
+        loop {
+            match state {
+                AuthorizationState::Ready => return Ok(()),
+                AuthorizationState::WaitTdlibParameters => {
+                    self.set_parameters(name, client_id).await?;
+                }
+                AuthorizationState::WaitPhoneNumber => match authenticator.bot_token(name) {
+                    Some(token) => {
+                        functions::check_authentication_bot_token(token, client_id).await?;
+                    }
+                    None => {
+                        let phone_number = authenticator.phone_number(name);
+                        functions::set_authentication_phone_number(phone_number, None, client_id)
+                            .await?;
+                    }
+                },
+                AuthorizationState::WaitCode(_) => {
+                    let code = authenticator.code(name);
+                    functions::check_authentication_code(code, client_id).await?;
+                }
+                AuthorizationState::WaitPassword(_) => {
+                    let password = authenticator.password(name);
+                    functions::check_authentication_password(password, client_id).await?;
+                }
+                AuthorizationState::LoggingOut
+                | AuthorizationState::Closing
+                | AuthorizationState::Closed => return Err(TdError::Canceled),
+                _ => return Err(TdError::invalid("Unsupported authorization state")),
+            }
+            state = loop {
+                match poll_fn(|cx| Pin::new(&mut states).poll_next(cx)).await {
+                    Some(Update::AuthorizationState(update)) => break update.authorization_state,
+                    Some(_) => {}
+                    // The client was closed
+                    None => return Err(TdError::Canceled),
+                }
+            };
+        }
+    }
+
+    /// Close the client of the account `name` and remove the account, its
+    /// directory being kept.
+    pub async fn remove(&mut self, name: &str) -> Result<(), TdError> {
+        let client = self
+            .client(name)
+            .ok_or_else(|| TdError::invalid("Account not found"))?;
+        functions::close(client.id()).await?;
+        self.accounts.retain(|account| &*account.name != name);
+        Ok(())
+    }
+
+    /// Stream the updates of the accounts, tagged with their name.
+    ///
+    /// The stream only includes the accounts added so far, and ends once all
+    /// of them are closed.
+    pub fn updates(&self) -> AccountUpdates {
+        let receivers = self.accounts.iter().map(|account| {
+            let updates = updates::subscribe(account.client.id(), |_| true);
+            (account.clone(), updates)
+        });
+        AccountUpdates {
+            receivers: receivers.collect(),
+            next: 0,
+        }
+    }
+}
+
+/// An update of an account, streamed by [`AccountUpdates`].
+#[derive(Clone, Debug)]
+pub struct AccountUpdate {
+    /// The name of the account.
+    pub account: Arc<str>,
+    /// The client of the account.
+    pub client: Client,
+    /// The update.
+    pub update: Update,
+}
+
+/// A stream of the updates of the accounts returned by
+/// [`AccountManager::updates`].
+pub struct AccountUpdates {
+    receivers: Vec<(Account, mpsc::UnboundedReceiver<Update>)>,
+    /// The index of the account polled first, so that a busy account doesn't
+    /// hold back the others.
+    next: usize,
+}
+
+impl Stream for AccountUpdates {
+    type Item = AccountUpdate;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let count = self.receivers.len();
+        let mut ready = None;
+        let mut closed = Vec::new();
+        for offset in 0..count {
+            let position = (self.next + offset) % count;
+            let (account, receiver) = &mut self.receivers[position];
+            match Pin::new(receiver).poll_next(cx) {
+                Poll::Ready(Some(update)) => {
+                    ready = Some(AccountUpdate {
+                        account: account.name.clone(),
+                        client: account.client,
+                        update,
+                    });
+                    self.next = position + 1;
+                    break;
+                }
+                // The client was closed
+                Poll::Ready(None) => closed.push(position),
+                Poll::Pending => {}
+            }
+        }
+        closed.sort_unstable();
+        for position in closed.into_iter().rev() {
+            self.receivers.remove(position);
+        }
+        match ready {
+            Some(update) => Poll::Ready(Some(update)),
+            None if self.receivers.is_empty() => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
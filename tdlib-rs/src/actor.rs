@@ -0,0 +1,188 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The [`ClientActor`], a task owning a client which runs the commands sent
+//! through its [`ActorHandle`]s one at a time and emits its updates, so that
+//! the tasks of the application share the handles instead of the client id.
+use crate::enums::Update;
+use crate::{functions, updates, Client, TdError};
+use futures_channel::{mpsc, oneshot};
+use futures_core::Stream;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A command run by the actor.
+type Command = Box<dyn FnOnce(Client) -> CommandFuture + Send>;
+
+/// The future of a command, returning the error of the commands sent without
+/// waiting for their result.
+type CommandFuture = Pin<Box<dyn Future<Output = Option<TdError>> + Send>>;
+
+/// An event emitted by a [`ClientActor`], streamed by [`ActorEvents`].
+#[derive(Debug)]
+pub enum ActorEvent {
+    /// An update of the client, boxed since the updates are large.
+    Update(Box<Update>),
+    /// A command sent with [`ActorHandle::send`] failed.
+    Failed(TdError),
+}
+
+/// A task owning a client, e.g.
+///
+/// ```ignore
+/// let (actor, handle, mut events) = ClientActor::new(Client::create());
+/// tokio::spawn(actor.run());
+/// let me = handle.call(|client| functions::get_me(client.id())).await?;
+/// while let Some(event) = events.next().await {
+///     // Handle the event
+/// }
+/// ```
+///
+/// The commands run one at a time, in the order they were sent, while the
+/// updates received meanwhile are queued.
+pub struct ClientActor {
+    client: Client,
+    commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::UnboundedSender<ActorEvent>,
+}
+
+/// A handle sending commands to a [`ClientActor`], cheap to clone.
+#[derive(Clone)]
+pub struct ActorHandle {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+/// A stream of the events emitted by a [`ClientActor`], ending when the
+/// actor stops.
+pub struct ActorEvents {
+    events: mpsc::UnboundedReceiver<ActorEvent>,
+}
+
+/// What the actor is woken up by.
+enum Next {
+    Update(Option<Box<Update>>),
+    Command(Option<Command>),
+}
+
+impl ClientActor {
+    /// The actor owning `client`, with a handle to send it commands and the
+    /// stream of its events.
+    ///
+    /// The actor does nothing until [`run`](Self::run) is spawned.
+    pub fn new(client: Client) -> (Self, ActorHandle, ActorEvents) {
+        let (command_sender, commands) = mpsc::unbounded();
+        let (events, event_receiver) = mpsc::unbounded();
+        let actor = Self {
+            client,
+            commands,
+            events,
+        };
+        let handle = ActorHandle {
+            commands: command_sender,
+        };
+        let events = ActorEvents {
+            events: event_receiver,
+        };
+        (actor, handle, events)
+    }
+
+    /// Run the commands and emit the updates of the client until it's
+    /// closed, closing it once all the handles are dropped.
+    pub async fn run(mut self) {
+        let client_id = self.client.id();
+        let mut updates = updates::subscribe(client_id, |_| true);
+        let mut is_closing = false;
+        loop {
+            let next = poll_fn(|cx: &mut Context<'_>| {
+                if let Poll::Ready(update) = Pin::new(&mut updates).poll_next(cx) {
+                    return Poll::Ready(Next::Update(update.map(Box::new)));
+                }
+                if is_closing {
+                    return Poll::Pending;
+                }
+                Pin::new(&mut self.commands)
+                    .poll_next(cx)
+                    .map(Next::Command)
+            })
+            .await;
+            match next {
+                Next::Update(Some(update)) => {
+                    let _ = self.events.unbounded_send(ActorEvent::Update(update));
+                }
+                // The client was closed
+                Next::Update(None) => break,
+                Next::Command(Some(command)) => {
+                    if let Some(error) = command(self.client).await {
+                        let _ = self.events.unbounded_send(ActorEvent::Failed(error));
+                    }
+                }
+                // Keep emitting the updates until the client is closed
+                Next::Command(None) => {
+                    is_closing = true;
+                    let _ = functions::close(client_id).await;
+                }
+            }
+        }
+    }
+}
+
+impl ActorHandle {
+    /// Run `command` on the actor, waiting for its result.
+    ///
+    /// Fails with [`TdError::Canceled`] if the actor stopped before running
+    /// it.
+    pub async fn call<T, F, Fut>(&self, command: F) -> Result<T, TdError>
+    where
+        F: FnOnce(Client) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, TdError>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        self.push(Box::new(move |client| -> CommandFuture {
+            Box::pin(async move {
+                let _ = sender.send(command(client).await);
+                None
+            })
+        }))?;
+        receiver.await.unwrap_or(Err(TdError::Canceled))
+    }
+
+    /// Run `command` on the actor without waiting for it, its error being
+    /// emitted as [`ActorEvent::Failed`].
+    ///
+    /// Fails with [`TdError::Canceled`] if the actor already stopped.
+    pub fn send<F, Fut>(&self, command: F) -> Result<(), TdError>
+    where
+        F: FnOnce(Client) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), TdError>> + Send + 'static,
+    {
+        self.push(Box::new(move |client| -> CommandFuture {
+            Box::pin(async move { command(client).await.err() })
+        }))
+    }
+
+    /// Whether the actor stopped.
+    pub fn is_closed(&self) -> bool {
+        self.commands.is_closed()
+    }
+
+    fn push(&self, command: Command) -> Result<(), TdError> {
+        self.commands
+            .unbounded_send(command)
+            .map_err(|_| TdError::Canceled)
+    }
+}
+
+impl Stream for ActorEvents {
+    type Item = ActorEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
@@ -0,0 +1,18 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers for the bots, enabled by the `bots-only-api` feature.
+mod command;
+mod inline;
+mod keyboard;
+#[cfg(feature = "payments")]
+pub mod payments;
+
+pub use command::BotCommand;
+pub use inline::{ArticleResult, DocumentResult, PhotoResult};
+pub use keyboard::{force_reply, remove_keyboard, InlineKeyboard, ReplyKeyboard};
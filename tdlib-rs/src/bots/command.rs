@@ -0,0 +1,131 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The commands sent to the bots, e.g. `/start@examplebot payload`.
+use crate::enums::{MessageContent, TextEntityType};
+use crate::{types, utf16};
+
+/// A command starting a text message, found from its
+/// `textEntityTypeBotCommand` entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BotCommand<'a> {
+    /// The name of the command, without the `/`, e.g. `start`.
+    pub name: &'a str,
+    /// The username of the bot the command is sent to, if mentioned, e.g.
+    /// `examplebot` in `/start@examplebot`.
+    pub bot_username: Option<&'a str>,
+    /// The text following the command, without the leading whitespace.
+    pub args: &'a str,
+}
+
+impl<'a> BotCommand<'a> {
+    /// The command starting `text`, if any.
+    pub fn parse(text: &'a types::FormattedText) -> Option<Self> {
+        let plain: &str = &text.text;
+        let entity = text.entities.iter().find(|entity| {
+            entity.offset == 0 && matches!(entity.r#type, TextEntityType::BotCommand)
+        })?;
+        let command = utf16::substring(plain, 0, entity.length).strip_prefix('/')?;
+        let (name, bot_username) = match command.split_once('@') {
+            Some((name, bot_username)) => (name, Some(bot_username)),
+            None => (command, None),
+        };
+        Some(Self {
+            name,
+            bot_username,
+            args: plain[command.len() + 1..].trim_start(),
+        })
+    }
+
+    /// The command starting the text of `message`, if any.
+    pub fn from_message(message: &'a types::Message) -> Option<Self> {
+        match &message.content {
+            MessageContent::MessageText(content) => Self::parse(&content.text),
+            _ => None,
+        }
+    }
+
+    /// Whether the command is sent to the bot `bot_username`, i.e. it mentions
+    /// no bot, or this one.
+    pub fn is_for(&self, bot_username: &str) -> bool {
+        self.bot_username
+            .is_none_or(|username| username.eq_ignore_ascii_case(bot_username))
+    }
+
+    /// The arguments of the command separated by whitespace.
+    pub fn split_args(&self) -> impl Iterator<Item = &'a str> {
+        self.args.split_whitespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A text starting with a command entity of `length` UTF-16 code units.
+    // The text is a `TdString`, which may be a `String`
+    #[allow(clippy::useless_conversion)]
+    fn command(text: &str, length: i32) -> types::FormattedText {
+        types::FormattedText {
+            text: text.to_owned().into(),
+            entities: vec![types::TextEntity {
+                offset: 0,
+                length,
+                r#type: TextEntityType::BotCommand,
+            }],
+        }
+    }
+
+    #[test]
+    fn check_parse() {
+        let text = command("/start@ExampleBot  payload  two", 17);
+        let command = BotCommand::parse(&text).unwrap();
+        assert_eq!(command.name, "start");
+        assert_eq!(command.bot_username, Some("ExampleBot"));
+        assert_eq!(command.args, "payload  two");
+        assert_eq!(command.split_args().collect::<Vec<_>>(), ["payload", "two"]);
+    }
+
+    #[test]
+    fn check_parse_without_bot() {
+        let text = command("/help", 5);
+        let command = BotCommand::parse(&text).unwrap();
+        assert_eq!(command.name, "help");
+        assert_eq!(command.bot_username, None);
+        assert_eq!(command.args, "");
+        assert_eq!(command.split_args().count(), 0);
+    }
+
+    #[test]
+    fn check_parse_emoji_args() {
+        let text = command("/echo\n😀 a", 5);
+        let command = BotCommand::parse(&text).unwrap();
+        assert_eq!(command.name, "echo");
+        assert_eq!(command.args, "😀 a");
+    }
+
+    #[test]
+    fn check_parse_no_command() {
+        assert_eq!(BotCommand::parse(&command("start", 5)), None);
+        let mut text = command("hi /start", 6);
+        text.entities[0].offset = 3;
+        assert_eq!(BotCommand::parse(&text), None);
+        text.entities.clear();
+        assert_eq!(BotCommand::parse(&text), None);
+    }
+
+    #[test]
+    fn check_is_for() {
+        let mentioning = command("/start@ExampleBot", 17);
+        let mentioning = BotCommand::parse(&mentioning).unwrap();
+        assert!(mentioning.is_for("examplebot"));
+        assert!(!mentioning.is_for("otherbot"));
+        let unmentioning = command("/start", 6);
+        assert!(BotCommand::parse(&unmentioning).unwrap().is_for("otherbot"));
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The builders of the results of the inline queries, answered with
+//! `answerInlineQuery`.
+use crate::enums::{InputFile, InputInlineQueryResult, InputMessageContent, ReplyMarkup};
+use crate::{types, TdString};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A new id for a result, unique in the process.
+// The id is a `TdString`, which may be a `String`
+#[allow(clippy::useless_conversion)]
+fn next_id() -> TdString {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed).to_string().into()
+}
+
+/// The fields shared by the results.
+#[derive(Clone, Debug)]
+struct Common {
+    id: TdString,
+    title: TdString,
+    description: TdString,
+    reply_markup: Option<ReplyMarkup>,
+}
+
+impl Common {
+    fn new(title: TdString) -> Self {
+        Self {
+            id: next_id(),
+            title,
+            description: TdString::from(""),
+            reply_markup: None,
+        }
+    }
+}
+
+/// Implement the setters of the fields shared by the results.
+macro_rules! common_setters {
+    () => {
+        /// Set the id of the result, unique among the results of the query,
+        /// instead of the generated one.
+        pub fn id(mut self, id: impl Into<TdString>) -> Self {
+            self.common.id = id.into();
+            self
+        }
+
+        /// Set the description of the result, shown below its title.
+        pub fn description(mut self, description: impl Into<TdString>) -> Self {
+            self.common.description = description.into();
+            self
+        }
+
+        /// Set the reply markup of the message sent, e.g. an
+        /// [`InlineKeyboard`](super::InlineKeyboard).
+        pub fn reply_markup(mut self, reply_markup: impl Into<ReplyMarkup>) -> Self {
+            self.common.reply_markup = Some(reply_markup.into());
+            self
+        }
+    };
+}
+
+/// The builder of an article result, sending `content` when chosen.
+#[derive(Clone, Debug)]
+pub struct ArticleResult {
+    common: Common,
+    content: InputMessageContent,
+    url: TdString,
+    thumbnail: Option<(TdString, i32, i32)>,
+}
+
+impl ArticleResult {
+    /// An article titled `title`, sending `content`, e.g. a text built with
+    /// [`InputMessageContent::text`].
+    pub fn new(title: impl Into<TdString>, content: InputMessageContent) -> Self {
+        Self {
+            common: Common::new(title.into()),
+            content,
+            url: TdString::from(""),
+            thumbnail: None,
+        }
+    }
+
+    common_setters!();
+
+    /// Set the URL of the article.
+    pub fn url(mut self, url: impl Into<TdString>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Set the URL of the thumbnail of the article, and its size if known.
+    pub fn thumbnail(mut self, url: impl Into<TdString>, width: i32, height: i32) -> Self {
+        self.thumbnail = Some((url.into(), width, height));
+        self
+    }
+
+    /// Build the result.
+    // The variants are only boxed by the `box-large-variants` feature
+    #[allow(clippy::useless_conversion)]
+    pub fn build(self) -> InputInlineQueryResult {
+        let (thumbnail_url, thumbnail_width, thumbnail_height) =
+            self.thumbnail.unwrap_or((TdString::from(""), 0, 0));
+        let result = types::InputInlineQueryResultArticle {
+            id: self.common.id,
+            url: self.url,
+            #[cfg(feature = "api-1.8.19")]
+            hide_url: false,
+            title: self.common.title,
+            description: self.common.description,
+            thumbnail_url,
+            thumbnail_width,
+            thumbnail_height,
+            reply_markup: self.common.reply_markup,
+            input_message_content: self.content,
+        };
+        InputInlineQueryResult::Article(result.into())
+    }
+}
+
+/// The builder of a photo result, sending the photo when chosen unless
+/// another content is set.
+#[derive(Clone, Debug)]
+pub struct PhotoResult {
+    common: Common,
+    photo_url: TdString,
+    thumbnail_url: TdString,
+    width: i32,
+    height: i32,
+    caption: Option<types::FormattedText>,
+    content: Option<InputMessageContent>,
+}
+
+impl PhotoResult {
+    /// The JPEG photo at `photo_url`, which is also its thumbnail unless
+    /// [`PhotoResult::thumbnail`] is called.
+    pub fn new(photo_url: impl Into<TdString>) -> Self {
+        let photo_url = photo_url.into();
+        Self {
+            common: Common::new(TdString::from("")),
+            thumbnail_url: photo_url.clone(),
+            photo_url,
+            width: 0,
+            height: 0,
+            caption: None,
+            content: None,
+        }
+    }
+
+    common_setters!();
+
+    /// Set the title of the result.
+    pub fn title(mut self, title: impl Into<TdString>) -> Self {
+        self.common.title = title.into();
+        self
+    }
+
+    /// Set the URL of the thumbnail of the photo.
+    pub fn thumbnail(mut self, url: impl Into<TdString>) -> Self {
+        self.thumbnail_url = url.into();
+        self
+    }
+
+    /// Set the size of the photo.
+    pub fn size(mut self, width: i32, height: i32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set the caption of the photo sent.
+    pub fn caption(mut self, caption: types::FormattedText) -> Self {
+        self.caption = Some(caption);
+        self
+    }
+
+    /// Send `content` instead of the photo, e.g. a text.
+    pub fn content(mut self, content: InputMessageContent) -> Self {
+        self.content = Some(content);
+        self
+    }
+
+    /// Build the result.
+    // The variants are only boxed by the `box-large-variants` feature
+    #[allow(clippy::useless_conversion)]
+    pub fn build(self) -> InputInlineQueryResult {
+        // The photo is sent from its URL, so the file of the content is
+        // ignored by TDLib
+        let content = self.content.unwrap_or_else(|| {
+            let photo = types::InputMessagePhoto {
+                photo: InputFile::Remote(types::InputFileRemote {
+                    id: self.photo_url.clone(),
+                }),
+                thumbnail: None,
+                added_sticker_file_ids: Vec::new(),
+                width: self.width,
+                height: self.height,
+                caption: self.caption,
+                #[cfg(not(feature = "api-1.8.19"))]
+                show_caption_above_media: false,
+                self_destruct_type: None,
+                has_spoiler: false,
+            };
+            InputMessageContent::InputMessagePhoto(photo.into())
+        });
+        let result = types::InputInlineQueryResultPhoto {
+            id: self.common.id,
+            title: self.common.title,
+            description: self.common.description,
+            thumbnail_url: self.thumbnail_url,
+            photo_url: self.photo_url,
+            photo_width: self.width,
+            photo_height: self.height,
+            reply_markup: self.common.reply_markup,
+            input_message_content: content,
+        };
+        InputInlineQueryResult::Photo(result.into())
+    }
+}
+
+/// The builder of a document result, sending the document when chosen
+/// unless another content is set.
+#[derive(Clone, Debug)]
+pub struct DocumentResult {
+    common: Common,
+    document_url: TdString,
+    mime_type: TdString,
+    thumbnail: Option<(TdString, i32, i32)>,
+    caption: Option<types::FormattedText>,
+    content: Option<InputMessageContent>,
+}
+
+impl DocumentResult {
+    /// The document titled `title` at `document_url`, whose MIME type must
+    /// be `application/pdf` or `application/zip`.
+    pub fn new(
+        title: impl Into<TdString>,
+        document_url: impl Into<TdString>,
+        mime_type: impl Into<TdString>,
+    ) -> Self {
+        Self {
+            common: Common::new(title.into()),
+            document_url: document_url.into(),
+            mime_type: mime_type.into(),
+            thumbnail: None,
+            caption: None,
+            content: None,
+        }
+    }
+
+    common_setters!();
+
+    /// Set the URL of the thumbnail of the document, and its size if known.
+    pub fn thumbnail(mut self, url: impl Into<TdString>, width: i32, height: i32) -> Self {
+        self.thumbnail = Some((url.into(), width, height));
+        self
+    }
+
+    /// Set the caption of the document sent.
+    pub fn caption(mut self, caption: types::FormattedText) -> Self {
+        self.caption = Some(caption);
+        self
+    }
+
+    /// Send `content` instead of the document, e.g. a text.
+    pub fn content(mut self, content: InputMessageContent) -> Self {
+        self.content = Some(content);
+        self
+    }
+
+    /// Build the result.
+    // The variants are only boxed by the `box-large-variants` feature
+    #[allow(clippy::useless_conversion)]
+    pub fn build(self) -> InputInlineQueryResult {
+        // The document is sent from its URL, so the file of the content is
+        // ignored by TDLib
+        let content = self.content.unwrap_or_else(|| {
+            let document = types::InputMessageDocument {
+                document: InputFile::Remote(types::InputFileRemote {
+                    id: self.document_url.clone(),
+                }),
+                thumbnail: None,
+                disable_content_type_detection: false,
+                caption: self.caption,
+            };
+            InputMessageContent::InputMessageDocument(document.into())
+        });
+        let (thumbnail_url, thumbnail_width, thumbnail_height) =
+            self.thumbnail.unwrap_or((TdString::from(""), 0, 0));
+        let result = types::InputInlineQueryResultDocument {
+            id: self.common.id,
+            title: self.common.title,
+            description: self.common.description,
+            document_url: self.document_url,
+            mime_type: self.mime_type,
+            thumbnail_url,
+            thumbnail_width,
+            thumbnail_height,
+            reply_markup: self.common.reply_markup,
+            input_message_content: content,
+        };
+        InputInlineQueryResult::Document(result.into())
+    }
+}
+
+impl From<ArticleResult> for InputInlineQueryResult {
+    fn from(result: ArticleResult) -> Self {
+        result.build()
+    }
+}
+
+impl From<PhotoResult> for InputInlineQueryResult {
+    fn from(result: PhotoResult) -> Self {
+        result.build()
+    }
+}
+
+impl From<DocumentResult> for InputInlineQueryResult {
+    fn from(result: DocumentResult) -> Self {
+        result.build()
+    }
+}
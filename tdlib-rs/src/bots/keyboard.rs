@@ -0,0 +1,268 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The builders of the keyboards attached to the messages of the bots.
+use crate::enums::{InlineKeyboardButtonType, KeyboardButtonType, ReplyMarkup, TargetChat};
+use crate::{types, TdString};
+
+/// The rows of the buttons of a keyboard, the buttons being added to the
+/// last one.
+#[derive(Clone, Debug)]
+struct Rows<T> {
+    rows: Vec<Vec<T>>,
+}
+
+impl<T> Default for Rows<T> {
+    fn default() -> Self {
+        Self { rows: Vec::new() }
+    }
+}
+
+impl<T> Rows<T> {
+    fn push(&mut self, button: T) {
+        match self.rows.last_mut() {
+            Some(row) => row.push(button),
+            None => self.rows.push(vec![button]),
+        }
+    }
+
+    fn next_row(&mut self) {
+        // A row is only started once the previous one has buttons
+        if self.rows.last().is_some_and(|row| !row.is_empty()) {
+            self.rows.push(Vec::new());
+        }
+    }
+
+    fn into_rows(mut self) -> Vec<Vec<T>> {
+        self.rows.retain(|row| !row.is_empty());
+        self.rows
+    }
+}
+
+/// The builder of an inline keyboard, shown below a message, e.g.
+///
+/// ```ignore
+/// let markup = InlineKeyboard::new()
+///     .button_url("Website", "https://telegram.org")
+///     .row()
+///     .button_callback("Yes", b"yes")
+///     .button_callback("No", b"no")
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct InlineKeyboard {
+    rows: Rows<types::InlineKeyboardButton>,
+}
+
+impl InlineKeyboard {
+    /// An empty keyboard, whose buttons are added to its first row until
+    /// [`InlineKeyboard::row`] is called.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new row of buttons.
+    pub fn row(mut self) -> Self {
+        self.rows.next_row();
+        self
+    }
+
+    /// Add a button of any type to the current row.
+    pub fn button(mut self, text: impl Into<TdString>, r#type: InlineKeyboardButtonType) -> Self {
+        self.rows.push(types::InlineKeyboardButton {
+            text: text.into(),
+            r#type,
+        });
+        self
+    }
+
+    /// Add a button opening `url`.
+    pub fn button_url(self, text: impl Into<TdString>, url: impl Into<TdString>) -> Self {
+        let url = url.into();
+        self.button(
+            text,
+            InlineKeyboardButtonType::Url(types::InlineKeyboardButtonTypeUrl { url }),
+        )
+    }
+
+    /// Add a button sending `data`, up to 64 bytes, to the bot in an
+    /// `updateNewCallbackQuery`.
+    pub fn button_callback(self, text: impl Into<TdString>, data: impl AsRef<[u8]>) -> Self {
+        let data = crate::bytes_field(data.as_ref());
+        self.button(
+            text,
+            InlineKeyboardButtonType::Callback(types::InlineKeyboardButtonTypeCallback { data }),
+        )
+    }
+
+    /// Add a button opening the Web App at `url`.
+    pub fn button_web_app(self, text: impl Into<TdString>, url: impl Into<TdString>) -> Self {
+        let url = url.into();
+        self.button(
+            text,
+            InlineKeyboardButtonType::WebApp(types::InlineKeyboardButtonTypeWebApp { url }),
+        )
+    }
+
+    /// Add a button starting an inline query to the bot with `query` in the
+    /// current chat.
+    pub fn button_switch_inline(
+        self,
+        text: impl Into<TdString>,
+        query: impl Into<TdString>,
+    ) -> Self {
+        let query = query.into();
+        self.button(
+            text,
+            InlineKeyboardButtonType::SwitchInline(types::InlineKeyboardButtonTypeSwitchInline {
+                query,
+                target_chat: TargetChat::Current,
+            }),
+        )
+    }
+
+    /// Build the reply markup of the keyboard.
+    pub fn build(self) -> ReplyMarkup {
+        ReplyMarkup::InlineKeyboard(types::ReplyMarkupInlineKeyboard {
+            rows: self.rows.into_rows(),
+        })
+    }
+}
+
+impl From<InlineKeyboard> for ReplyMarkup {
+    fn from(keyboard: InlineKeyboard) -> Self {
+        keyboard.build()
+    }
+}
+
+/// The builder of a custom keyboard, replacing the keyboard of the user in
+/// private chats and groups, e.g.
+///
+/// ```ignore
+/// let markup = ReplyKeyboard::new()
+///     .button("Yes")
+///     .button("No")
+///     .row()
+///     .button_phone_number("Share my phone number")
+///     .resize()
+///     .one_time()
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ReplyKeyboard {
+    rows: Rows<types::KeyboardButton>,
+    is_persistent: bool,
+    resize_keyboard: bool,
+    one_time: bool,
+    is_personal: bool,
+    input_field_placeholder: TdString,
+}
+
+impl ReplyKeyboard {
+    /// An empty keyboard, whose buttons are added to its first row until
+    /// [`ReplyKeyboard::row`] is called.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new row of buttons.
+    pub fn row(mut self) -> Self {
+        self.rows.next_row();
+        self
+    }
+
+    /// Add a button of any type to the current row.
+    pub fn button_of_type(mut self, text: impl Into<TdString>, r#type: KeyboardButtonType) -> Self {
+        self.rows.push(types::KeyboardButton {
+            text: text.into(),
+            r#type,
+        });
+        self
+    }
+
+    /// Add a button sending its text.
+    pub fn button(self, text: impl Into<TdString>) -> Self {
+        self.button_of_type(text, KeyboardButtonType::Text)
+    }
+
+    /// Add a button sending the phone number of the user, in private chats
+    /// only.
+    pub fn button_phone_number(self, text: impl Into<TdString>) -> Self {
+        self.button_of_type(text, KeyboardButtonType::RequestPhoneNumber)
+    }
+
+    /// Add a button sending the location of the user, in private chats only.
+    pub fn button_location(self, text: impl Into<TdString>) -> Self {
+        self.button_of_type(text, KeyboardButtonType::RequestLocation)
+    }
+
+    /// Keep showing the keyboard when the keyboard of the user is hidden.
+    pub fn persistent(mut self) -> Self {
+        self.is_persistent = true;
+        self
+    }
+
+    /// Shrink the keyboard to fit its buttons.
+    pub fn resize(mut self) -> Self {
+        self.resize_keyboard = true;
+        self
+    }
+
+    /// Hide the keyboard once a button is pressed.
+    pub fn one_time(mut self) -> Self {
+        self.one_time = true;
+        self
+    }
+
+    /// Only show the keyboard to the mentioned users and to the author of
+    /// the replied message.
+    pub fn personal(mut self) -> Self {
+        self.is_personal = true;
+        self
+    }
+
+    /// Show `placeholder`, up to 64 characters, in the input field while the
+    /// keyboard is shown.
+    pub fn placeholder(mut self, placeholder: impl Into<TdString>) -> Self {
+        self.input_field_placeholder = placeholder.into();
+        self
+    }
+
+    /// Build the reply markup of the keyboard.
+    pub fn build(self) -> ReplyMarkup {
+        ReplyMarkup::ShowKeyboard(types::ReplyMarkupShowKeyboard {
+            rows: self.rows.into_rows(),
+            is_persistent: self.is_persistent,
+            resize_keyboard: self.resize_keyboard,
+            one_time: self.one_time,
+            is_personal: self.is_personal,
+            input_field_placeholder: self.input_field_placeholder,
+        })
+    }
+}
+
+impl From<ReplyKeyboard> for ReplyMarkup {
+    fn from(keyboard: ReplyKeyboard) -> Self {
+        keyboard.build()
+    }
+}
+
+/// The reply markup removing the custom keyboard, from the mentioned users
+/// and the author of the replied message only if `is_personal`.
+pub fn remove_keyboard(is_personal: bool) -> ReplyMarkup {
+    ReplyMarkup::RemoveKeyboard(types::ReplyMarkupRemoveKeyboard { is_personal })
+}
+
+/// The reply markup asking the user to reply to the message, showing
+/// `placeholder` in the input field.
+pub fn force_reply(placeholder: impl Into<TdString>) -> ReplyMarkup {
+    ReplyMarkup::ForceReply(types::ReplyMarkupForceReply {
+        is_personal: false,
+        input_field_placeholder: placeholder.into(),
+    })
+}
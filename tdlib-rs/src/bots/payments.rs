@@ -0,0 +1,244 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The payments flow of the bots: the invoices sent, and the answers to the
+//! shipping and the pre-checkout queries of the users paying them.
+use crate::enums::InputMessageContent;
+use crate::{functions, types, TdError, TdString};
+
+/// The number of digits of the minor units of `currency`, e.g. 2 for the
+/// cents of `USD`, in which the amounts of the prices are expressed.
+pub fn currency_exponent(currency: &str) -> u32 {
+    match currency {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" | "XTR" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// Format `amount`, in the minor units of `currency`, e.g. `"12.34 USD"` for
+/// the amount 1234 of `USD`.
+pub fn format_price(amount: i64, currency: &str) -> String {
+    let exponent = currency_exponent(currency);
+    let sign = if amount < 0 { "-" } else { "" };
+    let amount = amount.unsigned_abs();
+    if exponent == 0 {
+        return format!("{sign}{amount} {currency}");
+    }
+    let unit = 10u64.pow(exponent);
+    let (major, minor) = (amount / unit, amount % unit);
+    let width = exponent as usize;
+    format!("{sign}{major}.{minor:0width$} {currency}")
+}
+
+/// A part of a price, e.g. the price of a product or the shipping costs,
+/// whose `amount` is in the minor units of the currency.
+pub fn price(label: impl Into<TdString>, amount: i64) -> types::LabeledPricePart {
+    types::LabeledPricePart {
+        label: label.into(),
+        amount,
+    }
+}
+
+/// A shipping option offered to the users, answered to their shipping
+/// queries with [`answer_shipping_query`].
+pub fn shipping_option(
+    id: impl Into<TdString>,
+    title: impl Into<TdString>,
+    price_parts: impl IntoIterator<Item = types::LabeledPricePart>,
+) -> types::ShippingOption {
+    types::ShippingOption {
+        id: id.into(),
+        title: title.into(),
+        price_parts: price_parts.into_iter().collect(),
+    }
+}
+
+/// The builder of an invoice message, e.g.
+///
+/// ```ignore
+/// let content = InvoiceBuilder::new("Coffee", "A cup of coffee", "EUR", b"order-42")
+///     .provider_token(token)
+///     .price("Coffee", 250)
+///     .price("Tip", 50)
+///     .build();
+/// ```
+///
+/// The invoices of the API of TDLib 1.8.19 require an extended media, so
+/// the builder is only available with the latest API.
+#[cfg(not(feature = "api-1.8.19"))]
+#[derive(Clone, Debug)]
+pub struct InvoiceBuilder {
+    invoice: types::Invoice,
+    title: TdString,
+    description: TdString,
+    payload: Vec<u8>,
+    provider_token: TdString,
+    start_parameter: TdString,
+    photo: Option<(TdString, i32, i32)>,
+}
+
+#[cfg(not(feature = "api-1.8.19"))]
+impl InvoiceBuilder {
+    /// An invoice of a product, paid in `currency`, whose `payload`, up to
+    /// 128 bytes, is sent back to the bot in the queries.
+    pub fn new(
+        title: impl Into<TdString>,
+        description: impl Into<TdString>,
+        currency: impl Into<TdString>,
+        payload: impl AsRef<[u8]>,
+    ) -> Self {
+        Self {
+            invoice: types::Invoice {
+                currency: currency.into(),
+                ..Default::default()
+            },
+            title: title.into(),
+            description: description.into(),
+            payload: payload.as_ref().to_vec(),
+            provider_token: TdString::from(""),
+            start_parameter: TdString::from(""),
+            photo: None,
+        }
+    }
+
+    /// Add a part of the price, in the minor units of the currency.
+    pub fn price(mut self, label: impl Into<TdString>, amount: i64) -> Self {
+        self.invoice.price_parts.push(price(label, amount));
+        self
+    }
+
+    /// Set the token of the payment provider, or none for the payments in
+    /// Telegram Stars.
+    pub fn provider_token(mut self, provider_token: impl Into<TdString>) -> Self {
+        self.provider_token = provider_token.into();
+        self
+    }
+
+    /// Set the parameter of the deep link starting the bot from a forwarded
+    /// invoice, instead of paying it directly.
+    pub fn start_parameter(mut self, start_parameter: impl Into<TdString>) -> Self {
+        self.start_parameter = start_parameter.into();
+        self
+    }
+
+    /// Set the URL of the photo of the product, and its size if known.
+    pub fn photo(mut self, url: impl Into<TdString>, width: i32, height: i32) -> Self {
+        self.photo = Some((url.into(), width, height));
+        self
+    }
+
+    /// Allow the users to add a tip, up to `max_tip_amount`, suggesting up to
+    /// 4 `suggested_tip_amounts`.
+    pub fn tips(mut self, max_tip_amount: i64, suggested_tip_amounts: impl Into<Vec<i64>>) -> Self {
+        self.invoice.max_tip_amount = max_tip_amount;
+        self.invoice.suggested_tip_amounts = suggested_tip_amounts.into();
+        self
+    }
+
+    /// Ask the name of the user.
+    pub fn need_name(mut self) -> Self {
+        self.invoice.need_name = true;
+        self
+    }
+
+    /// Ask the phone number of the user.
+    pub fn need_phone_number(mut self) -> Self {
+        self.invoice.need_phone_number = true;
+        self
+    }
+
+    /// Ask the email address of the user.
+    pub fn need_email_address(mut self) -> Self {
+        self.invoice.need_email_address = true;
+        self
+    }
+
+    /// Ask the shipping address of the user.
+    pub fn need_shipping_address(mut self) -> Self {
+        self.invoice.need_shipping_address = true;
+        self
+    }
+
+    /// Make the final price depend on the shipping address, sending the
+    /// shipping queries answered with [`answer_shipping_query`].
+    pub fn flexible(mut self) -> Self {
+        self.invoice.is_flexible = true;
+        self
+    }
+
+    /// Mark the invoice as a test one, paid with the test payment provider.
+    pub fn test(mut self) -> Self {
+        self.invoice.is_test = true;
+        self
+    }
+
+    /// Build the content of the invoice message.
+    // The variants are only boxed by the `box-large-variants` feature
+    #[allow(clippy::useless_conversion)]
+    pub fn build(self) -> InputMessageContent {
+        let (photo_url, photo_width, photo_height) =
+            self.photo.unwrap_or((TdString::from(""), 0, 0));
+        let invoice = types::InputMessageInvoice {
+            invoice: self.invoice,
+            title: self.title,
+            description: self.description,
+            photo_url,
+            photo_size: 0,
+            photo_width,
+            photo_height,
+            payload: crate::bytes_field(&self.payload),
+            provider_token: self.provider_token,
+            provider_data: TdString::from(""),
+            start_parameter: self.start_parameter,
+            paid_media: None,
+            paid_media_caption: None,
+        };
+        InputMessageContent::InputMessageInvoice(invoice.into())
+    }
+}
+
+/// Answer the shipping query `shipping_query_id`, sent for the flexible
+/// invoices, with the shipping options available for the address.
+pub async fn answer_shipping_query(
+    shipping_query_id: i64,
+    shipping_options: impl IntoIterator<Item = types::ShippingOption>,
+    client_id: i32,
+) -> Result<(), TdError> {
+    functions::answer_shipping_query(shipping_query_id, shipping_options, "", client_id).await
+}
+
+/// Reject the shipping query `shipping_query_id`, e.g. when the address
+/// can't be shipped to, showing `error_message` to the user.
+pub async fn reject_shipping_query(
+    shipping_query_id: i64,
+    error_message: impl Into<TdString>,
+    client_id: i32,
+) -> Result<(), TdError> {
+    functions::answer_shipping_query(shipping_query_id, [], error_message, client_id).await
+}
+
+/// Confirm the pre-checkout query `pre_checkout_query_id`, which must be
+/// answered within 10 seconds for the payment to proceed.
+pub async fn accept_pre_checkout_query(
+    pre_checkout_query_id: i64,
+    client_id: i32,
+) -> Result<(), TdError> {
+    functions::answer_pre_checkout_query(pre_checkout_query_id, "", client_id).await
+}
+
+/// Reject the pre-checkout query `pre_checkout_query_id`, e.g. when the
+/// product is sold out, showing `error_message` to the user.
+pub async fn reject_pre_checkout_query(
+    pre_checkout_query_id: i64,
+    error_message: impl Into<TdString>,
+    client_id: i32,
+) -> Result<(), TdError> {
+    functions::answer_pre_checkout_query(pre_checkout_query_id, error_message, client_id).await
+}
@@ -3,6 +3,11 @@
 //! - `local-tdlib`
 //! - `pkg-config`
 //! - `download-tdlib`
+//! - `vcpkg`
+//! - `vendored`
+//!
+//! With the `vendored` feature, TDLib is built from source and linked statically by the build
+//! script of `tdlib-rs` itself, so none of the build functions is needed.
 
 #[allow(dead_code)]
 #[cfg(not(any(feature = "docs", feature = "pkg-config")))]
@@ -62,18 +67,41 @@ fn copy_dir_all(
 /// If the OS or architecture is not supported, the function will panic.
 fn download_tdlib() {
     let base_url = "https://github.com/fifteenlabs/tdlib-rs/releases/download";
-    let url = format!(
-        "{}/v{}/tdlib-{}-{}-{}.zip",
-        base_url,
-        TDLIB_CARGO_PKG_VERSION,
-        TDLIB_VERSION,
-        std::env::var("CARGO_CFG_TARGET_OS").unwrap(),
-        std::env::var("CARGO_CFG_TARGET_ARCH").unwrap(),
-    );
+    let url = std::env::var("TDLIB_DOWNLOAD_URL").unwrap_or_else(|_| {
+        format!(
+            "{}/v{}/tdlib-{}-{}-{}.zip",
+            base_url,
+            TDLIB_CARGO_PKG_VERSION,
+            TDLIB_VERSION,
+            std::env::var("CARGO_CFG_TARGET_OS").unwrap(),
+            std::env::var("CARGO_CFG_TARGET_ARCH").unwrap(),
+        )
+    });
+    let expected_sha256 = std::env::var("TDLIB_DOWNLOAD_SHA256")
+        .ok()
+        .map(|sha256| sha256.trim().to_ascii_lowercase());
 
     let out_dir = std::env::var("OUT_DIR").unwrap();
     let tdlib_dir = format!("{}/tdlib", &out_dir);
     let zip_path = format!("{}.zip", &tdlib_dir);
+    let stamp_path = format!("{}.stamp", &tdlib_dir);
+
+    // Reuse the artifact extracted by a previous build, if it comes from the
+    // same URL and matches the expected checksum
+    if let Ok(stamp) = std::fs::read_to_string(&stamp_path) {
+        if let Some((stamp_url, stamp_sha256)) = stamp.trim().split_once('\n') {
+            if stamp_url == url
+                && expected_sha256
+                    .as_ref()
+                    .is_none_or(|sha256| sha256 == stamp_sha256)
+                && std::path::Path::new(&tdlib_dir).exists()
+            {
+                return;
+            }
+        }
+    }
+    let _ = std::fs::remove_file(&stamp_path);
+    let _ = std::fs::remove_dir_all(&tdlib_dir);
 
     // Create the request with a longer timeout for large files
     let client = reqwest::blocking::Client::builder()
@@ -83,14 +111,7 @@ fn download_tdlib() {
     let response = client.get(&url).send().unwrap();
 
     // Check if the response status is successful
-    if response.status().is_success() {
-        // Create a file to write to
-        let mut dest = std::fs::File::create(&zip_path).unwrap();
-
-        // Get the response bytes and write to the file
-        let content = response.bytes().unwrap();
-        std::io::copy(&mut content.as_ref(), &mut dest).unwrap();
-    } else {
+    if !response.status().is_success() {
         panic!(
             "[{}] Failed to download file: {}\n{}\n{}",
             "Your OS or architecture may be unsupported.",
@@ -99,6 +120,23 @@ fn download_tdlib() {
             &url
         )
     }
+    let content = response.bytes().unwrap();
+
+    // Verify the checksum of the artifact
+    let sha256 = sha256_hex(&content);
+    match &expected_sha256 {
+        Some(expected) if *expected != sha256 => panic!(
+            "Checksum mismatch for {url}: expected SHA-256 {expected}, got {sha256}"
+        ),
+        Some(_) => {}
+        None => println!(
+            "cargo:warning=Downloaded {url} with SHA-256 {sha256}; set TDLIB_DOWNLOAD_SHA256 to verify it"
+        ),
+    }
+
+    // Create a file to write to
+    let mut dest = std::fs::File::create(&zip_path).unwrap();
+    std::io::copy(&mut content.as_ref(), &mut dest).unwrap();
 
     let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
 
@@ -129,6 +167,79 @@ fn download_tdlib() {
     }
 
     let _ = std::fs::remove_file(&zip_path);
+    std::fs::write(&stamp_path, format!("{url}\n{sha256}")).unwrap();
+}
+
+#[cfg(feature = "download-tdlib")]
+/// Compute the lowercase hexadecimal SHA-256 digest of `bytes`.
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(feature = "local-tdlib")]
+/// Find the prefix of the tdlib installed with Homebrew when targeting macOS.
+/// The prefix is given by `brew --prefix tdlib`. If `brew` is not in the `PATH` (e.g. when
+/// building from an IDE), the prefix is looked up in the following folders:
+/// - `$HOMEBREW_PREFIX/opt/tdlib`
+/// - `/opt/homebrew/opt/tdlib` (Apple Silicon)
+/// - `/usr/local/opt/tdlib` (Intel)
+///
+/// `None` is returned if the tdlib is not found or the target is not macOS.
+fn homebrew_tdlib_prefix() -> Option<std::path::PathBuf> {
+    if std::env::var("CARGO_CFG_TARGET_OS").ok()? != "macos" {
+        return None;
+    }
+
+    let is_tdlib = |prefix: &std::path::PathBuf| prefix.join("lib").is_dir();
+    if let Ok(output) = std::process::Command::new("brew")
+        .args(["--prefix", "tdlib"])
+        .output()
+    {
+        let prefix = std::path::PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        if output.status.success() && is_tdlib(&prefix) {
+            return Some(prefix);
+        }
+    }
+
+    std::env::var("HOMEBREW_PREFIX")
+        .ok()
+        .into_iter()
+        .chain(["/opt/homebrew".to_string(), "/usr/local".to_string()])
+        .map(|prefix| std::path::Path::new(&prefix).join("opt/tdlib"))
+        .find(is_tdlib)
+}
+
+#[cfg(feature = "vcpkg")]
+/// Get the vcpkg triplet matching the target.
+/// The triplet is `<arch>-windows`, or `<arch>-windows-static` when the C runtime is linked
+/// statically (`-C target-feature=+crt-static`), where `<arch>` is `x64`, `arm64` or `x86`.
+/// The `VCPKGRS_TRIPLET` environment variable overrides it.
+///
+/// On the other OSes, `None` is returned and the triplet is left to the `vcpkg` crate.
+fn vcpkg_triplet() -> Option<String> {
+    if let Ok(triplet) = std::env::var("VCPKGRS_TRIPLET") {
+        return Some(triplet);
+    }
+    if std::env::var("CARGO_CFG_TARGET_OS").ok()? != "windows" {
+        return None;
+    }
+
+    let arch = match std::env::var("CARGO_CFG_TARGET_ARCH").ok()?.as_str() {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        "x86" => "x86",
+        _ => return None,
+    };
+    let crt_static = std::env::var("CARGO_CFG_TARGET_FEATURE")
+        .unwrap_or_default()
+        .split(',')
+        .any(|feature| feature == "crt-static");
+    if crt_static {
+        Some(format!("{arch}-windows-static"))
+    } else {
+        Some(format!("{arch}-windows"))
+    }
 }
 
 #[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
@@ -145,6 +256,9 @@ fn download_tdlib() {
 ///
 /// The `...` represents the `dest_path` or the `OUT_DIR` environment variable.
 ///
+/// When targeting Android, the library is linked as described in `android_build`, and when
+/// targeting iOS or using an xcframework, as described in `apple_build`.
+///
 /// If the tdlib library is not found at the specified path, the function will panic.
 ///
 /// The function will panic if the tdlib library is not found at the specified path.
@@ -163,6 +277,17 @@ fn generic_build(lib_path: Option<String>) {
         }
     }
     let prefix = correct_lib_path.to_string();
+    if std::env::var("CARGO_CFG_TARGET_OS").unwrap() == "android" {
+        android_build(&prefix);
+        return;
+    }
+    if std::env::var("CARGO_CFG_TARGET_OS").unwrap() == "ios"
+        || std::path::Path::new(&prefix).join("Info.plist").exists()
+    {
+        apple_build(&prefix);
+        return;
+    }
+
     let include_dir = format!("{prefix}/include");
     let lib_dir = format!("{prefix}/lib");
     let mut_lib_path = {
@@ -243,20 +368,182 @@ fn generic_build(lib_path: Option<String>) {
     println!("cargo:rustc-link-arg=-Wl,-rpath,{lib_dir}");
 }
 
+#[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+/// Link the prebuilt TDLib for Android found in `prefix`, when cross-compiling with the NDK.
+/// The library is looked up in the `libs/<abi>` folder, the layout of the libraries built by
+/// the TDLib Android example, and then in the `lib` folder. The supported ABIs are:
+/// - arm64-v8a (aarch64)
+/// - armeabi-v7a (arm)
+/// - x86_64
+/// - x86
+///
+/// TDLib is linked against the shared C++ runtime of the NDK, which is looked up in the sysroot
+/// of the `ANDROID_NDK_HOME` or `ANDROID_NDK_ROOT` environment variable. The `libc++_shared.so`
+/// library must be packaged in the app together with `libtdjson.so`.
+fn android_build(prefix: &str) {
+    let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let (abi, triple) = match target_arch.as_str() {
+        "aarch64" => ("arm64-v8a", "aarch64-linux-android"),
+        "arm" => ("armeabi-v7a", "arm-linux-androideabi"),
+        "x86_64" => ("x86_64", "x86_64-linux-android"),
+        "x86" => ("x86", "i686-linux-android"),
+        _ => panic!("Unsupported Android architecture: {target_arch}"),
+    };
+    let lib_dir = [format!("{prefix}/libs/{abi}"), format!("{prefix}/lib")]
+        .into_iter()
+        .find(|lib_dir| std::path::Path::new(&format!("{lib_dir}/libtdjson.so")).exists())
+        .unwrap_or_else(|| {
+            panic!("tdjson shared library not found at {prefix}/libs/{abi}/libtdjson.so")
+        });
+
+    println!("cargo:rustc-link-search=native={lib_dir}");
+    println!("cargo:include={prefix}/include");
+    println!("cargo:rustc-link-lib=dylib=tdjson");
+
+    if let Some(sysroot_lib_dir) = android_sysroot_lib_dir(triple) {
+        println!("cargo:rustc-link-search=native={sysroot_lib_dir}");
+    }
+    println!("cargo:rustc-link-lib=dylib=c++_shared");
+}
+
+#[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+/// Get the folder of the NDK sysroot with the libraries for the `triple` target, e.g.
+/// `$ANDROID_NDK_HOME/toolchains/llvm/prebuilt/linux-x86_64/sysroot/usr/lib/aarch64-linux-android`.
+/// `None` is returned if neither `ANDROID_NDK_HOME` nor `ANDROID_NDK_ROOT` is set.
+fn android_sysroot_lib_dir(triple: &str) -> Option<String> {
+    let ndk = std::env::var("ANDROID_NDK_HOME")
+        .or_else(|_| std::env::var("ANDROID_NDK_ROOT"))
+        .ok()?;
+    // The NDK only ships x86_64 toolchains, also for Apple Silicon hosts
+    let host_tag = if cfg!(target_os = "windows") {
+        "windows-x86_64"
+    } else if cfg!(target_os = "macos") {
+        "darwin-x86_64"
+    } else {
+        "linux-x86_64"
+    };
+    Some(format!(
+        "{ndk}/toolchains/llvm/prebuilt/{host_tag}/sysroot/usr/lib/{triple}"
+    ))
+}
+
+#[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+/// Link the TDLib for iOS, or for macOS with an xcframework, found in `prefix`.
+/// If `prefix` is an xcframework (e.g. `TDLib.xcframework`), the libraries are looked up in the
+/// slice matching the target, as described in `xcframework_slice`, otherwise in the `lib` folder.
+///
+/// The function will pass to the `rustc` the following flags:
+/// - `cargo:rustc-link-search=native=...`
+/// - `cargo:rustc-link-search=framework=...` (only for frameworks)
+/// - `cargo:rustc-link-lib=framework=<name>` (only for frameworks)
+/// - `cargo:rustc-link-arg=-Wl,-rpath,...` (only for frameworks on macOS)
+///
+/// The static libraries are linked by `tdlib-rs` itself, and bundled in its rlib, so an app
+/// embedding a Rust core built as a `staticlib` only needs to link `libc++` and `libz`.
+/// A dynamic framework must instead be embedded in the app.
+fn apple_build(prefix: &str) {
+    let lib_dir = if std::path::Path::new(prefix).join("Info.plist").exists() {
+        xcframework_slice(prefix)
+    } else {
+        std::path::Path::new(prefix).join("lib")
+    };
+    if !lib_dir.exists() {
+        panic!("tdjson library not found at {}", lib_dir.display());
+    }
+
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    let framework = std::fs::read_dir(&lib_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "framework")
+        });
+    if let Some(framework) = framework {
+        println!("cargo:rustc-link-search=framework={}", lib_dir.display());
+        println!(
+            "cargo:rustc-link-lib=framework={}",
+            framework.file_stem().unwrap().to_string_lossy()
+        );
+        if std::env::var("CARGO_CFG_TARGET_OS").unwrap() == "macos" {
+            println!("cargo:rustc-link-arg=-Wl,-rpath,{}", lib_dir.display());
+        }
+    }
+}
+
+#[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+/// Get the slice of the xcframework matching the target.
+/// The slices are chosen by platform (`ios` or `macos`), by architecture (`arm64` or `x86_64`)
+/// and by variant:
+/// - `ios-arm64` for the devices (`aarch64-apple-ios`)
+/// - `ios-arm64_x86_64-simulator` for the simulators (`aarch64-apple-ios-sim` and
+///   `x86_64-apple-ios`)
+/// - `ios-arm64_x86_64-maccatalyst` for Mac Catalyst (`aarch64-apple-ios-macabi`)
+/// - `macos-arm64_x86_64` for macOS
+///
+/// If no slice matches the target, the function will panic.
+fn xcframework_slice(xcframework: &str) -> std::path::PathBuf {
+    let target = std::env::var("TARGET").unwrap();
+    let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap();
+    let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let platform = if target_os == "ios" { "ios" } else { "macos" };
+    let arch = match target_arch.as_str() {
+        "aarch64" => "arm64",
+        "x86_64" => "x86-64",
+        _ => panic!("Unsupported Apple architecture: {target_arch}"),
+    };
+    let variant = if target.ends_with("-macabi") {
+        "-maccatalyst"
+    } else if target_os == "ios" && (target.ends_with("-sim") || target_arch == "x86_64") {
+        "-simulator"
+    } else {
+        ""
+    };
+
+    // The slices are named after the platform, the architectures and the variant, e.g.
+    // `ios-arm64`, `ios-arm64_x86_64-simulator` or `macos-arm64_x86_64`
+    std::fs::read_dir(xcframework)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let name = path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .replace("x86_64", "x86-64");
+            name.strip_prefix(platform)
+                .and_then(|name| name.strip_prefix('-'))
+                .and_then(|name| name.strip_suffix(variant))
+                .is_some_and(|archs| {
+                    !archs.contains('-') && archs.split('_').any(|slice_arch| slice_arch == arch)
+                })
+        })
+        .unwrap_or_else(|| {
+            panic!("No slice for {target} found in the xcframework at {xcframework}")
+        })
+}
+
 /// Check if the features are correctly set.
 /// The features are correctly set when exactly one of the following features is enabled:
 /// - `local-tdlib`
 /// - `pkg-config`
 /// - `download-tdlib`
+/// - `vcpkg`
+/// - `vendored`
 /// - `docs` (only for tdlib documentation)
 ///
 /// The following features cannot be enabled at the same time:
 /// - `docs` and `pkg-config`
 /// - `docs` and `download-tdlib`
 /// - `docs` and `local-tdlib`
+/// - `docs` and `vcpkg`
 /// - `pkg-config` and `local-tdlib`
 /// - `pkg-config` and `download-tdlib`
+/// - `pkg-config` and `vcpkg`
 /// - `local-tdlib` and `download-tdlib`
+/// - `local-tdlib` and `vcpkg`
+/// - `download-tdlib` and `vcpkg`
+/// - `vendored` and any of the above
 ///
 /// If the features are not correctly set, the function will generate a compile error
 pub fn check_features() {
@@ -289,14 +576,69 @@ pub fn check_features() {
     compile_error!(
         "feature \"local-tdlib\" and feature \"download-tdlib\" cannot be enabled at the same time"
     );
+
+    #[cfg(all(feature = "vcpkg", feature = "docs"))]
+    compile_error!("feature \"vcpkg\" and feature \"docs\" cannot be enabled at the same time");
+    #[cfg(all(feature = "vcpkg", feature = "pkg-config"))]
+    compile_error!(
+        "feature \"vcpkg\" and feature \"pkg-config\" cannot be enabled at the same time"
+    );
+    #[cfg(all(feature = "vcpkg", feature = "local-tdlib"))]
+    compile_error!(
+        "feature \"vcpkg\" and feature \"local-tdlib\" cannot be enabled at the same time"
+    );
+    #[cfg(all(feature = "vcpkg", feature = "download-tdlib"))]
+    compile_error!(
+        "feature \"vcpkg\" and feature \"download-tdlib\" cannot be enabled at the same time"
+    );
+
+    #[cfg(all(feature = "vendored", feature = "docs"))]
+    compile_error!("feature \"vendored\" and feature \"docs\" cannot be enabled at the same time");
+    #[cfg(all(feature = "vendored", feature = "pkg-config"))]
+    compile_error!(
+        "feature \"vendored\" and feature \"pkg-config\" cannot be enabled at the same time"
+    );
+    #[cfg(all(feature = "vendored", feature = "local-tdlib"))]
+    compile_error!(
+        "feature \"vendored\" and feature \"local-tdlib\" cannot be enabled at the same time"
+    );
+    #[cfg(all(feature = "vendored", feature = "download-tdlib"))]
+    compile_error!(
+        "feature \"vendored\" and feature \"download-tdlib\" cannot be enabled at the same time"
+    );
+    #[cfg(all(feature = "vendored", feature = "vcpkg"))]
+    compile_error!("feature \"vendored\" and feature \"vcpkg\" cannot be enabled at the same time");
 }
 
 /// Set the `rerun-if-changed` and `rerun-if-env-changed` flags for the build script.
 /// The `rerun-if-changed` flag is set for the `build.rs` file.
-/// The `rerun-if-env-changed` flag is set for the `LOCAL_TDLIB_PATH` environment variable.
+/// The `rerun-if-env-changed` flag is set for the `LOCAL_TDLIB_PATH`, `HOMEBREW_PREFIX`,
+/// `ANDROID_NDK_HOME`, `ANDROID_NDK_ROOT`, `TDLIB_DOWNLOAD_URL`, `TDLIB_DOWNLOAD_SHA256`,
+/// `VCPKG_ROOT` and `VCPKGRS_TRIPLET` environment variables.
 pub fn set_rerun_if() {
     #[cfg(feature = "local-tdlib")]
-    println!("cargo:rerun-if-env-changed=LOCAL_TDLIB_PATH");
+    {
+        println!("cargo:rerun-if-env-changed=LOCAL_TDLIB_PATH");
+        println!("cargo:rerun-if-env-changed=HOMEBREW_PREFIX");
+    }
+
+    #[cfg(any(feature = "download-tdlib", feature = "local-tdlib"))]
+    {
+        println!("cargo:rerun-if-env-changed=ANDROID_NDK_HOME");
+        println!("cargo:rerun-if-env-changed=ANDROID_NDK_ROOT");
+    }
+
+    #[cfg(feature = "download-tdlib")]
+    {
+        println!("cargo:rerun-if-env-changed=TDLIB_DOWNLOAD_URL");
+        println!("cargo:rerun-if-env-changed=TDLIB_DOWNLOAD_SHA256");
+    }
+
+    #[cfg(feature = "vcpkg")]
+    {
+        println!("cargo:rerun-if-env-changed=VCPKG_ROOT");
+        println!("cargo:rerun-if-env-changed=VCPKGRS_TRIPLET");
+    }
 
     println!("cargo:rerun-if-changed=build.rs");
 }
@@ -355,6 +697,14 @@ pub fn build_pkg_config() {
 ///
 /// The function will download the tdlib library from the GitHub release page.
 /// Using the `download-tdlib` feature, no system dependencies are required.
+///
+/// The following environment variables tune the download:
+/// - `TDLIB_DOWNLOAD_URL`: the URL of the zip archive to download instead of the GitHub release.
+/// - `TDLIB_DOWNLOAD_SHA256`: the expected SHA-256 of the archive. If it doesn't match, the
+///   function will panic.
+///
+/// The extracted archive is cached in the `OUT_DIR` folder, and downloaded again only when the URL
+/// or the expected checksum change.
 /// The OS and architecture currently supported are:
 /// - Linux x86_64
 /// - Linux aarch64
@@ -407,10 +757,15 @@ pub fn build_download_tdlib(dest_path: Option<String>) {
 /// Build the project using the `local-tdlib` feature.
 /// Using the `local-tdlib` feature, the function will copy the tdlib library from the
 /// `LOCAL_TDLIB_PATH` environment variable.
-/// The tdlib folder must contain the `lib` and `include` folders.
+/// The tdlib folder must contain the `lib` and `include` folders. When cross-compiling for
+/// Android, the libraries can also be in the `libs/<abi>` folders (e.g. `libs/arm64-v8a`).
+/// For iOS and macOS, the `LOCAL_TDLIB_PATH` environment variable can also point to an
+/// xcframework (e.g. `TDLib.xcframework`) with a framework or static libraries in its slices.
 /// You can directly download the tdlib library from the [TDLib Release GitHub page](https://github.com/FedericoBruzzone/tdlib-rs/releases).
 ///
 /// The `LOCAL_TDLIB_PATH` environment variable must be set to the path of the tdlib folder.
+/// On macOS, if it is not set, the tdlib installed with `brew install tdlib` is used, both with
+/// the Apple Silicon (`/opt/homebrew`) and Intel (`/usr/local`) prefixes.
 ///
 /// The function will pass to the `rustc` the following flags:
 /// - `cargo:rustc-link-search=native=.../tdlib/lib`
@@ -421,7 +776,8 @@ pub fn build_download_tdlib(dest_path: Option<String>) {
 ///
 /// The `...` represents the `LOCAL_TDLIB_PATH` environment variable.
 ///
-/// If the `LOCAL_TDLIB_PATH` environment variable is not set, the function will panic.
+/// If the `LOCAL_TDLIB_PATH` environment variable is not set and the tdlib is not installed with
+/// Homebrew, the function will panic.
 ///
 /// # Example
 /// Cargo.toml:
@@ -447,11 +803,75 @@ pub fn build_local_tdlib() {
     #[cfg(not(feature = "docs"))]
     {
         // copy_local_tdlib();
-        let path = std::env::var("LOCAL_TDLIB_PATH").unwrap();
+        let path = std::env::var("LOCAL_TDLIB_PATH")
+            .ok()
+            .or_else(|| homebrew_tdlib_prefix().map(|prefix| prefix.display().to_string()))
+            .expect(
+                "The LOCAL_TDLIB_PATH env variable must be set to the path of the tdlib folder",
+            );
         generic_build(Some(path));
     }
 }
 
+#[cfg(any(feature = "vcpkg", feature = "docs"))]
+#[allow(clippy::needless_doctest_main)]
+/// Build the project using the `vcpkg` feature.
+/// Using the `vcpkg` feature, the function will find the tdlib library installed with
+/// `vcpkg install tdlib`, so that no environment variable has to be set by hand on Windows.
+///
+/// The vcpkg installation is found through the `VCPKG_ROOT` environment variable, or the one
+/// integrated with `vcpkg integrate install`.
+/// The triplet is chosen from the target architecture and C runtime:
+/// - `x64-windows` (or `arm64-windows`, `x86-windows`) when the C runtime is linked dynamically
+/// - `x64-windows-static` (or `arm64-windows-static`, `x86-windows-static`) when it is linked
+///   statically with `-C target-feature=+crt-static`
+///
+/// The `VCPKGRS_TRIPLET` environment variable overrides the triplet (e.g. `x64-windows-static-md`).
+///
+/// The function will pass to the `rustc` the link flags of the libraries installed by the
+/// `tdlib` port, and the following flag:
+/// - `cargo:rustc-link-search=native=.../installed/<triplet>/bin` (only for dynamic triplets)
+///
+/// If the `tdlib` port is not installed for the triplet, the function will panic.
+///
+/// # Example
+/// Cargo.toml:
+/// ```toml
+/// [dependencies]
+/// tdlib = { version = "...", features = ["vcpkg"] }
+///
+/// [build-dependencies]
+/// tdlib = { version = "...", features = [ "vcpkg" ] }
+/// ```
+///
+/// build.rs:
+/// ```rust
+/// fn main() {
+///   tdlib_rs::build::check_features();
+///   tdlib_rs::build::set_rerun_if();
+///   tdlib_rs::build::build_vcpkg();
+///   // Other build configurations
+///   // ...
+/// }
+/// ```
+pub fn build_vcpkg() {
+    #[cfg(not(feature = "docs"))]
+    {
+        let mut config = vcpkg::Config::new();
+        if let Some(triplet) = vcpkg_triplet() {
+            config.target_triplet(triplet);
+        }
+        let library = config.find_package("tdlib").unwrap_or_else(|e| {
+            panic!("tdlib not found with vcpkg, try `vcpkg install tdlib`: {e}");
+        });
+
+        // Same workaround of `generic_build`, the dlls are not found otherwise
+        for path in library.dll_paths {
+            println!("cargo:rustc-link-search=native={}", path.display());
+        }
+    }
+}
+
 #[allow(clippy::needless_doctest_main)]
 /// Build the project using the enabled features.
 ///
@@ -463,7 +883,10 @@ pub fn build_local_tdlib() {
 /// The function will check if the features are correctly set.
 /// The function will set the `rerun-if-changed` and `rerun-if-env-changed` flags for the build
 /// script.
-/// The function will build the project using the enabled feature.
+/// The function will build the project using the enabled feature. Nothing is built with the
+/// `vendored` feature, since `tdlib-rs` already links TDLib statically, nor with the `stub`
+/// feature or on docs.rs (when the `DOCS_RS` environment variable is set), where TDLib is not
+/// linked at all.
 ///
 /// # Example
 /// Cargo.toml:
@@ -487,10 +910,17 @@ pub fn build(_dest_path: Option<String>) {
     check_features();
     set_rerun_if();
 
-    #[cfg(feature = "pkg-config")]
-    build_pkg_config();
-    #[cfg(feature = "download-tdlib")]
-    build_download_tdlib(_dest_path);
-    #[cfg(feature = "local-tdlib")]
-    build_local_tdlib();
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+    // Nothing is linked by the stub backend
+    let is_stub = cfg!(feature = "stub") || std::env::var_os("DOCS_RS").is_some();
+    if !is_stub {
+        #[cfg(feature = "pkg-config")]
+        build_pkg_config();
+        #[cfg(feature = "download-tdlib")]
+        build_download_tdlib(_dest_path);
+        #[cfg(feature = "local-tdlib")]
+        build_local_tdlib();
+        #[cfg(feature = "vcpkg")]
+        build_vcpkg();
+    }
 }
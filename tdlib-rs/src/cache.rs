@@ -0,0 +1,244 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The optional cache of the users, chats, basic groups and supergroups of
+//! the clients, kept up to date from the updates like TDLib expects the
+//! applications to do, so that e.g. [`get_user`] doesn't send a request for
+//! a user already reported by `updateUser`.
+//!
+//! Once enabled with [`set_entity_cache`], the cache only knows the entities
+//! reported from then on, and the ones returned by its functions.
+use crate::chats::chat_of;
+use crate::client::user_of;
+use crate::enums::{self, AuthorizationState, Update};
+use crate::{functions, types, TdError};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The entities of a client.
+#[derive(Default)]
+struct Entities {
+    users: HashMap<i64, types::User>,
+    chats: HashMap<i64, types::Chat>,
+    basic_groups: HashMap<i64, types::BasicGroup>,
+    supergroups: HashMap<i64, types::Supergroup>,
+}
+
+impl Entities {
+    /// Update the entities from one of the updates reporting them.
+    fn handle_update(&mut self, update: &Update) {
+        match update {
+            Update::User(update) => {
+                self.users.insert(update.user.id, update.user.clone());
+            }
+            Update::UserStatus(update) => {
+                if let Some(user) = self.users.get_mut(&update.user_id) {
+                    user.status = update.status.clone();
+                }
+            }
+            Update::BasicGroup(update) => {
+                let basic_group = update.basic_group.clone();
+                self.basic_groups.insert(basic_group.id, basic_group);
+            }
+            Update::Supergroup(update) => {
+                let supergroup = update.supergroup.clone();
+                self.supergroups.insert(supergroup.id, supergroup);
+            }
+            Update::NewChat(update) => {
+                self.chats.insert(update.chat.id, update.chat.clone());
+            }
+            _ => self.handle_chat_update(update),
+        }
+    }
+
+    /// Update a field of a chat from its update.
+    fn handle_chat_update(&mut self, update: &Update) {
+        let Some(chat) = update.chat_id().and_then(|id| self.chats.get_mut(&id)) else {
+            return;
+        };
+        match update {
+            Update::ChatTitle(update) => chat.title = update.title.clone(),
+            Update::ChatPhoto(update) => chat.photo = update.photo.clone(),
+            Update::ChatPermissions(update) => chat.permissions = update.permissions.clone(),
+            Update::ChatLastMessage(update) => {
+                chat.last_message = update.last_message.clone();
+                chat.positions = update.positions.clone();
+            }
+            Update::ChatPosition(update) => {
+                let position = &update.position;
+                chat.positions.retain(|other| other.list != position.list);
+                // A chat with a zero order is removed from the list
+                if position.order != 0 {
+                    chat.positions.push(position.clone());
+                }
+            }
+            Update::ChatReadInbox(update) => {
+                chat.last_read_inbox_message_id = update.last_read_inbox_message_id;
+                chat.unread_count = update.unread_count;
+            }
+            Update::ChatReadOutbox(update) => {
+                chat.last_read_outbox_message_id = update.last_read_outbox_message_id;
+            }
+            Update::ChatActionBar(update) => chat.action_bar = update.action_bar.clone(),
+            Update::ChatDraftMessage(update) => {
+                chat.draft_message = update.draft_message.clone();
+                chat.positions = update.positions.clone();
+            }
+            Update::ChatNotificationSettings(update) => {
+                chat.notification_settings = update.notification_settings.clone();
+            }
+            Update::ChatReplyMarkup(update) => {
+                chat.reply_markup_message_id = update.reply_markup_message_id;
+            }
+            Update::ChatUnreadMentionCount(update) => {
+                chat.unread_mention_count = update.unread_mention_count;
+            }
+            Update::ChatUnreadReactionCount(update) => {
+                chat.unread_reaction_count = update.unread_reaction_count;
+            }
+            Update::ChatHasProtectedContent(update) => {
+                chat.has_protected_content = update.has_protected_content;
+            }
+            Update::ChatIsMarkedAsUnread(update) => {
+                chat.is_marked_as_unread = update.is_marked_as_unread;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Default)]
+struct Cache {
+    is_enabled: bool,
+    clients: HashMap<i32, Entities>,
+}
+
+static CACHE: Lazy<Mutex<Cache>> = Lazy::new(Mutex::default);
+
+/// Cache the entities of every client from now on, or stop caching them and
+/// forget the cached ones, which is the default.
+pub fn set_entity_cache(enabled: bool) {
+    let mut cache = CACHE.lock().unwrap();
+    cache.is_enabled = enabled;
+    if !enabled {
+        cache.clients.clear();
+    }
+}
+
+/// Update the cached entities of `client_id` from one of its updates.
+pub(crate) fn handle_update(update: &Update, client_id: i32) {
+    let mut cache = CACHE.lock().unwrap();
+    if !cache.is_enabled {
+        return;
+    }
+    match update {
+        Update::AuthorizationState(update)
+            if matches!(update.authorization_state, AuthorizationState::Closed) =>
+        {
+            cache.clients.remove(&client_id);
+        }
+        _ => cache
+            .clients
+            .entry(client_id)
+            .or_default()
+            .handle_update(update),
+    }
+}
+
+/// The entity of `client_id` returned by `get`, if cached.
+fn cached<T: Clone>(client_id: i32, get: impl FnOnce(&Entities) -> Option<&T>) -> Option<T> {
+    let cache = CACHE.lock().unwrap();
+    cache.clients.get(&client_id).and_then(get).cloned()
+}
+
+/// Cache an entity of `client_id` with `insert`, if the cache is enabled.
+fn cache(client_id: i32, insert: impl FnOnce(&mut Entities)) {
+    let mut cache = CACHE.lock().unwrap();
+    if cache.is_enabled {
+        insert(cache.clients.entry(client_id).or_default());
+    }
+}
+
+/// The user `user_id`, from the cache or else from `getUser`.
+pub async fn get_user(user_id: i64, client_id: i32) -> Result<types::User, TdError> {
+    if let Some(user) = cached(client_id, |entities| entities.users.get(&user_id)) {
+        return Ok(user);
+    }
+    let user = user_of(functions::get_user(user_id, client_id).await?)?;
+    cache(client_id, |entities| {
+        entities.users.insert(user.id, user.clone());
+    });
+    Ok(user)
+}
+
+/// The chat `chat_id`, from the cache or else from `getChat`.
+pub async fn get_chat(chat_id: i64, client_id: i32) -> Result<types::Chat, TdError> {
+    if let Some(chat) = cached(client_id, |entities| entities.chats.get(&chat_id)) {
+        return Ok(chat);
+    }
+    let chat = chat_of(functions::get_chat(chat_id, client_id).await?)?;
+    cache(client_id, |entities| {
+        entities.chats.insert(chat.id, chat.clone());
+    });
+    Ok(chat)
+}
+
+/// The basic group `basic_group_id`, from the cache or else from
+/// `getBasicGroup`.
+// The basic group is only boxed by the `box-large-variants` feature
+#[allow(clippy::useless_conversion)]
+pub async fn get_basic_group(
+    basic_group_id: i64,
+    client_id: i32,
+) -> Result<types::BasicGroup, TdError> {
+    let basic_group = cached(client_id, |entities| {
+        entities.basic_groups.get(&basic_group_id)
+    });
+    if let Some(basic_group) = basic_group {
+        return Ok(basic_group);
+    }
+    let basic_group = match functions::get_basic_group(basic_group_id, client_id).await? {
+        enums::BasicGroup::BasicGroup(group) => *Box::<types::BasicGroup>::from(group),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::BasicGroup::Unrecognized => return Err(TdError::unrecognized("BasicGroup")),
+    };
+    cache(client_id, |entities| {
+        entities
+            .basic_groups
+            .insert(basic_group.id, basic_group.clone());
+    });
+    Ok(basic_group)
+}
+
+/// The supergroup or channel `supergroup_id`, from the cache or else from
+/// `getSupergroup`.
+// The supergroup is only boxed by the `box-large-variants` feature
+#[allow(clippy::useless_conversion)]
+pub async fn get_supergroup(
+    supergroup_id: i64,
+    client_id: i32,
+) -> Result<types::Supergroup, TdError> {
+    let supergroup = cached(client_id, |entities| {
+        entities.supergroups.get(&supergroup_id)
+    });
+    if let Some(supergroup) = supergroup {
+        return Ok(supergroup);
+    }
+    let supergroup = match functions::get_supergroup(supergroup_id, client_id).await? {
+        enums::Supergroup::Supergroup(group) => *Box::<types::Supergroup>::from(group),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::Supergroup::Unrecognized => return Err(TdError::unrecognized("Supergroup")),
+    };
+    cache(client_id, |entities| {
+        entities
+            .supergroups
+            .insert(supergroup.id, supergroup.clone());
+    });
+    Ok(supergroup)
+}
@@ -0,0 +1,167 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The chat lists, loaded with `loadChats` and ordered from the positions of
+//! the chats reported by the updates.
+use crate::enums::{self, ChatList, Update};
+use crate::{functions, types, updates, Client, TdError};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// The number of chats loaded at once by [`load_all_chats`].
+const LOAD_CHATS_LIMIT: i32 = 100;
+
+/// The positions of the chats of a client in the chat lists.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChatPositions {
+    positions: HashMap<i64, Vec<types::ChatPosition>>,
+}
+
+impl ChatPositions {
+    /// Update the positions from one of the updates reporting them.
+    pub(crate) fn handle_update(&mut self, update: &Update) {
+        match update {
+            Update::NewChat(update) => {
+                self.set_positions(update.chat.id, &update.chat.positions);
+            }
+            Update::ChatLastMessage(update) => {
+                self.set_positions(update.chat_id, &update.positions);
+            }
+            Update::ChatDraftMessage(update) => {
+                self.set_positions(update.chat_id, &update.positions);
+            }
+            Update::ChatPosition(update) => {
+                let positions = self.positions.entry(update.chat_id).or_default();
+                positions.retain(|position| position.list != update.position.list);
+                // A chat with a zero order is removed from the list
+                if update.position.order != 0 {
+                    positions.push(update.position.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set_positions(&mut self, chat_id: i64, positions: &[types::ChatPosition]) {
+        let positions = positions.iter().filter(|position| position.order != 0);
+        self.positions.insert(chat_id, positions.cloned().collect());
+    }
+
+    /// The ids of the chats in `list`, from the first to the last one.
+    pub(crate) fn chat_list(&self, list: &ChatList) -> Vec<i64> {
+        let mut chats: Vec<_> = self
+            .positions
+            .iter()
+            .filter_map(|(chat_id, positions)| {
+                let position = positions.iter().find(|position| position.list == *list)?;
+                Some((position.order, *chat_id))
+            })
+            .collect();
+        // The chats are sorted by descending order, then by descending id
+        chats.sort_unstable_by(|a, b| b.cmp(a));
+        chats.into_iter().map(|(_, chat_id)| chat_id).collect()
+    }
+}
+
+/// Whether `update` may change the position of a chat.
+fn is_position_update(update: &Update) -> bool {
+    matches!(
+        update,
+        Update::NewChat(_)
+            | Update::ChatLastMessage(_)
+            | Update::ChatDraftMessage(_)
+            | Update::ChatPosition(_)
+    )
+}
+
+/// The chat of a response, failing on the unrecognized constructors.
+// The chat is only boxed by the `box-large-variants` feature
+#[allow(clippy::useless_conversion)]
+pub(crate) fn chat_of(chat: enums::Chat) -> Result<types::Chat, TdError> {
+    match chat {
+        enums::Chat::Chat(chat) => Ok(*Box::<types::Chat>::from(chat)),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::Chat::Unrecognized => Err(TdError::unrecognized("Chat")),
+    }
+}
+
+/// The chat `chat_id`, known to TDLib from the updates or loaded from the
+/// server.
+pub async fn chat(chat_id: i64, client_id: i32) -> Result<types::Chat, TdError> {
+    functions::get_chat(chat_id, client_id)
+        .await
+        .and_then(chat_of)
+}
+
+/// Load the next `limit` chats of `list` with `loadChats`, returning `false`
+/// once all the chats of the list are loaded.
+///
+/// The loaded chats are reported by the updates, and then returned by
+/// [`Client::chat_list`].
+pub async fn load_chats(list: ChatList, limit: i32, client_id: i32) -> Result<bool, TdError> {
+    match functions::load_chats(Some(list), limit, client_id).await {
+        Ok(()) => Ok(true),
+        Err(TdError::Api(error)) if error.code == 404 => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+/// Load all the chats of `list`, returning their ids from the first to the
+/// last one.
+pub async fn load_all_chats(list: ChatList, client_id: i32) -> Result<Vec<i64>, TdError> {
+    while load_chats(list.clone(), LOAD_CHATS_LIMIT, client_id).await? {}
+    Ok(Client::from_id(client_id).chat_list(&list))
+}
+
+/// A stream of the chat list returned by [`watch_chat_list`].
+pub struct ChatListChanges {
+    client: Client,
+    list: ChatList,
+    updates: mpsc::UnboundedReceiver<Update>,
+    /// The last chat ids yielded, `None` before the first poll.
+    last: Option<Vec<i64>>,
+}
+
+/// Stream the ids of the chats in `list` of `client_id`, from the first to
+/// the last one, first as they are now and then every time they change, e.g.
+/// to keep the chat list of a UI up to date while the chats are loaded with
+/// [`load_chats`].
+pub fn watch_chat_list(list: ChatList, client_id: i32) -> ChatListChanges {
+    ChatListChanges {
+        client: Client::from_id(client_id),
+        list,
+        updates: updates::subscribe(client_id, is_position_update),
+        last: None,
+    }
+}
+
+impl Stream for ChatListChanges {
+    type Item = Vec<i64>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.last.is_none() {
+            let chat_ids = self.client.chat_list(&self.list);
+            self.last = Some(chat_ids.clone());
+            return Poll::Ready(Some(chat_ids));
+        }
+
+        // The state of the client is updated before the update is streamed
+        while ready!(Pin::new(&mut self.updates).poll_next(cx)).is_some() {
+            let chat_ids = self.client.chat_list(&self.list);
+            if self.last.as_ref() != Some(&chat_ids) {
+                self.last = Some(chat_ids.clone());
+                return Poll::Ready(Some(chat_ids));
+            }
+        }
+        // The client was closed
+        Poll::Ready(None)
+    }
+}
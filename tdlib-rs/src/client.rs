@@ -0,0 +1,228 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The [`Client`] handle, giving access to the state of a client tracked from
+//! its updates.
+use crate::chats::ChatPositions;
+use crate::enums::{self, AuthorizationState, ChatList, ConnectionState, Update};
+use crate::{functions, types, updates, OptionsCache, TdError};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The state of a client, tracked from its updates.
+#[derive(Default)]
+struct ClientState {
+    connection_state: Option<ConnectionState>,
+    options: OptionsCache,
+    chat_positions: ChatPositions,
+    chat_folders: Vec<types::ChatFolderInfo>,
+    /// The current user, once returned by [`Client::me`].
+    me: Option<types::User>,
+}
+
+static STATES: Lazy<Mutex<HashMap<i32, ClientState>>> = Lazy::new(Mutex::default);
+
+/// The user of a response, failing on the unrecognized constructors.
+// The user is only boxed by the `box-large-variants` feature
+#[allow(clippy::useless_conversion)]
+pub(crate) fn user_of(user: enums::User) -> Result<types::User, TdError> {
+    match user {
+        enums::User::User(user) => Ok(*Box::<types::User>::from(user)),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::User::Unrecognized => Err(TdError::unrecognized("User")),
+    }
+}
+
+/// Track the state of `client_id` from one of its updates.
+pub(crate) fn handle_update(update: &Update, client_id: i32) {
+    let mut states = STATES.lock().unwrap();
+    match update {
+        Update::ConnectionState(update) => {
+            states.entry(client_id).or_default().connection_state = Some(update.state.clone());
+        }
+        Update::Option(update) => {
+            let options = &mut states.entry(client_id).or_default().options;
+            options.set(&update.name, update.value.clone());
+        }
+        Update::AuthorizationState(update)
+            if matches!(update.authorization_state, AuthorizationState::Closed) =>
+        {
+            states.remove(&client_id);
+        }
+        // The current user changes with the authorization
+        Update::AuthorizationState(update)
+            if !matches!(update.authorization_state, AuthorizationState::Ready) =>
+        {
+            if let Some(state) = states.get_mut(&client_id) {
+                state.me = None;
+            }
+        }
+        Update::User(update) => {
+            let me = states
+                .get_mut(&client_id)
+                .and_then(|state| state.me.as_mut());
+            if let Some(me) = me.filter(|me| me.id == update.user.id) {
+                *me = update.user.clone();
+            }
+        }
+        Update::NewChat(_)
+        | Update::ChatLastMessage(_)
+        | Update::ChatDraftMessage(_)
+        | Update::ChatPosition(_) => {
+            let chat_positions = &mut states.entry(client_id).or_default().chat_positions;
+            chat_positions.handle_update(update);
+        }
+        Update::ChatFolders(update) => {
+            states.entry(client_id).or_default().chat_folders = update.chat_folders.clone();
+        }
+        _ => {}
+    }
+}
+
+/// A TDLib client, identified by its id.
+///
+/// The handle is a plain copy of the id, which can be passed to the generated
+/// functions with [`Client::id`]. The state it exposes is tracked from the
+/// updates of the client, so it's only up to date as long as the application
+/// keeps calling [`receive`](crate::receive).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Client {
+    id: i32,
+}
+
+impl Client {
+    /// Create a new client, through the installed [`Transport`](crate::Transport).
+    pub fn create() -> Self {
+        Self::from_id(crate::create_client())
+    }
+
+    /// The handle of the client with id `id`.
+    pub fn from_id(id: i32) -> Self {
+        Self { id }
+    }
+
+    /// The id of the client, to pass to the generated functions.
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// The last connection state reported by TDLib, or `None` if it hasn't
+    /// reported one yet.
+    pub fn connection_state(&self) -> Option<ConnectionState> {
+        let states = STATES.lock().unwrap();
+        states.get(&self.id)?.connection_state.clone()
+    }
+
+    /// A snapshot of the options of the client, as reported by TDLib.
+    pub fn options(&self) -> OptionsCache {
+        let states = STATES.lock().unwrap();
+        states
+            .get(&self.id)
+            .map(|state| state.options.clone())
+            .unwrap_or_default()
+    }
+
+    /// The ids of the chats in `list`, from the first to the last one, as
+    /// reported by TDLib so far.
+    ///
+    /// The chats are only reported once they are loaded, e.g. with
+    /// [`chats::load_chats`](crate::chats::load_chats).
+    pub fn chat_list(&self, list: &ChatList) -> Vec<i64> {
+        let states = STATES.lock().unwrap();
+        states
+            .get(&self.id)
+            .map(|state| state.chat_positions.chat_list(list))
+            .unwrap_or_default()
+    }
+
+    /// The chat folders of the user, in their order, as last reported by
+    /// TDLib.
+    pub fn chat_folders(&self) -> Vec<types::ChatFolderInfo> {
+        let states = STATES.lock().unwrap();
+        states
+            .get(&self.id)
+            .map(|state| state.chat_folders.clone())
+            .unwrap_or_default()
+    }
+
+    /// The current user, returned by `getMe` the first time and then kept up
+    /// to date from the updates of the user, e.g. to know the id of the user
+    /// without a request every time.
+    ///
+    /// The user is requested again after a change of the authorization, e.g.
+    /// once logged out.
+    pub async fn me(&self) -> Result<types::User, TdError> {
+        if let Some(me) = self.cached_me() {
+            return Ok(me);
+        }
+        let me = user_of(functions::get_me(self.id).await?)?;
+        let mut states = STATES.lock().unwrap();
+        states.entry(self.id).or_default().me = Some(me.clone());
+        Ok(me)
+    }
+
+    /// The current user, if returned by [`me`](Self::me) since the last
+    /// change of the authorization.
+    pub fn cached_me(&self) -> Option<types::User> {
+        let states = STATES.lock().unwrap();
+        states.get(&self.id)?.me.clone()
+    }
+
+    /// Wait for the next update of the client for which `matches` returns
+    /// `true`, failing with [`TdError::Timeout`] after `timeout`, if any, e.g.
+    ///
+    /// ```ignore
+    /// let created = client.wait_for(
+    ///     |update| matches!(update, Update::NewChat(update) if update.chat.title == "Team"),
+    ///     Some(Duration::from_secs(10)),
+    /// );
+    /// functions::create_new_basic_group_chat(user_ids, "Team".into(), 0, client.id()).await?;
+    /// let update = created.await?;
+    /// ```
+    ///
+    /// The wait starts when the method is called rather than when the future
+    /// is first polled, so that an update caused by a request sent meanwhile
+    /// is not missed. The timeout is checked by [`receive`](crate::receive),
+    /// so it can expire up to the timeout of `receive` late.
+    pub fn wait_for(
+        &self,
+        matches: impl FnMut(&Update) -> bool + Send + 'static,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = Result<Update, TdError>> + Send + 'static {
+        updates::wait_for_update(self.id, matches, timeout)
+    }
+
+    /// Wait until TDLib reports [`ConnectionState::Ready`], e.g. to hold back
+    /// the requests until the client is connected, failing with
+    /// [`TdError::Timeout`] after `timeout`.
+    ///
+    /// The timeout is checked by [`receive`](crate::receive), so it can expire
+    /// up to the timeout of `receive` late.
+    pub async fn await_connected(&self, timeout: Duration) -> Result<(), TdError> {
+        let is_ready = |update: &Update| match update {
+            Update::ConnectionState(update) => update.state == ConnectionState::Ready,
+            _ => false,
+        };
+        // Start waiting before checking the current state, so that an update
+        // received in the meantime is not missed
+        let ready = self.wait_for(is_ready, Some(timeout));
+        if self.connection_state() == Some(ConnectionState::Ready) {
+            return Ok(());
+        }
+        ready.await.map(|_| ())
+    }
+}
+
+impl From<Client> for i32 {
+    fn from(client: Client) -> Self {
+        client.id
+    }
+}
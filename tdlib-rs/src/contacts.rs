@@ -0,0 +1,112 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The contacts of the current user, loaded with `getContacts` and kept up to
+//! date from the `updateUser` updates.
+use crate::enums::{self, Update};
+use crate::{functions, types, updates, TdError};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use std::collections::BTreeSet;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// A contact to import, described by its phone number and its name.
+#[cfg(not(feature = "api-1.8.19"))]
+pub type NewContact = types::ImportedContact;
+
+/// A contact to import, described by its phone number and its name.
+#[cfg(feature = "api-1.8.19")]
+pub type NewContact = types::Contact;
+
+/// A change of the contacts streamed by [`Contacts`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContactChange {
+    /// The user was added to the contacts, boxed since the users are large.
+    Added(Box<types::User>),
+    /// The user with this id was removed from the contacts.
+    Removed(i64),
+}
+
+/// The contacts of the current user, returned by [`contacts`].
+///
+/// The contacts are a stream of their changes, which are applied to the set
+/// returned by [`Contacts::user_ids`] as they are streamed.
+pub struct Contacts {
+    client_id: i32,
+    user_ids: BTreeSet<i64>,
+    updates: mpsc::UnboundedReceiver<Update>,
+}
+
+/// Load the contacts of the current user of `client_id`.
+pub async fn contacts(client_id: i32) -> Result<Contacts, TdError> {
+    // Subscribe before loading the contacts, so that no change is missed
+    let updates = updates::subscribe(client_id, |update| matches!(update, Update::User(_)));
+    let user_ids = match functions::get_contacts(client_id).await? {
+        enums::Users::Users(users) => users.user_ids.iter().copied().collect(),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::Users::Unrecognized => return Err(TdError::unrecognized("Users")),
+    };
+    Ok(Contacts {
+        client_id,
+        user_ids,
+        updates,
+    })
+}
+
+impl Contacts {
+    /// The ids of the contacts, in ascending order.
+    pub fn user_ids(&self) -> impl Iterator<Item = i64> + '_ {
+        self.user_ids.iter().copied()
+    }
+
+    /// Whether the user `user_id` is a contact.
+    pub fn contains(&self, user_id: i64) -> bool {
+        self.user_ids.contains(&user_id)
+    }
+
+    /// Add the contacts by their phone numbers with `importContacts`,
+    /// returning the ids of the imported users, or 0 for the phone numbers
+    /// not registered on Telegram.
+    pub async fn import(
+        &self,
+        contacts: impl IntoIterator<Item = NewContact>,
+    ) -> Result<Vec<i64>, TdError> {
+        match functions::import_contacts(contacts, self.client_id).await? {
+            enums::ImportedContacts::ImportedContacts(imported) => Ok(imported.user_ids.clone()),
+            #[cfg(feature = "unrecognized-variants")]
+            enums::ImportedContacts::Unrecognized => Err(TdError::unrecognized("ImportedContacts")),
+        }
+    }
+
+    /// Remove the users from the contacts with `removeContacts`.
+    pub async fn remove(&self, user_ids: impl IntoIterator<Item = i64>) -> Result<(), TdError> {
+        functions::remove_contacts(user_ids, self.client_id).await
+    }
+}
+
+impl Stream for Contacts {
+    type Item = ContactChange;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        while let Some(update) = ready!(Pin::new(&mut self.updates).poll_next(cx)) {
+            let Update::User(update) = update else {
+                continue;
+            };
+            let user = &update.user;
+            if user.is_contact && self.user_ids.insert(user.id) {
+                return Poll::Ready(Some(ContactChange::Added(Box::new(user.clone()))));
+            }
+            if !user.is_contact && self.user_ids.remove(&user.id) {
+                return Poll::Ready(Some(ContactChange::Removed(user.id)));
+            }
+        }
+        // The client was closed
+        Poll::Ready(None)
+    }
+}
@@ -0,0 +1,691 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Builders of the contents of the messages to send, started from
+//! [`InputMessageContent::text`] or one of the media constructors, e.g.
+//!
+//! ```ignore
+//! let content = InputMessageContent::text("*Hello*, world\\!").markdown().build()?;
+//! let content = InputMessageContent::photo("cat.jpg").caption("A cat").build()?;
+//! ```
+use crate::enums::{InputMessageContent, PollType};
+use crate::{files, formatting, types, TdError, TdString};
+use std::path::Path;
+use std::time::Duration;
+#[cfg(feature = "bots-only-api")]
+use std::time::SystemTime;
+
+/// The amount of time `duration`, as represented by the generated types.
+#[cfg(feature = "duration")]
+fn seconds(duration: Duration) -> Duration {
+    duration
+}
+
+/// The amount of time `duration`, as represented by the generated types.
+#[cfg(not(feature = "duration"))]
+fn seconds(duration: Duration) -> i32 {
+    duration.as_secs().min(i32::MAX as u64) as i32
+}
+
+/// A text without entities.
+pub(crate) fn plain_text(text: TdString) -> types::FormattedText {
+    types::FormattedText {
+        text,
+        entities: Vec::new(),
+    }
+}
+
+/// A text, and how to parse its entities.
+#[derive(Clone, Debug)]
+enum Text {
+    Plain(TdString),
+    Markdown(TdString),
+    Html(TdString),
+    Formatted(types::FormattedText),
+}
+
+impl Text {
+    fn parse(self) -> Result<types::FormattedText, TdError> {
+        match self {
+            Text::Plain(text) => Ok(plain_text(text)),
+            Text::Markdown(text) => formatting::markdown(&text),
+            Text::Html(text) => formatting::html(&text),
+            Text::Formatted(text) => Ok(text),
+        }
+    }
+
+    /// The same text, parsed with `markup`.
+    fn with_markup(self, markup: fn(TdString) -> Text) -> Text {
+        match self {
+            Text::Plain(text) | Text::Markdown(text) | Text::Html(text) => markup(text),
+            Text::Formatted(text) => Text::Formatted(text),
+        }
+    }
+}
+
+impl InputMessageContent {
+    /// A text message, whose text is plain unless [`TextBuilder::markdown`]
+    /// or [`TextBuilder::html`] is called.
+    pub fn text(text: impl Into<TdString>) -> TextBuilder {
+        TextBuilder::new(Text::Plain(text.into()))
+    }
+
+    /// A text message of an already formatted text.
+    pub fn formatted_text(text: types::FormattedText) -> TextBuilder {
+        TextBuilder::new(Text::Formatted(text))
+    }
+
+    /// A photo message of the local file at `path`.
+    pub fn photo(path: impl AsRef<Path>) -> MediaBuilder {
+        MediaBuilder::new(Media::Photo, path.as_ref())
+    }
+
+    /// A video message of the local file at `path`.
+    pub fn video(path: impl AsRef<Path>) -> MediaBuilder {
+        MediaBuilder::new(Media::Video, path.as_ref())
+    }
+
+    /// An animation message, e.g. a GIF, of the local file at `path`.
+    pub fn animation(path: impl AsRef<Path>) -> MediaBuilder {
+        MediaBuilder::new(Media::Animation, path.as_ref())
+    }
+
+    /// A document message of the local file at `path`.
+    pub fn document(path: impl AsRef<Path>) -> MediaBuilder {
+        MediaBuilder::new(Media::Document, path.as_ref())
+    }
+
+    /// A poll asking `question`, whose options are added with
+    /// [`PollBuilder::option`].
+    pub fn poll(question: impl Into<TdString>) -> PollBuilder {
+        PollBuilder::new(question)
+    }
+}
+
+/// The builder of a text message, returned by [`InputMessageContent::text`].
+#[derive(Clone, Debug)]
+pub struct TextBuilder {
+    text: Text,
+    link_preview: bool,
+    clear_draft: bool,
+}
+
+impl TextBuilder {
+    fn new(text: Text) -> Self {
+        Self {
+            text,
+            link_preview: true,
+            clear_draft: false,
+        }
+    }
+
+    /// Parse the text with the MarkdownV2 syntax of the Bot API.
+    pub fn markdown(mut self) -> Self {
+        self.text = self.text.with_markup(Text::Markdown);
+        self
+    }
+
+    /// Parse the text with the HTML syntax of the Bot API.
+    pub fn html(mut self) -> Self {
+        self.text = self.text.with_markup(Text::Html);
+        self
+    }
+
+    /// Don't show the preview of the first link of the text.
+    pub fn without_link_preview(mut self) -> Self {
+        self.link_preview = false;
+        self
+    }
+
+    /// Clear the draft of the chat the message is sent to.
+    pub fn clear_draft(mut self) -> Self {
+        self.clear_draft = true;
+        self
+    }
+
+    /// Build the content, failing with [`TdError::Api`] if the text can't be
+    /// parsed.
+    pub fn build(self) -> Result<InputMessageContent, TdError> {
+        let text = types::InputMessageText {
+            text: self.text.parse()?,
+            #[cfg(not(feature = "api-1.8.19"))]
+            link_preview_options: (!self.link_preview).then(|| types::LinkPreviewOptions {
+                is_disabled: true,
+                ..Default::default()
+            }),
+            #[cfg(feature = "api-1.8.19")]
+            disable_web_page_preview: !self.link_preview,
+            clear_draft: self.clear_draft,
+        };
+        Ok(InputMessageContent::InputMessageText(text))
+    }
+}
+
+/// The kind of the media of a [`MediaBuilder`].
+#[derive(Clone, Copy, Debug)]
+enum Media {
+    Photo,
+    Video,
+    Animation,
+    Document,
+}
+
+/// The builder of a media message, returned by
+/// [`InputMessageContent::photo`], [`InputMessageContent::video`],
+/// [`InputMessageContent::animation`] and [`InputMessageContent::document`].
+///
+/// The options not supported by the media are ignored, e.g. the size of a
+/// document.
+#[derive(Clone, Debug)]
+pub struct MediaBuilder {
+    media: Media,
+    file: crate::enums::InputFile,
+    caption: Option<Text>,
+    /// How to parse the plain caption.
+    markup: fn(TdString) -> Text,
+    width: i32,
+    height: i32,
+    duration: Duration,
+    supports_streaming: bool,
+    has_spoiler: bool,
+    #[cfg_attr(feature = "api-1.8.19", allow(dead_code))]
+    show_caption_above_media: bool,
+}
+
+impl MediaBuilder {
+    fn new(media: Media, path: &Path) -> Self {
+        Self {
+            media,
+            file: files::local_file(path),
+            caption: None,
+            markup: Text::Plain,
+            width: 0,
+            height: 0,
+            duration: Duration::ZERO,
+            supports_streaming: false,
+            has_spoiler: false,
+            show_caption_above_media: false,
+        }
+    }
+
+    /// Set the caption of the media, up to `message_caption_length_max`
+    /// characters, which is plain unless [`MediaBuilder::markdown`] or
+    /// [`MediaBuilder::html`] is called.
+    pub fn caption(mut self, caption: impl Into<TdString>) -> Self {
+        self.caption = Some(Text::Plain(caption.into()));
+        self
+    }
+
+    /// Set the caption of the media to an already formatted text.
+    pub fn formatted_caption(mut self, caption: types::FormattedText) -> Self {
+        self.caption = Some(Text::Formatted(caption));
+        self
+    }
+
+    /// Parse the caption with the MarkdownV2 syntax of the Bot API.
+    pub fn markdown(mut self) -> Self {
+        self.markup = Text::Markdown;
+        self
+    }
+
+    /// Parse the caption with the HTML syntax of the Bot API.
+    pub fn html(mut self) -> Self {
+        self.markup = Text::Html;
+        self
+    }
+
+    /// Set the width and the height of a photo, a video or an animation.
+    pub fn size(mut self, width: i32, height: i32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Set the duration of a video or an animation, rounded down to the
+    /// second.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Mark a video as suitable for streaming.
+    pub fn supports_streaming(mut self) -> Self {
+        self.supports_streaming = true;
+        self
+    }
+
+    /// Cover a photo, a video or an animation with a spoiler animation.
+    pub fn spoiler(mut self) -> Self {
+        self.has_spoiler = true;
+        self
+    }
+
+    /// Show the caption above a photo, a video or an animation.
+    pub fn caption_above_media(mut self) -> Self {
+        self.show_caption_above_media = true;
+        self
+    }
+
+    /// Build the content, failing with [`TdError::Api`] if the caption can't
+    /// be parsed.
+    // The variants are only boxed by the `box-large-variants` feature
+    #[allow(clippy::useless_conversion)]
+    pub fn build(self) -> Result<InputMessageContent, TdError> {
+        let caption = match self.caption {
+            Some(caption) => Some(caption.with_markup(self.markup).parse()?),
+            None => None,
+        };
+        let content = match self.media {
+            Media::Photo => InputMessageContent::InputMessagePhoto(
+                types::InputMessagePhoto {
+                    photo: self.file,
+                    thumbnail: None,
+                    added_sticker_file_ids: Vec::new(),
+                    width: self.width,
+                    height: self.height,
+                    caption,
+                    #[cfg(not(feature = "api-1.8.19"))]
+                    show_caption_above_media: self.show_caption_above_media,
+                    self_destruct_type: None,
+                    has_spoiler: self.has_spoiler,
+                }
+                .into(),
+            ),
+            Media::Video => InputMessageContent::InputMessageVideo(
+                types::InputMessageVideo {
+                    video: self.file,
+                    thumbnail: None,
+                    #[cfg(not(feature = "api-1.8.19"))]
+                    cover: None,
+                    #[cfg(not(feature = "api-1.8.19"))]
+                    start_timestamp: seconds(Duration::ZERO),
+                    added_sticker_file_ids: Vec::new(),
+                    duration: seconds(self.duration),
+                    width: self.width,
+                    height: self.height,
+                    supports_streaming: self.supports_streaming,
+                    caption,
+                    #[cfg(not(feature = "api-1.8.19"))]
+                    show_caption_above_media: self.show_caption_above_media,
+                    self_destruct_type: None,
+                    has_spoiler: self.has_spoiler,
+                }
+                .into(),
+            ),
+            Media::Animation => InputMessageContent::InputMessageAnimation(
+                types::InputMessageAnimation {
+                    animation: self.file,
+                    thumbnail: None,
+                    added_sticker_file_ids: Vec::new(),
+                    duration: seconds(self.duration),
+                    width: self.width,
+                    height: self.height,
+                    caption,
+                    #[cfg(not(feature = "api-1.8.19"))]
+                    show_caption_above_media: self.show_caption_above_media,
+                    has_spoiler: self.has_spoiler,
+                }
+                .into(),
+            ),
+            Media::Document => InputMessageContent::InputMessageDocument(
+                types::InputMessageDocument {
+                    document: self.file,
+                    thumbnail: None,
+                    disable_content_type_detection: false,
+                    caption,
+                }
+                .into(),
+            ),
+        };
+        Ok(content)
+    }
+}
+
+/// The minimum and maximum number of options of a poll.
+const POLL_OPTIONS: std::ops::RangeInclusive<usize> = 2..=10;
+/// The maximum length of the question of a poll, in characters.
+#[cfg(not(feature = "bots-only-api"))]
+const POLL_QUESTION_LENGTH_MAX: usize = 255;
+/// The maximum length of the question of a poll sent by a bot, in
+/// characters.
+#[cfg(feature = "bots-only-api")]
+const POLL_QUESTION_LENGTH_MAX: usize = 300;
+/// The maximum length of an option of a poll, in characters.
+const POLL_OPTION_LENGTH_MAX: usize = 100;
+/// The maximum length of the explanation of a quiz, in characters.
+const QUIZ_EXPLANATION_LENGTH_MAX: usize = 200;
+
+/// The Unix timestamp `time`, as represented by the generated types.
+#[cfg(all(feature = "bots-only-api", feature = "time"))]
+fn unix_time(time: SystemTime) -> time::OffsetDateTime {
+    time.into()
+}
+
+/// The Unix timestamp `time`, as represented by the generated types.
+#[cfg(all(feature = "bots-only-api", feature = "chrono", not(feature = "time")))]
+fn unix_time(time: SystemTime) -> chrono::DateTime<chrono::Utc> {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    chrono::DateTime::from_timestamp(seconds.as_secs() as i64, 0).unwrap_or_default()
+}
+
+/// The Unix timestamp `time`, as represented by the generated types.
+#[cfg(all(
+    feature = "bots-only-api",
+    not(any(feature = "time", feature = "chrono"))
+))]
+fn unix_time(time: SystemTime) -> i32 {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    seconds.as_secs() as i32
+}
+
+/// The builder of a poll, returned by [`InputMessageContent::poll`], e.g.
+///
+/// ```ignore
+/// let content = PollBuilder::new("What's the capital of Italy?")
+///     .options(["Milan", "Rome", "Turin"])
+///     .quiz(1)
+///     .explanation("Rome is the capital since 1871")
+///     .build()?;
+/// ```
+///
+/// The poll is anonymous and has a single answer unless
+/// [`PollBuilder::public`] or [`PollBuilder::multiple_answers`] is called.
+#[derive(Clone, Debug)]
+pub struct PollBuilder {
+    question: TdString,
+    options: Vec<TdString>,
+    is_anonymous: bool,
+    allow_multiple_answers: bool,
+    correct_option: Option<usize>,
+    explanation: Option<TdString>,
+    #[cfg(feature = "bots-only-api")]
+    open_period: Duration,
+    #[cfg(feature = "bots-only-api")]
+    close_date: Option<SystemTime>,
+    #[cfg(feature = "bots-only-api")]
+    is_closed: bool,
+}
+
+impl PollBuilder {
+    /// A poll asking `question`, up to 255 characters, or 300 for the bots
+    /// with the `bots-only-api` feature.
+    pub fn new(question: impl Into<TdString>) -> Self {
+        Self {
+            question: question.into(),
+            options: Vec::new(),
+            is_anonymous: true,
+            allow_multiple_answers: false,
+            correct_option: None,
+            explanation: None,
+            #[cfg(feature = "bots-only-api")]
+            open_period: Duration::ZERO,
+            #[cfg(feature = "bots-only-api")]
+            close_date: None,
+            #[cfg(feature = "bots-only-api")]
+            is_closed: false,
+        }
+    }
+
+    /// Add an option, up to 100 characters.
+    pub fn option(mut self, option: impl Into<TdString>) -> Self {
+        self.options.push(option.into());
+        self
+    }
+
+    /// Add several options, up to 100 characters each.
+    pub fn options<T: Into<TdString>>(mut self, options: impl IntoIterator<Item = T>) -> Self {
+        self.options.extend(options.into_iter().map(Into::into));
+        self
+    }
+
+    /// Show the voters of the poll, which then can't be sent to channels.
+    pub fn public(mut self) -> Self {
+        self.is_anonymous = false;
+        self
+    }
+
+    /// Allow choosing several options, except in a quiz.
+    pub fn multiple_answers(mut self) -> Self {
+        self.allow_multiple_answers = true;
+        self
+    }
+
+    /// Make the poll a quiz, whose correct option is the one at the index
+    /// `correct_option`.
+    pub fn quiz(mut self, correct_option: usize) -> Self {
+        self.correct_option = Some(correct_option);
+        self
+    }
+
+    /// Set the explanation of the answer of a quiz, up to 200 characters,
+    /// shown when a wrong option is chosen.
+    pub fn explanation(mut self, explanation: impl Into<TdString>) -> Self {
+        self.explanation = Some(explanation.into());
+        self
+    }
+
+    /// Close the poll `open_period` after it's sent, from 5 to 600 seconds.
+    #[cfg(feature = "bots-only-api")]
+    pub fn open_period(mut self, open_period: Duration) -> Self {
+        self.open_period = open_period;
+        self
+    }
+
+    /// Close the poll at `close_date`, from 5 to 600 seconds in the future.
+    #[cfg(feature = "bots-only-api")]
+    pub fn close_date(mut self, close_date: SystemTime) -> Self {
+        self.close_date = Some(close_date);
+        self
+    }
+
+    /// Send the poll already closed.
+    #[cfg(feature = "bots-only-api")]
+    pub fn closed(mut self) -> Self {
+        self.is_closed = true;
+        self
+    }
+
+    /// Check the lengths and the number of the options, failing like TDLib
+    /// with [`TdError::Api`].
+    fn validate(&self) -> Result<(), TdError> {
+        let length = |text: &TdString| text.chars().count();
+        if !(1..=POLL_QUESTION_LENGTH_MAX).contains(&length(&self.question)) {
+            return Err(TdError::invalid(&format!(
+                "Poll question length must be between 1 and {POLL_QUESTION_LENGTH_MAX}"
+            )));
+        }
+        if !POLL_OPTIONS.contains(&self.options.len()) {
+            return Err(TdError::invalid("Poll must have between 2 and 10 options"));
+        }
+        let options = self.options.iter();
+        if options
+            .map(length)
+            .any(|length| !(1..=POLL_OPTION_LENGTH_MAX).contains(&length))
+        {
+            return Err(TdError::invalid(
+                "Poll option length must be between 1 and 100",
+            ));
+        }
+        if let Some(correct_option) = self.correct_option {
+            if correct_option >= self.options.len() {
+                return Err(TdError::invalid("Wrong correct option"));
+            }
+            if self.allow_multiple_answers {
+                return Err(TdError::invalid("Quiz can't have multiple answers"));
+            }
+        }
+        if self.explanation.as_ref().map_or(0, length) > QUIZ_EXPLANATION_LENGTH_MAX {
+            return Err(TdError::invalid(
+                "Quiz explanation length must be at most 200",
+            ));
+        }
+        #[cfg(feature = "bots-only-api")]
+        {
+            let period = 5..=600;
+            if !self.open_period.is_zero() && !period.contains(&self.open_period.as_secs()) {
+                return Err(TdError::invalid(
+                    "Poll open period must be between 5 and 600 seconds",
+                ));
+            }
+            if let Some(close_date) = self.close_date {
+                let close_in = close_date.duration_since(SystemTime::now());
+                if !period.contains(&close_in.unwrap_or_default().as_secs()) {
+                    return Err(TdError::invalid(
+                        "Poll close date must be between 5 and 600 seconds in the future",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the content, failing with [`TdError::Api`] if the poll is
+    /// invalid.
+    // The variants are only boxed by the `box-large-variants` feature
+    #[allow(clippy::useless_conversion)]
+    pub fn build(self) -> Result<InputMessageContent, TdError> {
+        self.validate()?;
+        let r#type = match self.correct_option {
+            Some(correct_option) => PollType::Quiz(types::PollTypeQuiz {
+                correct_option_id: correct_option as i32,
+                explanation: plain_text(self.explanation.unwrap_or_default()),
+            }),
+            None => PollType::Regular(types::PollTypeRegular {
+                allow_multiple_answers: self.allow_multiple_answers,
+            }),
+        };
+        let poll = types::InputMessagePoll {
+            #[cfg(not(feature = "api-1.8.19"))]
+            question: plain_text(self.question),
+            #[cfg(not(feature = "api-1.8.19"))]
+            options: self.options.into_iter().map(plain_text).collect(),
+            #[cfg(feature = "api-1.8.19")]
+            question: self.question,
+            #[cfg(feature = "api-1.8.19")]
+            options: self.options,
+            is_anonymous: self.is_anonymous,
+            r#type,
+            #[cfg(all(feature = "bots-only-api", feature = "duration"))]
+            open_period: self.open_period,
+            #[cfg(all(feature = "bots-only-api", not(feature = "duration")))]
+            open_period: self.open_period.as_secs() as i32,
+            #[cfg(feature = "bots-only-api")]
+            close_date: unix_time(self.close_date.unwrap_or(SystemTime::UNIX_EPOCH)),
+            #[cfg(feature = "bots-only-api")]
+            is_closed: self.is_closed,
+        };
+        Ok(InputMessageContent::InputMessagePoll(poll.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll() -> PollBuilder {
+        PollBuilder::new("Question?").options(["Yes", "No"])
+    }
+
+    fn error(poll: PollBuilder) -> String {
+        poll.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn check_poll_options() {
+        assert!(poll().validate().is_ok());
+        let too_few = PollBuilder::new("Question?").option("Yes");
+        assert_eq!(
+            error(too_few),
+            "TDLib error 400: Poll must have between 2 and 10 options"
+        );
+        assert!(poll().options(["a"; 8]).validate().is_ok());
+        assert_eq!(
+            error(poll().options(["a"; 9])),
+            "TDLib error 400: Poll must have between 2 and 10 options"
+        );
+    }
+
+    #[test]
+    fn check_poll_lengths() {
+        let question = "é".repeat(POLL_QUESTION_LENGTH_MAX);
+        assert!(PollBuilder::new(question.clone())
+            .options(["Yes", "No"])
+            .validate()
+            .is_ok());
+        let too_long = PollBuilder::new(question + "é").options(["Yes", "No"]);
+        let question_error = format!(
+            "TDLib error 400: Poll question length must be between 1 and {POLL_QUESTION_LENGTH_MAX}"
+        );
+        assert_eq!(error(too_long), question_error);
+        let empty = PollBuilder::new("").options(["Yes", "No"]);
+        assert_eq!(error(empty), question_error);
+
+        let option_error = "TDLib error 400: Poll option length must be between 1 and 100";
+        assert!(poll().option("😀".repeat(100)).validate().is_ok());
+        assert_eq!(error(poll().option("😀".repeat(101))), option_error);
+        assert_eq!(error(poll().option("")), option_error);
+
+        let explanation_error = "TDLib error 400: Quiz explanation length must be at most 200";
+        assert!(poll()
+            .quiz(0)
+            .explanation("a".repeat(200))
+            .validate()
+            .is_ok());
+        assert_eq!(
+            error(poll().quiz(0).explanation("a".repeat(201))),
+            explanation_error
+        );
+    }
+
+    #[test]
+    fn check_poll_quiz() {
+        assert!(poll().quiz(1).validate().is_ok());
+        assert_eq!(
+            error(poll().quiz(2)),
+            "TDLib error 400: Wrong correct option"
+        );
+        assert_eq!(
+            error(poll().quiz(0).multiple_answers()),
+            "TDLib error 400: Quiz can't have multiple answers"
+        );
+        assert!(poll().multiple_answers().validate().is_ok());
+    }
+
+    #[cfg(feature = "bots-only-api")]
+    #[test]
+    fn check_poll_open_period() {
+        let period_error = "TDLib error 400: Poll open period must be between 5 and 600 seconds";
+        for seconds in [5, 600] {
+            let poll = poll().open_period(Duration::from_secs(seconds));
+            assert!(poll.validate().is_ok());
+        }
+        for seconds in [1, 4, 601] {
+            let poll = poll().open_period(Duration::from_secs(seconds));
+            assert_eq!(error(poll), period_error);
+        }
+        // The zero period doesn't close the poll
+        assert!(poll().open_period(Duration::ZERO).validate().is_ok());
+    }
+
+    #[cfg(feature = "bots-only-api")]
+    #[test]
+    fn check_poll_close_date() {
+        let close_date = SystemTime::now() + Duration::from_secs(60);
+        assert!(poll().close_date(close_date).validate().is_ok());
+        let date_error =
+            "TDLib error 400: Poll close date must be between 5 and 600 seconds in the future";
+        let past = SystemTime::now() - Duration::from_secs(60);
+        assert_eq!(error(poll().close_date(past)), date_error);
+        let far = SystemTime::now() + Duration::from_secs(3600);
+        assert_eq!(error(poll().close_date(far)), date_error);
+    }
+}
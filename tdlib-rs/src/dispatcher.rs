@@ -0,0 +1,188 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The [`UpdateDispatcher`], calling the handlers registered for the types of
+//! the updates instead of matching every update by hand.
+use crate::enums::Update;
+use crate::filters::UpdateFilter;
+use crate::Client;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A handler returning whether it handled the update.
+type Handler = Box<dyn FnMut(&Update, i32) -> bool + Send>;
+
+/// The handler of the updates without any other handler.
+type Fallback = Box<dyn FnMut(&Update) + Send>;
+
+/// The spawner running the futures of the async handlers.
+type Spawner = Arc<dyn Fn(HandlerFuture) + Send + Sync>;
+
+/// The future of an async handler, run by the spawner of the dispatcher.
+pub type HandlerFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// An async handler of the updates of type [`Update`](Self::Update),
+/// usually implemented with the `#[td_handler]` attribute of the `macros`
+/// feature, and registered with [`UpdateDispatcher::handler`].
+pub trait UpdateHandler: Send + 'static {
+    /// The type of the updates handled, e.g. `types::UpdateNewMessage`.
+    type Update: Clone;
+
+    /// Handle `update`, received by `client`.
+    fn handle(&self, update: Self::Update, client: Client) -> HandlerFuture;
+}
+
+/// The output of the async handlers, either nothing or a result whose
+/// errors are logged.
+pub trait HandlerOutput {
+    /// Report the output of the handler `handler`.
+    fn report(self, handler: &str);
+}
+
+impl HandlerOutput for () {
+    fn report(self, _handler: &str) {}
+}
+
+impl<E: fmt::Display> HandlerOutput for Result<(), E> {
+    fn report(self, handler: &str) {
+        if let Err(error) = self {
+            log::error!("Update handler {handler} failed: {error}");
+        }
+    }
+}
+
+/// The handlers of the updates by their type, e.g.
+///
+/// ```ignore
+/// let mut dispatcher = UpdateDispatcher::new()
+///     .on(|update: &types::UpdateNewMessage| println!("{:?}", update.message))
+///     .on(|update: &types::UpdateUser| println!("{}", update.user))
+///     .otherwise(|update| log::debug!("Unhandled update: {update:?}"));
+/// while let Some((update, client_id)) = tdlib_rs::receive() {
+///     dispatcher.dispatch(&update, client_id);
+/// }
+/// ```
+#[derive(Default)]
+pub struct UpdateDispatcher {
+    handlers: Vec<Handler>,
+    fallback: Option<Fallback>,
+    spawner: Option<Spawner>,
+}
+
+impl UpdateDispatcher {
+    /// A dispatcher without handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `handler` for the updates of type `T`, e.g.
+    /// `types::UpdateNewMessage`, after the handlers registered so far.
+    pub fn on<T>(mut self, mut handler: impl FnMut(&T) + Send + 'static) -> Self
+    where
+        for<'a> &'a T: TryFrom<&'a Update>,
+    {
+        self.handlers
+            .push(Box::new(move |update, _| match <&T>::try_from(update) {
+                Ok(update) => {
+                    handler(update);
+                    true
+                }
+                Err(_) => false,
+            }));
+        self
+    }
+
+    /// Register `handler` for the updates passing `filter`, after the
+    /// handlers registered so far, e.g.
+    /// `.when(filters::by_chat(chat_id), |update| println!("{update:?}"))`.
+    pub fn when(
+        mut self,
+        filter: UpdateFilter,
+        mut handler: impl FnMut(&Update) + Send + 'static,
+    ) -> Self {
+        self.handlers.push(Box::new(move |update, _| {
+            if !filter.matches(update) {
+                return false;
+            }
+            handler(update);
+            true
+        }));
+        self
+    }
+
+    /// Register the async `handler` after the handlers registered so far,
+    /// e.g. a function with the `#[td_handler]` attribute:
+    ///
+    /// ```ignore
+    /// #[td_handler]
+    /// async fn on_new_message(update: types::UpdateNewMessage, client: Client) -> Result<(), TdError> {
+    ///     functions::view_messages(update.message.chat_id, vec![update.message.id], None, false, client.id()).await
+    /// }
+    ///
+    /// let dispatcher = UpdateDispatcher::new()
+    ///     .spawn_with(|future| drop(tokio::spawn(future)))
+    ///     .handler(on_new_message);
+    /// ```
+    ///
+    /// The handler gets a copy of the update, and its future is run by the
+    /// spawner of the dispatcher.
+    ///
+    /// # Panics
+    ///
+    /// If no spawner was set before with [`spawn_with`](Self::spawn_with).
+    pub fn handler<H>(mut self, handler: H) -> Self
+    where
+        H: UpdateHandler,
+        for<'a> &'a H::Update: TryFrom<&'a Update>,
+    {
+        let spawner = self
+            .spawner
+            .clone()
+            .expect("the async handlers need a spawner, set with `UpdateDispatcher::spawn_with`");
+        self.handlers.push(Box::new(move |update, client_id| {
+            let Ok(update) = <&H::Update>::try_from(update) else {
+                return false;
+            };
+            spawner(handler.handle(update.clone(), Client::from_id(client_id)));
+            true
+        }));
+        self
+    }
+
+    /// Run the futures of the async handlers registered afterwards with
+    /// `spawn`, e.g. `|future| drop(tokio::spawn(future))`.
+    pub fn spawn_with(mut self, spawn: impl Fn(HandlerFuture) + Send + Sync + 'static) -> Self {
+        self.spawner = Some(Arc::new(spawn));
+        self
+    }
+
+    /// Register `handler` for the updates without any other handler.
+    pub fn otherwise(mut self, handler: impl FnMut(&Update) + Send + 'static) -> Self {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    /// Call the handlers registered for the type of `update`, or for a filter
+    /// it passes, received by the client `client_id`, in the order they were
+    /// registered, or else the fallback handler, returning whether any handler
+    /// was called.
+    pub fn dispatch(&mut self, update: &Update, client_id: i32) -> bool {
+        let mut is_handled = false;
+        for handler in &mut self.handlers {
+            is_handled |= handler(update, client_id);
+        }
+        if !is_handled {
+            if let Some(fallback) = &mut self.fallback {
+                fallback(update);
+            }
+        }
+        is_handled
+    }
+}
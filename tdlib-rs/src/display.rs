@@ -0,0 +1,83 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `Display` implementations for the user-facing types, so that they can be
+//! logged and rendered without formatting helpers.
+use crate::{enums, types};
+use std::fmt;
+
+/// Formats as `"<code> <message>"` (e.g. `"401 UNAUTHORIZED"`).
+impl fmt::Display for types::Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.message)
+    }
+}
+
+/// Formats as the plain text, without the entities.
+impl fmt::Display for types::FormattedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl fmt::Display for types::Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Formats as the full name of the user, falling back to the first active
+/// username (e.g. `"@durov"`) and then to the user identifier.
+impl fmt::Display for types::User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let first_name: &str = &self.first_name;
+        let last_name: &str = &self.last_name;
+        match (first_name.is_empty(), last_name.is_empty()) {
+            (false, false) => return write!(f, "{first_name} {last_name}"),
+            (false, true) => return f.write_str(first_name),
+            (true, false) => return f.write_str(last_name),
+            (true, true) => {}
+        }
+
+        let username = self
+            .usernames
+            .as_ref()
+            .and_then(|usernames| usernames.active_usernames.first());
+        match username {
+            Some(username) => write!(f, "@{username}"),
+            None => write!(f, "User {}", self.id),
+        }
+    }
+}
+
+/// Formats as the title of the chat.
+impl fmt::Display for types::Chat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.title)
+    }
+}
+
+/// Delegates `Display` for the single-variant enums returned by the
+/// functions (e.g. `functions::get_me`) to the wrapped type.
+macro_rules! display_enum {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl fmt::Display for enums::$name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    match self {
+                        enums::$name::$name(inner) => inner.fmt(f),
+                        #[cfg(feature = "unrecognized-variants")]
+                        enums::$name::Unrecognized => f.write_str("<unrecognized>"),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+display_enum!(Error, FormattedText, Text, User, Chat);
@@ -0,0 +1,147 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The encryption key of the TDLib database, passed to `setTdlibParameters`
+//! and changed with `setDatabaseEncryptionKey`.
+//!
+//! The database isn't encrypted with the empty key, the default, so the
+//! applications should generate a [`DatabaseKey`] and keep it in a
+//! [`Keystore`], e.g. backed by the keychain of the OS.
+use crate::{functions, TdError};
+use std::fmt;
+use std::io;
+
+/// The length of the keys generated by [`DatabaseKey::generate`], in bytes.
+pub const KEY_LENGTH: usize = 32;
+
+/// The encryption key of a database.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct DatabaseKey(Vec<u8>);
+
+impl DatabaseKey {
+    /// Generate a random key with the random number generator of the OS.
+    pub fn generate() -> Result<Self, TdError> {
+        let mut key = vec![0; KEY_LENGTH];
+        getrandom::getrandom(&mut key).map_err(|error| TdError::Io(error.into()))?;
+        Ok(Self(key))
+    }
+
+    /// The key `bytes`, e.g. loaded from a keystore.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        Self(bytes.into())
+    }
+
+    /// The bytes of the key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Whether the key is empty, i.e. the database isn't encrypted.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The key as passed to `setTdlibParameters`, encoded as base64 unless
+    /// the `bytes` feature is enabled.
+    ///
+    /// The copy isn't wiped when dropped, even with the `zeroize` feature.
+    #[cfg(feature = "bytes")]
+    pub fn encoded(&self) -> bytes::Bytes {
+        crate::bytes_field(&self.0)
+    }
+
+    /// The key as passed to `setTdlibParameters`, encoded as base64 unless
+    /// the `bytes` feature is enabled.
+    ///
+    /// The copy isn't wiped when dropped, even with the `zeroize` feature.
+    #[cfg(not(feature = "bytes"))]
+    pub fn encoded(&self) -> crate::TdString {
+        crate::bytes_field(&self.0)
+    }
+}
+
+#[cfg(not(feature = "unredacted-debug"))]
+impl fmt::Debug for DatabaseKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DatabaseKey(<redacted>)")
+    }
+}
+
+#[cfg(feature = "unredacted-debug")]
+impl fmt::Debug for DatabaseKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DatabaseKey").field(&self.0).finish()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for DatabaseKey {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.0);
+    }
+}
+
+/// The storage of the keys of the databases, each identified by the name of
+/// its account, e.g. backed by the keychain of the OS.
+pub trait Keystore {
+    /// The key of `account`, if stored.
+    fn load(&mut self, account: &str) -> io::Result<Option<DatabaseKey>>;
+
+    /// Store `key` as the key of `account`, replacing the previous one.
+    fn store(&mut self, account: &str, key: &DatabaseKey) -> io::Result<()>;
+}
+
+/// The key of `account` stored in `keystore`, generated and stored first if
+/// missing, e.g. to pass to `setTdlibParameters`.
+pub fn load_or_generate_key(
+    keystore: &mut impl Keystore,
+    account: &str,
+) -> Result<DatabaseKey, TdError> {
+    if let Some(key) = keystore.load(account).map_err(TdError::Io)? {
+        return Ok(key);
+    }
+    let key = DatabaseKey::generate()?;
+    keystore.store(account, &key).map_err(TdError::Io)?;
+    Ok(key)
+}
+
+/// Change the key of the database of `account`, used by the client
+/// `client_id`, to `key`, and store it in `keystore`.
+///
+/// If the key can't be stored, the database is encrypted again with the
+/// previous key, so that it stays readable with the stored key.
+pub async fn set_key(
+    keystore: &mut impl Keystore,
+    account: &str,
+    key: &DatabaseKey,
+    client_id: i32,
+) -> Result<(), TdError> {
+    let previous = keystore.load(account).map_err(TdError::Io)?;
+    functions::set_database_encryption_key(key.encoded(), client_id).await?;
+    if let Err(error) = keystore.store(account, key) {
+        let previous = previous.unwrap_or_default();
+        functions::set_database_encryption_key(previous.encoded(), client_id).await?;
+        return Err(TdError::Io(error));
+    }
+    Ok(())
+}
+
+/// Change the key of the database of `account`, used by the client
+/// `client_id`, to a new random key, stored in `keystore`.
+///
+/// The database must have been opened with the key stored in `keystore`, or
+/// with the empty key if none is stored.
+pub async fn rotate_key(
+    keystore: &mut impl Keystore,
+    account: &str,
+    client_id: i32,
+) -> Result<DatabaseKey, TdError> {
+    let key = DatabaseKey::generate()?;
+    set_key(keystore, account, &key, client_id).await?;
+    Ok(key)
+}
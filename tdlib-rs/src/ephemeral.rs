@@ -0,0 +1,153 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Throwaway sessions, e.g. for the integration tests, with their database
+//! and files stored in a unique temporary directory deleted on drop.
+use crate::{functions, TdError, TdString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of directories created by the process, making their names
+/// unique even when created at the same time.
+static DIRECTORY_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// A unique temporary directory for the database and the files of a client,
+/// deleted with its content on drop, e.g.
+///
+/// ```ignore
+/// let directory = EphemeralDirectory::new()?.in_memory().use_test_dc(true);
+/// let client = Client::create();
+/// functions::get_authorization_state(client.id()).await?;
+/// directory.set_parameters(api_id, api_hash, client.id()).await?;
+/// ```
+///
+/// The client should be closed before the directory is dropped, as TDLib
+/// keeps writing to it until then.
+#[derive(Debug)]
+pub struct EphemeralDirectory {
+    /// The directory, empty once kept.
+    path: PathBuf,
+    use_file_database: bool,
+    use_test_dc: bool,
+}
+
+impl EphemeralDirectory {
+    /// Create a directory in the temporary directory of the system.
+    pub fn new() -> io::Result<Self> {
+        Self::new_in(std::env::temp_dir())
+    }
+
+    /// Create a directory in `parent`, which must exist.
+    pub fn new_in(parent: impl AsRef<Path>) -> io::Result<Self> {
+        let process_id = std::process::id();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.subsec_nanos());
+        loop {
+            let count = DIRECTORY_COUNT.fetch_add(1, Ordering::Relaxed);
+            let name = format!("tdlib-rs-{process_id}-{nanos:x}-{count}");
+            let path = parent.as_ref().join(name);
+            match fs::create_dir(&path) {
+                Ok(()) => {
+                    return Ok(Self {
+                        path,
+                        use_file_database: true,
+                        use_test_dc: false,
+                    })
+                }
+                // Left over by a previous process with the same identifier
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Keep the information of the files, the chats and the messages in
+    /// memory only, TDLib still storing the authorization in the directory.
+    pub fn in_memory(mut self) -> Self {
+        self.use_file_database = false;
+        self
+    }
+
+    /// Set whether to use the Telegram test environment.
+    pub fn use_test_dc(mut self, use_test_dc: bool) -> Self {
+        self.use_test_dc = use_test_dc;
+        self
+    }
+
+    /// The directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The directory of the database, in the directory.
+    pub fn database_directory(&self) -> PathBuf {
+        self.path.join("database")
+    }
+
+    /// The directory of the files, in the directory.
+    pub fn files_directory(&self) -> PathBuf {
+        self.path.join("files")
+    }
+
+    /// Keep the directory instead of deleting it on drop, e.g. to inspect it
+    /// after a failed test, returning its path.
+    pub fn keep(mut self) -> PathBuf {
+        std::mem::take(&mut self.path)
+    }
+
+    /// Set the parameters of the client `client_id` to use the directory,
+    /// with the application `api_id` and `api_hash`, once the client asks for
+    /// them.
+    pub async fn set_parameters(
+        &self,
+        api_id: i32,
+        api_hash: impl Into<TdString>,
+        client_id: i32,
+    ) -> Result<(), TdError> {
+        // A `String` is converted to both the `PathBuf` and the `TdString`
+        // paths
+        let path = |directory: PathBuf| directory.to_string_lossy().into_owned();
+        // The chat and message databases need the file database
+        let use_databases = self.use_file_database;
+        functions::set_tdlib_parameters(
+            self.use_test_dc,
+            path(self.database_directory()),
+            path(self.files_directory()),
+            "",
+            use_databases,
+            use_databases,
+            use_databases,
+            false,
+            api_id,
+            api_hash,
+            "en",
+            "Desktop",
+            "",
+            env!("CARGO_PKG_VERSION"),
+            #[cfg(feature = "api-1.8.19")]
+            false,
+            #[cfg(feature = "api-1.8.19")]
+            false,
+            client_id,
+        )
+        .await
+    }
+}
+
+impl Drop for EphemeralDirectory {
+    fn drop(&mut self) {
+        if !self.path.as_os_str().is_empty() {
+            // The directory may already be deleted, e.g. by the test
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
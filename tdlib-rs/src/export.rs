@@ -0,0 +1,243 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The export of the chats of the user, e.g. for the backup tools, going
+//! through their histories and downloading their media.
+//!
+//! TDLib doesn't expose the takeout sessions of the Telegram API, so the
+//! export uses the regular requests, and spaces out the downloads to avoid
+//! hitting the flood limits.
+use crate::enums::{ChatList, MessageContent};
+use crate::{chats, files, messages, types, updates, TdError};
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::Duration;
+
+/// The priority of the downloads of the export, the lowest one, so that the
+/// downloads of the application go first.
+const DOWNLOAD_PRIORITY: i32 = 1;
+
+/// The default interval between two downloads of the export.
+const DEFAULT_DOWNLOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The progress of an export, reported with every [`ExportEvent`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExportProgress {
+    /// The number of chats to export.
+    pub chat_count: usize,
+    /// The number of chats exported so far.
+    pub exported_chats: usize,
+    /// The number of messages exported so far.
+    pub messages: usize,
+    /// The number of files downloaded so far.
+    pub files: usize,
+    /// The total size of the files downloaded so far, in bytes.
+    pub file_bytes: i64,
+    /// The number of files skipped so far, because they were too large or
+    /// couldn't be downloaded.
+    pub skipped_files: usize,
+}
+
+/// A step of an export, reported by [`ChatExport::run`].
+#[derive(Clone, Copy, Debug)]
+pub enum ExportEvent<'a> {
+    /// The export of the chat started.
+    ChatStarted(&'a types::Chat),
+    /// A message of the chat, from the newest to the oldest.
+    Message(&'a types::Message),
+    /// A file of the last message, downloaded to `path`.
+    File {
+        message: &'a types::Message,
+        file: &'a types::File,
+        path: &'a Path,
+    },
+    /// The export of the chat finished.
+    ChatFinished(&'a types::Chat),
+}
+
+/// The export of the chats of the user, e.g.
+///
+/// ```ignore
+/// let progress = ChatExport::new()
+///     .max_file_size(50 << 20)
+///     .run(
+///         |event, progress| match event {
+///             ExportEvent::Message(message) => archive.add_message(message),
+///             ExportEvent::File { path, .. } => archive.add_file(path),
+///             ExportEvent::ChatFinished(chat) => {
+///                 println!("{}/{} chats", progress.exported_chats, progress.chat_count)
+///             }
+///             _ => {}
+///         },
+///         client.id(),
+///     )
+///     .await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChatExport {
+    list: ChatList,
+    chat_ids: Option<Vec<i64>>,
+    download_media: bool,
+    max_file_size: Option<i64>,
+    download_interval: Duration,
+}
+
+impl Default for ChatExport {
+    fn default() -> Self {
+        Self {
+            list: ChatList::Main,
+            chat_ids: None,
+            download_media: true,
+            max_file_size: None,
+            download_interval: DEFAULT_DOWNLOAD_INTERVAL,
+        }
+    }
+}
+
+impl ChatExport {
+    /// The export of all the chats of the main chat list, with their media.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Export all the chats of `list` instead of the main chat list.
+    pub fn chat_list(mut self, list: ChatList) -> Self {
+        self.list = list;
+        self
+    }
+
+    /// Only export these chats, in this order.
+    pub fn chats(mut self, chat_ids: impl IntoIterator<Item = i64>) -> Self {
+        self.chat_ids = Some(chat_ids.into_iter().collect());
+        self
+    }
+
+    /// Only export the messages, without downloading their media.
+    pub fn without_media(mut self) -> Self {
+        self.download_media = false;
+        self
+    }
+
+    /// Skip the files larger than `max_file_size` bytes.
+    pub fn max_file_size(mut self, max_file_size: i64) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Wait for `interval` between two downloads, 500 milliseconds by
+    /// default.
+    ///
+    /// The interval is timed by [`receive`](crate::receive), so it can be up
+    /// to the timeout of `receive` longer.
+    pub fn download_interval(mut self, interval: Duration) -> Self {
+        self.download_interval = interval;
+        self
+    }
+
+    /// Export the chats, reporting every step with the progress so far to
+    /// `on_event`, and returning the final progress.
+    ///
+    /// The files which can't be downloaded are skipped, while the other
+    /// errors stop the export.
+    pub async fn run(
+        &self,
+        mut on_event: impl FnMut(ExportEvent<'_>, &ExportProgress),
+        client_id: i32,
+    ) -> Result<ExportProgress, TdError> {
+        let chat_ids = match &self.chat_ids {
+            Some(chat_ids) => chat_ids.clone(),
+            None => chats::load_all_chats(self.list.clone(), client_id).await?,
+        };
+        let mut progress = ExportProgress {
+            chat_count: chat_ids.len(),
+            ..Default::default()
+        };
+        let mut has_downloaded = false;
+        for chat_id in chat_ids {
+            let chat = chats::chat(chat_id, client_id).await?;
+            on_event(ExportEvent::ChatStarted(&chat), &progress);
+            let mut history = messages::chat_history(chat_id, client_id);
+            while let Some(message) = poll_fn(|cx| Pin::new(&mut history).poll_next(cx)).await {
+                let message = message?;
+                progress.messages += 1;
+                on_event(ExportEvent::Message(&message), &progress);
+                if !self.download_media {
+                    continue;
+                }
+                for file in message_files(&message.content) {
+                    if self.max_file_size.is_some_and(|max| file_size(file) > max) {
+                        progress.skipped_files += 1;
+                        continue;
+                    }
+                    if !file.local.is_downloading_completed {
+                        // Space out the downloads, but not the files already
+                        // downloaded
+                        if has_downloaded {
+                            updates::sleep(self.download_interval, client_id).await;
+                        }
+                        has_downloaded = true;
+                    }
+                    let Ok(path) = download(file, client_id).await else {
+                        progress.skipped_files += 1;
+                        continue;
+                    };
+                    progress.files += 1;
+                    progress.file_bytes += file_size(file);
+                    let event = ExportEvent::File {
+                        message: &message,
+                        file,
+                        path: &path,
+                    };
+                    on_event(event, &progress);
+                }
+            }
+            progress.exported_chats += 1;
+            on_event(ExportEvent::ChatFinished(&chat), &progress);
+        }
+        Ok(progress)
+    }
+}
+
+/// The files of the media of a message, the largest size for the photos.
+fn message_files(content: &MessageContent) -> Vec<&types::File> {
+    match content {
+        MessageContent::MessageAnimation(content) => vec![&content.animation.animation],
+        MessageContent::MessageAudio(content) => vec![&content.audio.audio],
+        MessageContent::MessageDocument(content) => vec![&content.document.document],
+        MessageContent::MessagePhoto(content) => {
+            let sizes = content.photo.sizes.iter();
+            let largest = sizes.max_by_key(|size| size.width * size.height);
+            largest.map(|size| &size.photo).into_iter().collect()
+        }
+        MessageContent::MessageVideo(content) => vec![&content.video.video],
+        MessageContent::MessageVideoNote(content) => vec![&content.video_note.video],
+        MessageContent::MessageVoiceNote(content) => vec![&content.voice_note.voice],
+        _ => Vec::new(),
+    }
+}
+
+/// The size of `file`, or its expected size if unknown.
+fn file_size(file: &types::File) -> i64 {
+    if file.size != 0 {
+        file.size
+    } else {
+        file.expected_size
+    }
+}
+
+/// Download `file`, returning its local path.
+async fn download(file: &types::File, client_id: i32) -> Result<PathBuf, TdError> {
+    if file.local.is_downloading_completed {
+        return Ok(PathBuf::from(&*file.local.path));
+    }
+    files::download(file.id, DOWNLOAD_PRIORITY, client_id)
+        .completed()
+        .await
+}
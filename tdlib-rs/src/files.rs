@@ -0,0 +1,549 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The downloads and the uploads of the files, tracking their progress from
+//! the `updateFile` updates.
+use crate::enums::{self, Update};
+use crate::progress::Progress;
+use crate::{functions, send_request, types, updates, TdError};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+type DownloadRequest = Pin<Box<dyn Future<Output = Result<enums::File, TdError>> + Send>>;
+
+/// The file of a response, failing on the unrecognized constructors.
+fn file_of(file: enums::File) -> Result<types::File, TdError> {
+    match file {
+        enums::File::File(file) => {
+            let file: &types::File = &file;
+            Ok(file.clone())
+        }
+        #[cfg(feature = "unrecognized-variants")]
+        enums::File::Unrecognized => Err(TdError::unrecognized("File")),
+    }
+}
+
+/// A download started by [`download`].
+///
+/// The download is a stream of the states of the file, yielding the file
+/// returned by `downloadFile` and then the one of every `updateFile`, until
+/// the file is downloaded. If the download stops before, e.g. because it was
+/// canceled, the stream ends with [`TdError::Canceled`].
+///
+/// Like the other futures of the library, it's driven by
+/// [`receive`](crate::receive), and the download only starts when the stream
+/// is first polled.
+pub struct Download {
+    file_id: i32,
+    client_id: i32,
+    request: Option<DownloadRequest>,
+    updates: mpsc::UnboundedReceiver<Update>,
+    finished: bool,
+}
+
+/// Download the file `file_id` of `client_id`, with a `priority` from 1 to 32
+/// (the downloads with a higher priority start first).
+pub fn download(file_id: i32, priority: i32, client_id: i32) -> Download {
+    // Subscribe before starting the download, so that no update is missed
+    let updates = updates::subscribe(client_id, move |update| match update {
+        Update::File(update) => update.file.id == file_id,
+        _ => false,
+    });
+    let request = functions::download_file(file_id, priority, 0, 0, false, client_id);
+    Download {
+        file_id,
+        client_id,
+        request: Some(Box::pin(request)),
+        updates,
+        finished: false,
+    }
+}
+
+impl Download {
+    /// The id of the downloaded file.
+    pub fn file_id(&self) -> i32 {
+        self.file_id
+    }
+
+    /// Wait until the file is downloaded, returning its local path.
+    pub async fn completed(self) -> Result<PathBuf, TdError> {
+        self.completed_with_progress(|_| {}).await
+    }
+
+    /// Wait until the file is downloaded, reporting the progress of the
+    /// download to `on_progress`, and returning its local path.
+    pub async fn completed_with_progress(
+        mut self,
+        mut on_progress: impl FnMut(Progress),
+    ) -> Result<PathBuf, TdError> {
+        while let Some(file) = poll_fn(|cx| Pin::new(&mut self).poll_next(cx)).await {
+            let file = file?;
+            on_progress(Progress::download(&file));
+            if file.local.is_downloading_completed {
+                return Ok(PathBuf::from(&*file.local.path));
+            }
+        }
+        Err(TdError::Canceled)
+    }
+
+    /// Cancel the download, ending the stream with [`TdError::Canceled`].
+    pub async fn cancel(&self) -> Result<(), TdError> {
+        functions::cancel_download_file(self.file_id, false, self.client_id).await
+    }
+
+    /// The next item of the stream for a new state of the file.
+    fn progress(&mut self, file: types::File) -> Result<types::File, TdError> {
+        if file.local.is_downloading_completed {
+            self.finished = true;
+            Ok(file)
+        } else if !file.local.is_downloading_active {
+            self.finished = true;
+            Err(TdError::Canceled)
+        } else {
+            Ok(file)
+        }
+    }
+}
+
+impl Stream for Download {
+    type Item = Result<types::File, TdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
+        if let Some(request) = &mut self.request {
+            let response = ready!(request.as_mut().poll(cx));
+            self.request = None;
+            return match response.and_then(file_of) {
+                Ok(file) => Poll::Ready(Some(self.progress(file))),
+                Err(error) => {
+                    self.finished = true;
+                    Poll::Ready(Some(Err(error)))
+                }
+            };
+        }
+
+        loop {
+            match ready!(Pin::new(&mut self.updates).poll_next(cx)) {
+                Some(Update::File(update)) => {
+                    return Poll::Ready(Some(self.progress(update.file.clone())));
+                }
+                Some(_) => {}
+                // The client was closed
+                None => {
+                    self.finished = true;
+                    return Poll::Ready(Some(Err(TdError::Canceled)));
+                }
+            }
+        }
+    }
+}
+
+/// Cancels a download when dropped before being disarmed.
+struct CancelOnDrop {
+    file_id: i32,
+    client_id: i32,
+    armed: bool,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        // The request must be sent right away, since the future of
+        // `functions::cancel_download_file` can't be awaited here
+        #[derive(Serialize)]
+        #[serde(tag = "@type", rename = "cancelDownloadFile")]
+        struct Request {
+            file_id: i32,
+            only_if_pending: bool,
+        }
+        let request = Request {
+            file_id: self.file_id,
+            only_if_pending: false,
+        };
+        drop(send_request(self.client_id, &request));
+    }
+}
+
+/// Download the file `file_id` of `client_id` and copy it to `dest`.
+///
+/// The file is first copied next to `dest`, with the `.part` extension
+/// appended, and then renamed, so that `dest` never holds a partial file. If
+/// the future is dropped before the file is downloaded, the download is
+/// canceled, also for the other futures downloading the same file.
+///
+/// The file is kept in the cache of TDLib, which may delete it later. The copy
+/// blocks the thread, like the other operations of `std::fs`.
+pub async fn download_file_to(
+    file_id: i32,
+    dest: impl AsRef<Path>,
+    client_id: i32,
+) -> Result<(), TdError> {
+    let mut guard = CancelOnDrop {
+        file_id,
+        client_id,
+        armed: true,
+    };
+    let result = download(file_id, 1, client_id).completed().await;
+    // A download stopped by TDLib doesn't need to be canceled
+    guard.armed = false;
+    copy_atomically(&result?, dest.as_ref()).map_err(TdError::Io)
+}
+
+/// Copy `source` to a partial file next to `dest`, and rename it to `dest`.
+fn copy_atomically(source: &Path, dest: &Path) -> io::Result<()> {
+    let mut partial = OsString::from(dest);
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    let result = fs::copy(source, &partial).and_then(|_| fs::rename(&partial, dest));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+/// An upload started by [`upload_file`].
+///
+/// The upload is a stream of the states of the file, yielding the one of
+/// every `updateFile` until the file is uploaded. If the upload stops before,
+/// e.g. because it was canceled, the stream ends with [`TdError::Canceled`].
+pub struct Upload {
+    client_id: i32,
+    file: types::File,
+    updates: mpsc::UnboundedReceiver<Update>,
+    finished: bool,
+}
+
+/// The input file of the local file at `path`.
+// The path is a `String`, a `TdString` or a `PathBuf` depending on the features
+#[allow(clippy::useless_conversion)]
+pub(crate) fn local_file(path: &Path) -> enums::InputFile {
+    enums::InputFile::Local(types::InputFileLocal {
+        path: path.to_string_lossy().into_owned().into(),
+    })
+}
+
+/// Start uploading the local file at `path` to the servers of Telegram with
+/// `preliminaryUploadFile`, e.g. to send it in a message later.
+pub async fn upload_file(
+    path: impl AsRef<Path>,
+    file_type: enums::FileType,
+    client_id: i32,
+) -> Result<Upload, TdError> {
+    // The id of the file is only known once it's returned, so the updates of
+    // all the files are received until then
+    let file_id = Arc::new(AtomicI32::new(0));
+    let updates = updates::subscribe(client_id, {
+        let file_id = file_id.clone();
+        move |update| match update {
+            Update::File(update) => {
+                let file_id = file_id.load(Ordering::Relaxed);
+                file_id == 0 || update.file.id == file_id
+            }
+            _ => false,
+        }
+    });
+
+    let input_file = local_file(path.as_ref());
+    let file = functions::preliminary_upload_file(input_file, Some(file_type), 1, client_id).await;
+    let file = file.and_then(file_of)?;
+    file_id.store(file.id, Ordering::Relaxed);
+
+    Ok(Upload {
+        client_id,
+        finished: file.remote.is_uploading_completed,
+        file,
+        updates,
+    })
+}
+
+impl Upload {
+    /// The id of the uploaded file.
+    pub fn file_id(&self) -> i32 {
+        self.file.id
+    }
+
+    /// The last known state of the file.
+    pub fn file(&self) -> &types::File {
+        &self.file
+    }
+
+    /// Wait until the file is uploaded, returning its final state.
+    pub async fn completed(self) -> Result<types::File, TdError> {
+        self.completed_with_progress(|_| {}).await
+    }
+
+    /// Wait until the file is uploaded, reporting the progress of the upload
+    /// to `on_progress`, and returning its final state.
+    pub async fn completed_with_progress(
+        mut self,
+        mut on_progress: impl FnMut(Progress),
+    ) -> Result<types::File, TdError> {
+        on_progress(Progress::upload(&self.file));
+        while let Some(file) = poll_fn(|cx| Pin::new(&mut self).poll_next(cx)).await {
+            on_progress(Progress::upload(&file?));
+        }
+        if self.file.remote.is_uploading_completed {
+            Ok(self.file)
+        } else {
+            Err(TdError::Canceled)
+        }
+    }
+
+    /// Cancel the upload with `cancelPreliminaryUploadFile`, ending the
+    /// stream with [`TdError::Canceled`].
+    pub async fn cancel(&self) -> Result<(), TdError> {
+        functions::cancel_preliminary_upload_file(self.file.id, self.client_id).await
+    }
+
+    /// The next item of the stream for the new state of the file.
+    fn progress(&mut self) -> Result<types::File, TdError> {
+        if self.file.remote.is_uploading_completed {
+            self.finished = true;
+            Ok(self.file.clone())
+        } else if !self.file.remote.is_uploading_active {
+            self.finished = true;
+            Err(TdError::Canceled)
+        } else {
+            Ok(self.file.clone())
+        }
+    }
+}
+
+impl Stream for Upload {
+    type Item = Result<types::File, TdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match ready!(Pin::new(&mut self.updates).poll_next(cx)) {
+                Some(Update::File(update)) if update.file.id == self.file.id => {
+                    self.file = update.file.clone();
+                    return Poll::Ready(Some(self.progress()));
+                }
+                Some(_) => {}
+                // The client was closed
+                None => {
+                    self.finished = true;
+                    return Poll::Ready(Some(Err(TdError::Canceled)));
+                }
+            }
+        }
+    }
+}
+
+/// A part of a file read by `readFilePart`.
+#[cfg(feature = "tokio")]
+#[serde_with::serde_as]
+#[derive(serde::Deserialize)]
+struct FilePart {
+    #[serde_as(as = "serde_with::base64::Base64")]
+    data: Vec<u8>,
+}
+
+/// Read `count` bytes of the file `file_id` from `offset`, decoding them
+/// whatever the type of the binary fields.
+#[cfg(feature = "tokio")]
+async fn read_file_part(
+    file_id: i32,
+    offset: i64,
+    count: i64,
+    client_id: i32,
+) -> Result<Vec<u8>, TdError> {
+    #[derive(Serialize)]
+    #[serde(tag = "@type", rename = "readFilePart")]
+    struct Request {
+        file_id: i32,
+        offset: i64,
+        count: i64,
+    }
+    let request = Request {
+        file_id,
+        offset,
+        count,
+    };
+    let response = send_request(client_id, &request).await;
+    match serde_json::from_str::<FilePart>(&response) {
+        Ok(part) => Ok(part.data),
+        Err(error) => match serde_json::from_str::<types::Error>(&response) {
+            Ok(error) => Err(TdError::Api(error)),
+            Err(_) => Err(TdError::Deserialization {
+                expected_type: "FilePart",
+                payload: response,
+                error,
+            }),
+        },
+    }
+}
+
+#[cfg(feature = "tokio")]
+type ReadRequest = Pin<Box<dyn Future<Output = Result<Vec<u8>, TdError>> + Send>>;
+
+/// What a [`FileReader`] is waiting for.
+#[cfg(feature = "tokio")]
+enum ReadState {
+    /// The file to download from the offset.
+    Starting(DownloadRequest),
+    /// The next part of the file.
+    Reading(ReadRequest),
+    /// More of the file to be downloaded.
+    Idle,
+    /// The end of the file, also reached when `readFilePart` returns an
+    /// empty part, e.g. if the size of the file was wrong.
+    Ended,
+}
+
+/// The largest part of a file read at once.
+#[cfg(feature = "tokio")]
+const READ_CHUNK_SIZE: i64 = 512 * 1024;
+
+/// A reader of a file returned by [`read_file`], implementing
+/// [`tokio::io::AsyncRead`].
+///
+/// The reader downloads the file from the given offset, and reads its parts
+/// with `readFilePart` as soon as they are downloaded, e.g. to stream a video
+/// to a player while it's being downloaded. The reads fail with
+/// [`TdError::Canceled`] if the download stops before the end of the file.
+#[cfg(feature = "tokio")]
+pub struct FileReader {
+    file_id: i32,
+    client_id: i32,
+    /// The offset of the next part to read.
+    offset: i64,
+    /// The last known state of the file.
+    file: Option<types::File>,
+    state: ReadState,
+    updates: mpsc::UnboundedReceiver<Update>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+/// Read the file `file_id` of `client_id` from `offset`, downloading it if
+/// needed.
+#[cfg(feature = "tokio")]
+pub fn read_file(file_id: i32, offset: i64, client_id: i32) -> FileReader {
+    let updates = updates::subscribe(client_id, move |update| match update {
+        Update::File(update) => update.file.id == file_id,
+        _ => false,
+    });
+    let request = functions::download_file(file_id, 1, offset, 0, false, client_id);
+    FileReader {
+        file_id,
+        client_id,
+        offset,
+        file: None,
+        state: ReadState::Starting(Box::pin(request)),
+        updates,
+        buffer: Vec::new(),
+        position: 0,
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl FileReader {
+    /// The id of the read file.
+    pub fn file_id(&self) -> i32 {
+        self.file_id
+    }
+
+    /// The number of bytes which can be read from the offset.
+    fn available(&self) -> i64 {
+        let Some(file) = &self.file else {
+            return 0;
+        };
+        if file.local.is_downloading_completed {
+            return file.size - self.offset;
+        }
+        let local = &file.local;
+        if local.download_offset <= self.offset {
+            local.download_offset + local.downloaded_prefix_size - self.offset
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for FileReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            if self.position < self.buffer.len() {
+                let len = buf.remaining().min(self.buffer.len() - self.position);
+                buf.put_slice(&self.buffer[self.position..self.position + len]);
+                self.position += len;
+                return Poll::Ready(Ok(()));
+            }
+
+            match &mut self.state {
+                ReadState::Starting(request) => {
+                    let file = ready!(request.as_mut().poll(cx)).and_then(file_of);
+                    self.file = Some(file.map_err(io::Error::other)?);
+                    self.state = ReadState::Idle;
+                }
+                ReadState::Reading(request) => {
+                    let data = ready!(request.as_mut().poll(cx)).map_err(io::Error::other)?;
+                    if data.is_empty() {
+                        self.state = ReadState::Ended;
+                        return Poll::Ready(Ok(()));
+                    }
+                    self.offset += data.len() as i64;
+                    self.buffer = data;
+                    self.position = 0;
+                    self.state = ReadState::Idle;
+                }
+                ReadState::Ended => return Poll::Ready(Ok(())),
+                ReadState::Idle => {
+                    let available = self.available();
+                    if available > 0 {
+                        let count = available.min(READ_CHUNK_SIZE);
+                        let request =
+                            read_file_part(self.file_id, self.offset, count, self.client_id);
+                        self.state = ReadState::Reading(Box::pin(request));
+                        continue;
+                    }
+
+                    let local = &self.file.as_ref().expect("the download started").local;
+                    if local.is_downloading_completed {
+                        return Poll::Ready(Ok(()));
+                    }
+                    if !local.is_downloading_active {
+                        return Poll::Ready(Err(io::Error::other(TdError::Canceled)));
+                    }
+                    match ready!(Pin::new(&mut self.updates).poll_next(cx)) {
+                        Some(Update::File(update)) => self.file = Some(update.file.clone()),
+                        Some(_) => {}
+                        // The client was closed
+                        None => return Poll::Ready(Err(io::Error::other(TdError::Canceled))),
+                    }
+                }
+            }
+        }
+    }
+}
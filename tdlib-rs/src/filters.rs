@@ -0,0 +1,120 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The [`UpdateFilter`]s, composable predicates over the updates, e.g.
+//!
+//! ```ignore
+//! let filter = filters::messages_only()
+//!     .and(filters::by_chat(chat_id))
+//!     .and(filters::not_outgoing());
+//! let mut updates = filters::updates(filter, client.id());
+//! while let Some(update) = updates.next().await {
+//!     // Handle the incoming message
+//! }
+//! ```
+use crate::enums::{MessageSender, Update};
+use crate::updates;
+use futures_channel::mpsc;
+use futures_core::Stream;
+use std::fmt;
+use std::ops::Not;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// A predicate over the updates, cheap to clone, which can be combined with
+/// [`and`](Self::and), [`or`](Self::or) and `!`.
+#[derive(Clone)]
+pub struct UpdateFilter {
+    matches: Arc<dyn Fn(&Update) -> bool + Send + Sync>,
+}
+
+impl UpdateFilter {
+    /// The filter of the updates for which `matches` returns `true`.
+    pub fn new(matches: impl Fn(&Update) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            matches: Arc::new(matches),
+        }
+    }
+
+    /// Whether `update` passes the filter.
+    pub fn matches(&self, update: &Update) -> bool {
+        (self.matches)(update)
+    }
+
+    /// The filter of the updates passing both this filter and `other`.
+    pub fn and(self, other: UpdateFilter) -> Self {
+        Self::new(move |update| self.matches(update) && other.matches(update))
+    }
+
+    /// The filter of the updates passing this filter or `other`.
+    pub fn or(self, other: UpdateFilter) -> Self {
+        Self::new(move |update| self.matches(update) || other.matches(update))
+    }
+}
+
+/// The filter of the updates not passing the filter.
+impl Not for UpdateFilter {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::new(move |update| !self.matches(update))
+    }
+}
+
+impl fmt::Debug for UpdateFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateFilter").finish_non_exhaustive()
+    }
+}
+
+/// The updates about the chat `chat_id`, as returned by [`Update::chat_id`].
+pub fn by_chat(chat_id: i64) -> UpdateFilter {
+    UpdateFilter::new(move |update| update.chat_id() == Some(chat_id))
+}
+
+/// The updates of the new messages, i.e. [`Update::NewMessage`].
+pub fn messages_only() -> UpdateFilter {
+    UpdateFilter::new(|update| matches!(update, Update::NewMessage(_)))
+}
+
+/// The updates carrying a message sent by the user `user_id`.
+pub fn from_user(user_id: i64) -> UpdateFilter {
+    UpdateFilter::new(move |update| {
+        update.message().is_some_and(|message| {
+            matches!(&message.sender_id, MessageSender::User(sender) if sender.user_id == user_id)
+        })
+    })
+}
+
+/// The updates except the ones carrying an outgoing message, e.g. the copies
+/// of the messages sent by the client.
+pub fn not_outgoing() -> UpdateFilter {
+    UpdateFilter::new(|update| !update.message().is_some_and(|message| message.is_outgoing))
+}
+
+/// Stream the updates of `client_id` passing `filter`, until the stream is
+/// dropped or the client is closed.
+pub fn updates(filter: UpdateFilter, client_id: i32) -> FilteredUpdates {
+    FilteredUpdates {
+        updates: updates::subscribe(client_id, move |update| filter.matches(update)),
+    }
+}
+
+/// A stream of the updates passing a filter, returned by [`updates`].
+pub struct FilteredUpdates {
+    updates: mpsc::UnboundedReceiver<Update>,
+}
+
+impl Stream for FilteredUpdates {
+    type Item = Update;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.updates).poll_next(cx)
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The chat folders of the user, created with `createChatFolder` and listed
+//! by the `updateChatFolders` updates.
+use crate::enums::{self, Update};
+use crate::{functions, types, updates, Client, TdError, TdString};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// The maximum length of the title of a folder, in characters.
+const FOLDER_TITLE_LENGTH_MAX: usize = 12;
+
+/// The builder of a chat folder, including the chats of the selected types
+/// and the chats added explicitly, e.g.
+///
+/// ```ignore
+/// let folder = ChatFolderBuilder::new("Work")
+///     .icon("Work")
+///     .include_groups()
+///     .include_chat(colleague_chat_id)
+///     .exclude_muted()
+///     .build()?;
+/// let info = folders::create_chat_folder(folder, client.id()).await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChatFolderBuilder {
+    title: TdString,
+    folder: types::ChatFolder,
+}
+
+impl ChatFolderBuilder {
+    /// A folder titled `title`, up to 12 characters, without any chat.
+    pub fn new(title: impl Into<TdString>) -> Self {
+        Self {
+            title: title.into(),
+            folder: types::ChatFolder {
+                #[cfg(not(feature = "api-1.8.19"))]
+                color_id: -1,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The builder of `folder`, e.g. to edit a folder returned by
+    /// [`chat_folder`].
+    pub fn from_folder(folder: types::ChatFolder) -> Self {
+        #[cfg(not(feature = "api-1.8.19"))]
+        let title = folder.name.text.text.clone();
+        #[cfg(feature = "api-1.8.19")]
+        let title = folder.title.clone();
+        Self { title, folder }
+    }
+
+    /// Set the icon of the folder, e.g. `"Work"`, instead of the one chosen
+    /// from its chats.
+    pub fn icon(mut self, name: impl Into<TdString>) -> Self {
+        let name = name.into();
+        self.folder.icon = Some(types::ChatFolderIcon { name });
+        self
+    }
+
+    /// Set the color of the icon of the folder, from 0 to 6, or -1 for none.
+    #[cfg(not(feature = "api-1.8.19"))]
+    pub fn color(mut self, color_id: i32) -> Self {
+        self.folder.color_id = color_id;
+        self
+    }
+
+    /// Include and pin the chat `chat_id`, the pinned chats being shown
+    /// first.
+    pub fn pin_chat(mut self, chat_id: i64) -> Self {
+        self.folder.included_chat_ids.retain(|id| *id != chat_id);
+        self.folder.excluded_chat_ids.retain(|id| *id != chat_id);
+        if !self.folder.pinned_chat_ids.contains(&chat_id) {
+            self.folder.pinned_chat_ids.push(chat_id);
+        }
+        self
+    }
+
+    /// Include the chat `chat_id`, whatever its type.
+    pub fn include_chat(mut self, chat_id: i64) -> Self {
+        self.folder.excluded_chat_ids.retain(|id| *id != chat_id);
+        let is_included = self.folder.pinned_chat_ids.contains(&chat_id)
+            || self.folder.included_chat_ids.contains(&chat_id);
+        if !is_included {
+            self.folder.included_chat_ids.push(chat_id);
+        }
+        self
+    }
+
+    /// Exclude the chat `chat_id`, even if its type is included.
+    pub fn exclude_chat(mut self, chat_id: i64) -> Self {
+        self.folder.pinned_chat_ids.retain(|id| *id != chat_id);
+        self.folder.included_chat_ids.retain(|id| *id != chat_id);
+        if !self.folder.excluded_chat_ids.contains(&chat_id) {
+            self.folder.excluded_chat_ids.push(chat_id);
+        }
+        self
+    }
+
+    /// Include the private chats with the contacts.
+    pub fn include_contacts(mut self) -> Self {
+        self.folder.include_contacts = true;
+        self
+    }
+
+    /// Include the private chats with the users who aren't contacts.
+    pub fn include_non_contacts(mut self) -> Self {
+        self.folder.include_non_contacts = true;
+        self
+    }
+
+    /// Include the private chats with the bots.
+    pub fn include_bots(mut self) -> Self {
+        self.folder.include_bots = true;
+        self
+    }
+
+    /// Include the basic groups and the supergroups.
+    pub fn include_groups(mut self) -> Self {
+        self.folder.include_groups = true;
+        self
+    }
+
+    /// Include the channels.
+    pub fn include_channels(mut self) -> Self {
+        self.folder.include_channels = true;
+        self
+    }
+
+    /// Exclude the muted chats.
+    pub fn exclude_muted(mut self) -> Self {
+        self.folder.exclude_muted = true;
+        self
+    }
+
+    /// Exclude the chats without unread messages.
+    pub fn exclude_read(mut self) -> Self {
+        self.folder.exclude_read = true;
+        self
+    }
+
+    /// Exclude the archived chats.
+    pub fn exclude_archived(mut self) -> Self {
+        self.folder.exclude_archived = true;
+        self
+    }
+
+    fn validate(&self) -> Result<(), TdError> {
+        let length = self.title.chars().count();
+        if !(1..=FOLDER_TITLE_LENGTH_MAX).contains(&length) || self.title.contains('\n') {
+            return Err(TdError::invalid(
+                "Chat folder title must be non-empty and at most 12 characters",
+            ));
+        }
+        let folder = &self.folder;
+        let includes_types = folder.include_contacts
+            || folder.include_non_contacts
+            || folder.include_bots
+            || folder.include_groups
+            || folder.include_channels;
+        if !includes_types
+            && folder.pinned_chat_ids.is_empty()
+            && folder.included_chat_ids.is_empty()
+        {
+            return Err(TdError::invalid("Folder must contain at least 1 chat"));
+        }
+        Ok(())
+    }
+
+    /// Build the folder, failing like TDLib if its title is invalid or if
+    /// it includes no chat.
+    pub fn build(self) -> Result<types::ChatFolder, TdError> {
+        self.validate()?;
+        let mut folder = self.folder;
+        #[cfg(not(feature = "api-1.8.19"))]
+        {
+            folder.name = types::ChatFolderName {
+                text: crate::content::plain_text(self.title),
+                animate_custom_emoji: false,
+            };
+        }
+        #[cfg(feature = "api-1.8.19")]
+        {
+            folder.title = self.title;
+        }
+        Ok(folder)
+    }
+}
+
+/// The information of a folder of a response, failing on the unrecognized
+/// constructors.
+fn folder_info(info: enums::ChatFolderInfo) -> Result<types::ChatFolderInfo, TdError> {
+    match info {
+        enums::ChatFolderInfo::ChatFolderInfo(info) => Ok(info),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::ChatFolderInfo::Unrecognized => Err(TdError::unrecognized("ChatFolderInfo")),
+    }
+}
+
+/// The folder `chat_folder_id`, with its chats.
+// The folder is only boxed by the `box-large-variants` feature
+#[allow(clippy::useless_conversion)]
+pub async fn chat_folder(
+    chat_folder_id: i32,
+    client_id: i32,
+) -> Result<types::ChatFolder, TdError> {
+    match functions::get_chat_folder(chat_folder_id, client_id).await? {
+        enums::ChatFolder::ChatFolder(folder) => Ok(*Box::<types::ChatFolder>::from(folder)),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::ChatFolder::Unrecognized => Err(TdError::unrecognized("ChatFolder")),
+    }
+}
+
+/// Create the folder `folder`, e.g. built with [`ChatFolderBuilder`].
+pub async fn create_chat_folder(
+    folder: types::ChatFolder,
+    client_id: i32,
+) -> Result<types::ChatFolderInfo, TdError> {
+    functions::create_chat_folder(folder, client_id)
+        .await
+        .and_then(folder_info)
+}
+
+/// Replace the folder `chat_folder_id` with `folder`.
+pub async fn edit_chat_folder(
+    chat_folder_id: i32,
+    folder: types::ChatFolder,
+    client_id: i32,
+) -> Result<types::ChatFolderInfo, TdError> {
+    functions::edit_chat_folder(chat_folder_id, folder, client_id)
+        .await
+        .and_then(folder_info)
+}
+
+/// Delete the folder `chat_folder_id`, keeping its chats.
+pub async fn delete_chat_folder(chat_folder_id: i32, client_id: i32) -> Result<(), TdError> {
+    functions::delete_chat_folder(chat_folder_id, [], client_id).await
+}
+
+/// A stream of the folders returned by [`watch_chat_folders`].
+pub struct ChatFolderChanges {
+    client: Client,
+    updates: mpsc::UnboundedReceiver<Update>,
+    /// Whether the current folders were yielded.
+    is_started: bool,
+}
+
+/// Stream the folders of the user of `client_id`, first as they are now and
+/// then every time they change, e.g. to keep the folder tabs of a UI up to
+/// date.
+pub fn watch_chat_folders(client_id: i32) -> ChatFolderChanges {
+    ChatFolderChanges {
+        client: Client::from_id(client_id),
+        updates: updates::subscribe(client_id, |update| matches!(update, Update::ChatFolders(_))),
+        is_started: false,
+    }
+}
+
+impl Stream for ChatFolderChanges {
+    type Item = Vec<types::ChatFolderInfo>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if !self.is_started {
+            self.is_started = true;
+            return Poll::Ready(Some(self.client.chat_folders()));
+        }
+
+        while let Some(update) = ready!(Pin::new(&mut self.updates).poll_next(cx)) {
+            if let Update::ChatFolders(update) = update {
+                return Poll::Ready(Some(update.chat_folders));
+            }
+        }
+        // The client was closed
+        Poll::Ready(None)
+    }
+}
@@ -0,0 +1,465 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The formatted texts of the messages, parsed from Markdown or HTML by the
+//! synchronous `parseTextEntities`, and rendered back to HTML, Markdown or
+//! ANSI-styled text.
+use crate::enums::{self, TextEntityType, TextParseMode};
+use crate::{execute_request, types, utf16, TdError};
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Parse the entities of `text` with `parseTextEntities`, executed
+/// synchronously.
+fn parse_text_entities(
+    text: &str,
+    parse_mode: TextParseMode,
+) -> Result<types::FormattedText, TdError> {
+    #[derive(Serialize)]
+    #[serde(tag = "@type", rename = "parseTextEntities")]
+    struct Request<'a> {
+        text: &'a str,
+        parse_mode: TextParseMode,
+    }
+    let request = Request { text, parse_mode };
+    match execute_request(&request, "FormattedText")? {
+        enums::FormattedText::FormattedText(text) => {
+            let text: &types::FormattedText = &text;
+            Ok(text.clone())
+        }
+        #[cfg(feature = "unrecognized-variants")]
+        enums::FormattedText::Unrecognized => Err(TdError::unrecognized("FormattedText")),
+    }
+}
+
+/// Parse a text formatted with the MarkdownV2 syntax of the Bot API, e.g.
+/// `"*bold* and _italic_"`, into a [`types::FormattedText`] to send.
+///
+/// The reserved characters, like `.` and `!`, must be escaped with `\`,
+/// otherwise TDLib fails with [`TdError::Api`].
+pub fn markdown(text: &str) -> Result<types::FormattedText, TdError> {
+    let parse_mode = TextParseMode::Markdown(types::TextParseModeMarkdown { version: 2 });
+    parse_text_entities(text, parse_mode)
+}
+
+/// Parse a text formatted with the HTML syntax of the Bot API, e.g.
+/// `"<b>bold</b> and <i>italic</i>"`, into a [`types::FormattedText`] to
+/// send.
+pub fn html(text: &str) -> Result<types::FormattedText, TdError> {
+    parse_text_entities(text, TextParseMode::Html)
+}
+
+/// The syntax of a rendered text.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    Html,
+    Markdown,
+    Ansi,
+}
+
+fn is_code(entity: &TextEntityType) -> bool {
+    matches!(
+        entity,
+        TextEntityType::Code | TextEntityType::Pre | TextEntityType::PreCode(_)
+    )
+}
+
+fn is_quote(entity: &TextEntityType) -> bool {
+    match entity {
+        #[cfg(not(feature = "api-1.8.19"))]
+        TextEntityType::BlockQuote | TextEntityType::ExpandableBlockQuote => true,
+        _ => false,
+    }
+}
+
+/// Whether `c` must be escaped in the MarkdownV2 syntax, outside of the code.
+fn is_markdown_reserved(c: char) -> bool {
+    matches!(
+        c,
+        '_' | '*'
+            | '['
+            | ']'
+            | '('
+            | ')'
+            | '~'
+            | '`'
+            | '>'
+            | '#'
+            | '+'
+            | '-'
+            | '='
+            | '|'
+            | '{'
+            | '}'
+            | '.'
+            | '!'
+            | '\\'
+    )
+}
+
+/// Escape `text` for the MarkdownV2 syntax, inside the code.
+fn escape_markdown_code(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('`', "\\`")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The HTML tag opening or closing `entity`.
+fn html_tag(entity: &TextEntityType, open: bool) -> String {
+    let name = match entity {
+        TextEntityType::Bold => "b",
+        TextEntityType::Italic => "i",
+        TextEntityType::Underline => "u",
+        TextEntityType::Strikethrough => "s",
+        TextEntityType::Spoiler => "tg-spoiler",
+        TextEntityType::Code => "code",
+        TextEntityType::Pre => "pre",
+        #[cfg(not(feature = "api-1.8.19"))]
+        TextEntityType::BlockQuote => "blockquote",
+        #[cfg(not(feature = "api-1.8.19"))]
+        TextEntityType::ExpandableBlockQuote if open => return "<blockquote expandable>".into(),
+        #[cfg(not(feature = "api-1.8.19"))]
+        TextEntityType::ExpandableBlockQuote => "blockquote",
+        TextEntityType::PreCode(pre) if open => {
+            let language = escape_html(&pre.language);
+            return format!("<pre><code class=\"language-{language}\">");
+        }
+        TextEntityType::PreCode(_) => return "</code></pre>".into(),
+        TextEntityType::TextUrl(text_url) if open => {
+            return format!("<a href=\"{}\">", escape_html(&text_url.url));
+        }
+        TextEntityType::MentionName(mention) if open => {
+            return format!("<a href=\"tg://user?id={}\">", mention.user_id);
+        }
+        TextEntityType::TextUrl(_) | TextEntityType::MentionName(_) => "a",
+        TextEntityType::CustomEmoji(emoji) if open => {
+            return format!("<tg-emoji emoji-id=\"{}\">", emoji.custom_emoji_id);
+        }
+        TextEntityType::CustomEmoji(_) => "tg-emoji",
+        _ => return String::new(),
+    };
+    if open {
+        format!("<{name}>")
+    } else {
+        format!("</{name}>")
+    }
+}
+
+/// The MarkdownV2 markup opening or closing `entity`.
+fn markdown_tag(entity: &TextEntityType, open: bool) -> String {
+    let markup = match entity {
+        TextEntityType::Bold => "*",
+        TextEntityType::Italic => "_",
+        TextEntityType::Underline => "__",
+        TextEntityType::Strikethrough => "~",
+        TextEntityType::Spoiler => "||",
+        TextEntityType::Code => "`",
+        TextEntityType::Pre if open => "```\n",
+        TextEntityType::PreCode(pre) if open => {
+            return format!("```{}\n", escape_markdown_code(&pre.language));
+        }
+        TextEntityType::Pre | TextEntityType::PreCode(_) => "\n```",
+        #[cfg(not(feature = "api-1.8.19"))]
+        TextEntityType::BlockQuote if open => ">",
+        #[cfg(not(feature = "api-1.8.19"))]
+        TextEntityType::ExpandableBlockQuote if open => "**>",
+        #[cfg(not(feature = "api-1.8.19"))]
+        TextEntityType::ExpandableBlockQuote => "||",
+        TextEntityType::TextUrl(_) | TextEntityType::MentionName(_) if open => "[",
+        TextEntityType::CustomEmoji(_) if open => "![",
+        TextEntityType::TextUrl(text_url) => {
+            let url = text_url.url.replace('\\', "\\\\").replace(')', "\\)");
+            return format!("]({url})");
+        }
+        TextEntityType::MentionName(mention) => {
+            return format!("](tg://user?id={})", mention.user_id);
+        }
+        TextEntityType::CustomEmoji(emoji) => {
+            return format!("](tg://emoji?id={})", emoji.custom_emoji_id);
+        }
+        _ => "",
+    };
+    markup.to_string()
+}
+
+/// The ANSI style of `entity`, if any.
+fn ansi_style(entity: &TextEntityType) -> Option<&'static str> {
+    match entity {
+        TextEntityType::Bold => Some("1"),
+        TextEntityType::Italic => Some("3"),
+        TextEntityType::Underline => Some("4"),
+        TextEntityType::Strikethrough => Some("9"),
+        TextEntityType::Spoiler => Some("2"),
+        TextEntityType::Code | TextEntityType::Pre | TextEntityType::PreCode(_) => Some("36"),
+        TextEntityType::Url
+        | TextEntityType::EmailAddress
+        | TextEntityType::TextUrl(_)
+        | TextEntityType::Mention
+        | TextEntityType::MentionName(_) => Some("4;34"),
+        _ => None,
+    }
+}
+
+/// Renders a formatted text, keeping the stack of the open entities.
+struct Renderer<'a> {
+    syntax: Syntax,
+    output: String,
+    /// The open entities, with the byte offset of their end.
+    open: Vec<(usize, &'a TextEntityType)>,
+}
+
+impl<'a> Renderer<'a> {
+    fn push_text(&mut self, text: &str) {
+        let in_code = self.open.iter().any(|(_, entity)| is_code(entity));
+        let in_quote = self.open.iter().any(|(_, entity)| is_quote(entity));
+        for c in text.chars() {
+            match (self.syntax, c) {
+                (Syntax::Html, '<') => self.output.push_str("&lt;"),
+                (Syntax::Html, '>') => self.output.push_str("&gt;"),
+                (Syntax::Html, '&') => self.output.push_str("&amp;"),
+                (Syntax::Markdown, '\n') if in_quote => self.output.push_str("\n>"),
+                (Syntax::Markdown, '`' | '\\') if in_code => {
+                    self.output.push('\\');
+                    self.output.push(c);
+                }
+                (Syntax::Markdown, _) if !in_code && is_markdown_reserved(c) => {
+                    self.output.push('\\');
+                    self.output.push(c);
+                }
+                _ => self.output.push(c),
+            }
+        }
+    }
+
+    fn open(&mut self, entity: &'a TextEntityType, end: usize) {
+        self.open.push((end, entity));
+        match self.syntax {
+            Syntax::Html => self.output.push_str(&html_tag(entity, true)),
+            Syntax::Markdown => self.output.push_str(&markdown_tag(entity, true)),
+            Syntax::Ansi => {
+                self.push_ansi_styles();
+                if let TextEntityType::TextUrl(text_url) = entity {
+                    write!(self.output, "\x1b]8;;{}\x1b\\", text_url.url).unwrap();
+                }
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        let Some((_, entity)) = self.open.pop() else {
+            return;
+        };
+        match self.syntax {
+            Syntax::Html => self.output.push_str(&html_tag(entity, false)),
+            Syntax::Markdown => self.output.push_str(&markdown_tag(entity, false)),
+            Syntax::Ansi => {
+                if let TextEntityType::TextUrl(_) = entity {
+                    self.output.push_str("\x1b]8;;\x1b\\");
+                }
+                self.push_ansi_styles();
+            }
+        }
+    }
+
+    /// Push the text up to `offset`, closing the entities ending before.
+    fn close_until(&mut self, text: &str, position: &mut usize, offset: usize) {
+        while let Some(&(end, _)) = self.open.last() {
+            if end > offset {
+                break;
+            }
+            self.push_text(&text[*position..end]);
+            *position = end;
+            self.close();
+        }
+    }
+
+    /// Reset the style, and apply the ones of the open entities again, since
+    /// the ANSI styles can't be ended one by one.
+    fn push_ansi_styles(&mut self) {
+        self.output.push_str("\x1b[0m");
+        for (_, entity) in &self.open {
+            if let Some(style) = ansi_style(entity) {
+                write!(self.output, "\x1b[{style}m").unwrap();
+            }
+        }
+    }
+}
+
+fn render(text: &types::FormattedText, syntax: Syntax) -> String {
+    let plain: &str = &text.text;
+    let mut entities: Vec<_> = text
+        .entities
+        .iter()
+        .map(|entity| {
+            let bytes = utf16::byte_range(plain, entity.offset, entity.length);
+            (bytes.start, bytes.end, &entity.r#type)
+        })
+        .collect();
+    // The outer entities are opened first
+    entities.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    let mut renderer = Renderer {
+        syntax,
+        output: String::with_capacity(plain.len()),
+        open: Vec::new(),
+    };
+    let mut position = 0;
+    for (start, end, entity) in entities {
+        renderer.close_until(plain, &mut position, start);
+        renderer.push_text(&plain[position..start]);
+        position = start;
+        // The entities can't intersect, so the intersecting ones are cut
+        let end = match renderer.open.last() {
+            Some(&(parent_end, _)) => end.min(parent_end),
+            None => end,
+        };
+        renderer.open(entity, end);
+    }
+    renderer.close_until(plain, &mut position, plain.len());
+    renderer.push_text(&plain[position..]);
+    renderer.output
+}
+
+/// Render a formatted text with the HTML syntax of the Bot API, which
+/// [`html`] parses back.
+pub fn to_html(text: &types::FormattedText) -> String {
+    render(text, Syntax::Html)
+}
+
+/// Render a formatted text with the MarkdownV2 syntax of the Bot API, which
+/// [`markdown`] parses back.
+pub fn to_markdown(text: &types::FormattedText) -> String {
+    render(text, Syntax::Markdown)
+}
+
+/// Render a formatted text with the ANSI escape codes understood by the
+/// terminals, e.g. bold for the bold entities and hyperlinks for the links.
+pub fn to_ansi(text: &types::FormattedText) -> String {
+    render(text, Syntax::Ansi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(offset: i32, length: i32, r#type: TextEntityType) -> types::TextEntity {
+        types::TextEntity {
+            offset,
+            length,
+            r#type,
+        }
+    }
+
+    // The text is a `TdString`, which may be a `String`
+    #[allow(clippy::useless_conversion)]
+    fn text(text: &str, entities: Vec<types::TextEntity>) -> types::FormattedText {
+        types::FormattedText {
+            text: text.to_owned().into(),
+            entities,
+        }
+    }
+
+    // The language is a `TdString`, which may be a `String`
+    #[allow(clippy::useless_conversion)]
+    fn pre_code(language: &str) -> TextEntityType {
+        TextEntityType::PreCode(types::TextEntityTypePreCode {
+            language: language.to_owned().into(),
+        })
+    }
+
+    #[test]
+    fn check_plain() {
+        let plain = text("a <b> & c.", Vec::new());
+        assert_eq!(to_html(&plain), "a &lt;b&gt; &amp; c.");
+        assert_eq!(to_markdown(&plain), "a <b\\> & c\\.");
+        assert_eq!(to_ansi(&plain), "a <b> & c.");
+    }
+
+    #[test]
+    fn check_nested() {
+        let nested = text(
+            "ab cd",
+            vec![
+                entity(3, 2, TextEntityType::Italic),
+                entity(0, 5, TextEntityType::Bold),
+            ],
+        );
+        assert_eq!(to_html(&nested), "<b>ab <i>cd</i></b>");
+        assert_eq!(to_markdown(&nested), "*ab _cd_*");
+        assert_eq!(
+            to_ansi(&nested),
+            "\x1b[0m\x1b[1mab \x1b[0m\x1b[1m\x1b[3mcd\x1b[0m\x1b[1m\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn check_overlapping() {
+        // The italic entity is cut at the end of the bold one
+        let overlapping = text(
+            "abcdef",
+            vec![
+                entity(0, 4, TextEntityType::Bold),
+                entity(2, 4, TextEntityType::Italic),
+            ],
+        );
+        assert_eq!(to_html(&overlapping), "<b>ab<i>cd</i></b>ef");
+        assert_eq!(to_markdown(&overlapping), "*ab_cd_*ef");
+    }
+
+    #[test]
+    fn check_emoji_offsets() {
+        // The emoji takes two UTF-16 code units
+        let emoji = text(
+            "😀 bold 😀",
+            vec![
+                entity(0, 2, TextEntityType::Italic),
+                entity(3, 4, TextEntityType::Bold),
+            ],
+        );
+        assert_eq!(to_html(&emoji), "<i>😀</i> <b>bold</b> 😀");
+        assert_eq!(to_markdown(&emoji), "_😀_ *bold* 😀");
+    }
+
+    #[test]
+    fn check_code() {
+        let code = text("a`b\\c.", vec![entity(0, 6, TextEntityType::Code)]);
+        assert_eq!(to_html(&code), "<code>a`b\\c.</code>");
+        assert_eq!(to_markdown(&code), "`a\\`b\\\\c.`");
+    }
+
+    #[test]
+    fn check_pre_code() {
+        let pre = text("fn f() {}", vec![entity(0, 9, pre_code("r`s\\\"<"))]);
+        assert_eq!(
+            to_html(&pre),
+            "<pre><code class=\"language-r`s\\&quot;&lt;\">fn f() {}</code></pre>"
+        );
+        assert_eq!(to_markdown(&pre), "```r\\`s\\\\\"<\nfn f() {}\n```");
+    }
+
+    #[test]
+    fn check_text_url() {
+        let url = types::TextEntityTypeTextUrl {
+            url: "https://example.com/a_(b)\"".into(),
+        };
+        let link = text("a link", vec![entity(2, 4, TextEntityType::TextUrl(url))]);
+        assert_eq!(
+            to_html(&link),
+            "a <a href=\"https://example.com/a_(b)&quot;\">link</a>"
+        );
+        assert_eq!(
+            to_markdown(&link),
+            "a [link](https://example.com/a_(b\\)\")"
+        );
+    }
+}
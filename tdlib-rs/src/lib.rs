@@ -7,22 +7,97 @@
 // <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
+pub mod accounts;
+pub mod actor;
+#[cfg(feature = "bots-only-api")]
+pub mod bots;
 pub mod build;
+pub mod cache;
+pub mod chats;
+mod client;
+pub mod contacts;
+pub mod content;
+pub mod dispatcher;
+mod display;
+pub mod encryption;
+pub mod ephemeral;
+#[cfg(not(feature = "wasm"))]
+mod executor;
+pub mod export;
+pub mod files;
+pub mod filters;
+pub mod folders;
+pub mod formatting;
 mod generated;
+pub mod members;
+pub mod messages;
+pub mod network;
+pub mod notifications;
 mod observer;
+pub mod options;
+mod pagination;
+pub mod progress;
+pub mod proxy;
+mod queue;
+pub mod rate_limit;
+#[cfg(not(feature = "wasm"))]
+mod receiver;
+pub mod secret_chats;
+pub mod storage;
 mod tdjson;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
+#[cfg(feature = "gpui")]
+pub mod ui;
+mod updates;
+pub mod utf16;
 
-pub use generated::{enums, functions, types};
+pub use client::Client;
+#[cfg(not(feature = "wasm"))]
+pub use executor::block_on;
+#[cfg(feature = "blocking")]
+pub use generated::blocking;
+pub use generated::{enums, functions, types, TDLIB_API_VERSION};
+pub use observer::{with_timeout, PendingRequests, WithTimeout};
+pub use options::OptionsCache;
+pub use pagination::Paginated;
+pub use queue::{with_priority, Priority, WithPriority};
+#[cfg(not(feature = "wasm"))]
+pub use receiver::{spawn_receiver, ReceivedUpdates};
+#[cfg(feature = "macros")]
+pub use tdlib_rs_macros::td_handler;
+pub use transport::{set_transport, TdJson, Transport};
 
 /// Type alias for string types in generated code.
 /// When the `gpui` feature is enabled, this resolves to `gpui::SharedString`.
+/// When the `cow-string` feature is enabled, this resolves to `Cow<'static, str>`,
+/// so that constant strings are not heap-allocated on every request.
+/// When the `arc-str` feature is enabled, this resolves to `Arc<str>`,
+/// so that cloning strings only bumps a reference count.
 /// Otherwise, it resolves to `String`.
 #[cfg(feature = "gpui")]
 pub type TdString = gpui::SharedString;
 
-#[cfg(not(feature = "gpui"))]
+#[cfg(all(feature = "cow-string", not(feature = "gpui")))]
+pub type TdString = std::borrow::Cow<'static, str>;
+
+#[cfg(all(
+    feature = "arc-str",
+    not(any(feature = "gpui", feature = "cow-string"))
+))]
+pub type TdString = std::sync::Arc<str>;
+
+#[cfg(not(any(feature = "gpui", feature = "cow-string", feature = "arc-str")))]
 pub type TdString = String;
 
+#[cfg(all(feature = "gpui", feature = "cow-string"))]
+compile_error!("feature \"gpui\" and feature \"cow-string\" cannot be enabled at the same time");
+#[cfg(all(feature = "gpui", feature = "arc-str"))]
+compile_error!("feature \"gpui\" and feature \"arc-str\" cannot be enabled at the same time");
+#[cfg(all(feature = "cow-string", feature = "arc-str"))]
+compile_error!("feature \"cow-string\" and feature \"arc-str\" cannot be enabled at the same time");
+
 /// Error type for TDLib function calls.
 ///
 /// Wraps both TDLib API errors and deserialization failures so that
@@ -40,6 +115,19 @@ pub enum TdError {
         /// The serde error.
         error: serde_json::Error,
     },
+    /// The linked TDLib is not the version the bindings were generated for.
+    VersionMismatch {
+        /// The version of the generated bindings, `TDLIB_API_VERSION`.
+        expected: &'static str,
+        /// The version reported by the linked TDLib.
+        found: String,
+    },
+    /// The awaited update didn't arrive in time.
+    Timeout,
+    /// The operation was canceled before completing, e.g. a file download.
+    Canceled,
+    /// An I/O operation on a local file failed.
+    Io(std::io::Error),
 }
 
 impl std::fmt::Display for TdError {
@@ -51,6 +139,13 @@ impl std::fmt::Display for TdError {
                 error,
                 ..
             } => write!(f, "Failed to deserialize {expected_type}: {error}"),
+            TdError::VersionMismatch { expected, found } => write!(
+                f,
+                "The bindings are generated for TDLib {expected}, but TDLib {found} is linked"
+            ),
+            TdError::Timeout => write!(f, "Timed out"),
+            TdError::Canceled => write!(f, "Canceled"),
+            TdError::Io(e) => write!(f, "I/O error: {e}"),
         }
     }
 }
@@ -58,53 +153,267 @@ impl std::fmt::Display for TdError {
 impl std::error::Error for TdError {}
 
 impl TdError {
-    /// Returns the API error code, or -1 for deserialization errors.
+    /// Returns the API error code, or -1 for the errors not returned by TDLib.
     pub fn code(&self) -> i32 {
         match self {
             TdError::Api(e) => e.code,
-            TdError::Deserialization { .. } => -1,
+            TdError::Deserialization { .. }
+            | TdError::VersionMismatch { .. }
+            | TdError::Timeout
+            | TdError::Canceled
+            | TdError::Io(_) => -1,
+        }
+    }
+
+    /// The error of a response with a constructor unknown to the bindings,
+    /// parsed as the `Unrecognized` variant of its enum.
+    #[cfg(feature = "unrecognized-variants")]
+    pub(crate) fn unrecognized(expected_type: &'static str) -> Self {
+        TdError::Deserialization {
+            expected_type,
+            payload: String::new(),
+            error: serde::de::Error::custom("unrecognized constructor"),
+        }
+    }
+
+    /// The error returned by TDLib for an invalid request, also returned by
+    /// the helpers validating the requests before sending them.
+    // The message is a `TdString`, which may be a `String`
+    #[allow(clippy::useless_conversion)]
+    pub(crate) fn invalid(message: &str) -> Self {
+        TdError::Api(types::Error {
+            code: 400,
+            message: message.to_owned().into(),
+        })
+    }
+}
+
+/// The value of a `bytes` field of the generated types holding `data`,
+/// encoded as base64 unless the `bytes` feature is enabled.
+#[cfg(feature = "bytes")]
+pub(crate) fn bytes_field<T: From<bytes::Bytes>>(data: &[u8]) -> T {
+    bytes::Bytes::copy_from_slice(data).into()
+}
+
+/// The value of a `bytes` field of the generated types holding `data`,
+/// encoded as base64 unless the `bytes` feature is enabled.
+#[cfg(not(feature = "bytes"))]
+pub(crate) fn bytes_field<T: From<String>>(data: &[u8]) -> T {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, byte)| bits | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (bits >> (18 - 6 * i)) & 0x3f;
+                encoded.push(ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
         }
     }
+    encoded.into()
 }
 
 use enums::Update;
+use futures_channel::oneshot;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::task::{Context, Poll};
 
 static EXTRA_COUNTER: AtomicU32 = AtomicU32::new(0);
 static OBSERVER: Lazy<observer::Observer> = Lazy::new(observer::Observer::new);
 
-/// Create a TdLib client returning its id. Note that to start receiving
-/// updates for a client you need to send at least a request with it first.
+/// Load the tdjson library from `path`, or from the `TDJSON_PATH` environment
+/// variable and then the default search paths of the platform when `None`.
+///
+/// Without calling this function, the library is loaded from the default
+/// location on first use, panicking if it can't be found; calling it at startup
+/// lets the application report a missing library gracefully instead. Once the
+/// library has been loaded, further calls do nothing.
+#[cfg(feature = "dynamic-loading")]
+pub fn load_tdjson(path: Option<impl AsRef<std::ffi::OsStr>>) -> Result<(), libloading::Error> {
+    tdjson::dynamic::load(path.map(|path| path.as_ref().to_os_string()))
+}
+
+/// Check that the TDLib used by `client_id` is the version the bindings were
+/// generated for, [`TDLIB_API_VERSION`], returning the version.
+///
+/// A mismatch usually shows up later as baffling deserialization failures, so
+/// calling this at startup reports it upfront: it's logged as a warning and
+/// returned as [`TdError::VersionMismatch`].
+pub async fn check_tdlib_version(client_id: i32) -> Result<String, TdError> {
+    let found = match functions::get_option("version", client_id).await? {
+        enums::OptionValue::String(version) => version.value.to_string(),
+        _ => String::new(),
+    };
+    if found != TDLIB_API_VERSION {
+        let error = TdError::VersionMismatch {
+            expected: TDLIB_API_VERSION,
+            found,
+        };
+        log::warn!("{error}");
+        return Err(error);
+    }
+    Ok(found)
+}
+
+/// The statistics of the requests waiting for their response, e.g. to find
+/// out why a client looks stuck.
+pub fn pending_requests() -> PendingRequests {
+    OBSERVER.pending_requests()
+}
+
+/// Set how many requests can wait for their response at once, 100000 by
+/// default. When there are too many, the requests whose caller stopped waiting
+/// are removed first, then the oldest ones fail with an error with code 429.
+pub fn set_max_pending_requests(capacity: usize) {
+    OBSERVER.set_capacity(capacity);
+}
+
+/// Set how many requests of a client can wait for their response at once, or
+/// `None` for no limit, which is the default. The requests over the limit are
+/// held back, by priority, until the previous ones are answered, e.g. to
+/// bound the memory used when the application spawns a task per request.
+///
+/// The requests failed with an error with code 408 or 429 stop counting
+/// against the limit on the next call to [`receive`], without waiting for
+/// TDLib to answer them.
+pub fn set_max_in_flight_requests(limit: Option<usize>) {
+    queue::set_max_in_flight(limit.map(|limit| limit.max(1)));
+}
+
+/// Set how long the requests sent from now on can wait for their response
+/// before failing with an error with code 408, or `None` to wait forever,
+/// which is the default. The requests sent with [`with_timeout`] use their
+/// own timeout instead.
+///
+/// The expired requests are checked by [`receive`] at most once per second, so
+/// they can fail up to a second late.
+pub fn set_request_expiry(expiry: Option<std::time::Duration>) {
+    OBSERVER.set_expiry(expiry);
+}
+
+/// Create a TdLib client returning its id, through the installed [`Transport`].
+/// Note that to start receiving updates for a client you need to send at least
+/// a request with it first.
 pub fn create_client() -> i32 {
-    tdjson::create_client()
+    transport::transport().create_client()
+}
+
+/// Execute a JSON-serialized request synchronously, through the installed
+/// [`Transport`], returning its JSON-serialized response.
+///
+/// TDLib can only execute some requests synchronously, e.g.
+/// `parseTextEntities` or `setLogVerbosityLevel`, and answers the other ones
+/// with an error. The requests don't need a client.
+pub fn execute(request: &str) -> String {
+    transport::transport().execute(request.to_string())
+}
+
+/// Execute `request` synchronously, parsing its response as a `T`, like the
+/// generated functions do with the responses of the requests they send.
+pub(crate) fn execute_request<T: serde::de::DeserializeOwned>(
+    request: &impl Serialize,
+    expected_type: &'static str,
+) -> Result<T, TdError> {
+    let request = serde_json::to_string(request).unwrap();
+    let response = transport::transport().execute(request);
+    match serde_json::from_str(&response) {
+        Ok(result) => Ok(result),
+        Err(error) => match serde_json::from_str::<types::Error>(&response) {
+            Ok(api_error) => Err(TdError::Api(api_error)),
+            Err(_) => Err(TdError::Deserialization {
+                expected_type,
+                payload: response,
+                error,
+            }),
+        },
+    }
 }
 
 /// Receive a single update or response from TdLib. If it's an update, it
 /// returns a tuple with the `Update` and the associated `client_id`.
 /// Note that to start receiving updates for a client you need to send
 /// at least a request with it first.
+///
+/// With the `wasm` feature, it doesn't wait for an update, since that would
+/// block the browser event loop: use `receive_async` instead.
 pub fn receive() -> Option<(Update, i32)> {
-    let response = tdjson::receive(2.0);
-    if let Some(response_str) = response {
-        let response: Value = serde_json::from_str(&response_str).unwrap();
+    OBSERVER.sweep();
+    updates::sweep();
+    transport::transport()
+        .receive(2.0)
+        .and_then(handle_response)
+}
+
+/// Receive the next update from TdLib, returning it with the associated
+/// `client_id`. The responses to the requests received in the meantime are
+/// delivered to their callers, while the browser event loop keeps running.
+#[cfg(feature = "wasm")]
+pub async fn receive_async() -> (Update, i32) {
+    loop {
+        OBSERVER.sweep();
+        updates::sweep();
+        if let Some(update) = handle_response(tdjson::wasm::next_response().await) {
+            return update;
+        }
+    }
+}
+
+/// The fields routing a response to the caller of the request, or an update
+/// to the application. The other fields are skipped without being allocated.
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(rename = "@extra")]
+    extra: Option<Value>,
+    #[serde(rename = "@client_id")]
+    client_id: Option<i64>,
+}
+
+/// Deliver a response to the caller of the request, or return it if it's an
+/// update.
+///
+/// Only the envelope is scanned before routing the response: the caller
+/// deserializes its response from the string, and the updates are
+/// deserialized from it once.
+fn handle_response(response_str: String) -> Option<(Update, i32)> {
+    let envelope: Envelope = match serde_json::from_str(&response_str) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            log::warn!("Received an invalid response: {response_str}\nReason: {e}");
+            return None;
+        }
+    };
 
-        match response.get("@extra") {
+    match envelope.extra {
+        Some(extra) => match extra.as_u64() {
             Some(extra) => {
-                let extra = extra.as_u64().unwrap() as u32;
+                let extra = extra as u32;
+                // The identical requests sharing the response
+                for follower in queue::complete(extra) {
+                    OBSERVER.notify(follower, response_str.clone());
+                }
                 OBSERVER.notify(extra, response_str);
             }
-            None => {
-                let client_id = response["@client_id"].as_i64().unwrap() as i32;
-                match serde_json::from_value(response) {
-                    Ok(update) => {
-                        return Some((update, client_id));
-                    }
-                    Err(e) => {
-                        log::warn!("Received an unknown response: {response_str}\nReason: {e}");
-                    }
-                }
+            None => log::warn!("Received a response with an unknown @extra: {response_str}"),
+        },
+        None => {
+            let Some(client_id) = envelope.client_id else {
+                log::warn!("Received an update without @client_id: {response_str}");
+                return None;
+            };
+            let client_id = client_id as i32;
+            if let Some(update) = parse_update(response_str) {
+                updates::handle_update(&update, client_id);
+                return Some((update, client_id));
             }
         }
     }
@@ -112,12 +421,55 @@ pub fn receive() -> Option<(Update, i32)> {
     None
 }
 
-pub(crate) async fn send_request(client_id: i32, mut request: Value) -> String {
+/// Deserialize an update from its JSON string.
+#[cfg(not(feature = "simd-json"))]
+fn parse_update(response: String) -> Option<Update> {
+    match serde_json::from_str(&response) {
+        Ok(update) => Some(update),
+        Err(e) => {
+            log::warn!("Received an unknown response: {response}\nReason: {e}");
+            None
+        }
+    }
+}
+
+/// Deserialize an update from its JSON string with `simd-json`, which parses
+/// the string in place, so it's no longer available for the error messages.
+#[cfg(feature = "simd-json")]
+fn parse_update(response: String) -> Option<Update> {
+    let mut bytes = response.into_bytes();
+    match simd_json::serde::from_slice(&mut bytes) {
+        Ok(update) => Some(update),
+        Err(e) => {
+            log::warn!("Received an unknown response\nReason: {e}");
+            None
+        }
+    }
+}
+
+/// The JSON response to a request, resolved when TDLib answers it.
+pub(crate) struct Response(oneshot::Receiver<String>);
+
+impl Future for Response {
+    type Output = String;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<String> {
+        Pin::new(&mut self.0).poll(cx).map(|response| {
+            response.unwrap_or_else(|_| observer::error_response(500, "Request dropped"))
+        })
+    }
+}
+
+/// Serialize the request straight to a JSON string and send it through the
+/// queue of the client, returning its response. The request is serialized
+/// right away, so that the caller can wipe its secrets before awaiting the
+/// response.
+pub(crate) fn send_request(client_id: i32, request: &impl Serialize) -> Response {
     let extra = EXTRA_COUNTER.fetch_add(1, Ordering::Relaxed);
-    request["@extra"] = serde_json::to_value(extra).unwrap();
+    let request = serde_json::to_string(request).unwrap();
 
-    let receiver = OBSERVER.subscribe(extra);
-    tdjson::send(client_id, request.to_string());
+    let (sender, receiver) = oneshot::channel();
+    queue::send(client_id, extra, request, sender);
 
-    receiver.await.unwrap()
+    Response(receiver)
 }
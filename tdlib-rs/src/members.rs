@@ -0,0 +1,134 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The members of the chats, hiding the paging of `getSupergroupMembers`
+//! and the differences between the basic groups and the supergroups.
+use crate::enums::{self, ChatMembersFilter, ChatType, SupergroupMembersFilter};
+use crate::pagination::{NextPage, Page};
+use crate::{chats, functions, types, Paginated, TdError};
+
+/// The number of members requested at once, the maximum allowed by TDLib.
+const MEMBERS_PAGE_SIZE: i32 = 200;
+
+/// The members streamed by [`chat_members`] and [`supergroup_members`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MemberFilter {
+    /// The owner and the administrators.
+    Administrators,
+    /// The bots.
+    Bots,
+    /// The members with restricted permissions.
+    Restricted,
+}
+
+impl MemberFilter {
+    fn supergroup_filter(self) -> SupergroupMembersFilter {
+        match self {
+            Self::Administrators => SupergroupMembersFilter::Administrators,
+            Self::Bots => SupergroupMembersFilter::Bots,
+            Self::Restricted => SupergroupMembersFilter::Restricted(Default::default()),
+        }
+    }
+
+    fn chat_filter(self) -> ChatMembersFilter {
+        match self {
+            Self::Administrators => ChatMembersFilter::Administrators,
+            Self::Bots => ChatMembersFilter::Bots,
+            Self::Restricted => ChatMembersFilter::Restricted,
+        }
+    }
+}
+
+/// The members of a response, and their total count, failing on the
+/// unrecognized constructors.
+fn members_of(members: enums::ChatMembers) -> Result<(Vec<types::ChatMember>, i32), TdError> {
+    match members {
+        enums::ChatMembers::ChatMembers(members) => Ok((members.members, members.total_count)),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::ChatMembers::Unrecognized => Err(TdError::unrecognized("ChatMembers")),
+    }
+}
+
+/// The page of the members of `supergroup_id` from `offset`.
+fn supergroup_page(
+    supergroup_id: i64,
+    filter: Option<MemberFilter>,
+    offset: i32,
+    client_id: i32,
+) -> NextPage<types::ChatMember> {
+    Box::pin(async move {
+        let page = functions::get_supergroup_members(
+            supergroup_id,
+            filter.map(MemberFilter::supergroup_filter),
+            offset,
+            MEMBERS_PAGE_SIZE,
+            client_id,
+        );
+        let (members, total_count) = members_of(page.await?)?;
+        let offset = offset.saturating_add(members.len() as i32);
+        // The total count is only an estimate for the large supergroups, so
+        // the members also end with an empty page
+        let next = (!members.is_empty() && offset < total_count)
+            .then(|| supergroup_page(supergroup_id, filter, offset, client_id));
+        Ok(Page {
+            items: members,
+            next,
+        })
+    })
+}
+
+/// Stream the members of the supergroup or channel `supergroup_id`, only
+/// the ones matching `filter` if it's given, paging through
+/// `getSupergroupMembers`.
+///
+/// The members of the channels can only be listed by their administrators.
+pub fn supergroup_members(
+    supergroup_id: i64,
+    filter: Option<MemberFilter>,
+    client_id: i32,
+) -> Paginated<types::ChatMember> {
+    Paginated::new(supergroup_page(supergroup_id, filter, 0, client_id))
+}
+
+/// Stream the members of the chat `chat_id`, only the ones matching
+/// `filter` if it's given, e.g.
+///
+/// ```ignore
+/// let mut admins = members::chat_members(chat_id, Some(MemberFilter::Administrators), client.id());
+/// while let Some(admin) = admins.next().await {
+///     println!("{:?}", admin?.member_id);
+/// }
+/// ```
+///
+/// The members of the supergroups and channels are paged through
+/// `getSupergroupMembers`, while the ones of the other chats are returned at
+/// once by `searchChatMembers`.
+pub fn chat_members(
+    chat_id: i64,
+    filter: Option<MemberFilter>,
+    client_id: i32,
+) -> Paginated<types::ChatMember> {
+    Paginated::new(Box::pin(async move {
+        let chat = chats::chat(chat_id, client_id).await?;
+        if let ChatType::Supergroup(supergroup) = &chat.r#type {
+            return supergroup_page(supergroup.supergroup_id, filter, 0, client_id).await;
+        }
+        let members = functions::search_chat_members(
+            chat_id,
+            "",
+            MEMBERS_PAGE_SIZE,
+            filter.map(MemberFilter::chat_filter),
+            client_id,
+        );
+        let (members, _) = members_of(members.await?)?;
+        Ok(Page {
+            items: members,
+            next: None,
+        })
+    }))
+}
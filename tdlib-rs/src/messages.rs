@@ -0,0 +1,257 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers over the messages, hiding the temporary ids of the messages being
+//! sent and the pagination of the chat histories and searches.
+use crate::enums::{self, MessageSender, MessageSendingState, SearchMessagesFilter, Update};
+use crate::pagination::{NextPage, Page};
+use crate::progress::{Progress, ProgressUnit};
+use crate::{functions, types, updates, Paginated, TdError};
+use futures_core::Stream;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+
+/// The message of a response, failing on the unrecognized constructors.
+fn message_of(message: enums::Message) -> Result<types::Message, TdError> {
+    match message {
+        enums::Message::Message(message) => {
+            let message: &types::Message = &message;
+            Ok(message.clone())
+        }
+        #[cfg(feature = "unrecognized-variants")]
+        enums::Message::Unrecognized => Err(TdError::unrecognized("Message")),
+    }
+}
+
+/// Send a message with `send`, e.g. the future of
+/// [`functions::send_message`](crate::functions::send_message), and wait
+/// until it's delivered to the server, returning the message with its final
+/// id.
+///
+/// TDLib answers the requests sending a message with a copy of the message
+/// holding a temporary id, which is replaced once the server acknowledges it
+/// by `updateMessageSendSucceeded`, or `updateMessageSendFailed` if it
+/// couldn't be sent, returned as [`TdError::Api`].
+///
+/// ```ignore
+/// let content = /* ... */;
+/// let request = functions::send_message(chat_id, None, None, None, content, client_id);
+/// let message = tdlib_rs::messages::sent(request, client_id).await?;
+/// ```
+pub async fn sent(
+    send: impl Future<Output = Result<enums::Message, TdError>>,
+    client_id: i32,
+) -> Result<types::Message, TdError> {
+    // The futures of the generated functions only send their request when
+    // they are first polled, so the outcome can't be received before
+    let mut results = updates::subscribe(client_id, |update| {
+        matches!(
+            update,
+            Update::MessageSendSucceeded(_) | Update::MessageSendFailed(_)
+        )
+    });
+
+    let message = send.await.and_then(message_of)?;
+    match &message.sending_state {
+        None => return Ok(message),
+        Some(MessageSendingState::Failed(state)) => return Err(TdError::Api(state.error.clone())),
+        Some(MessageSendingState::Pending(_)) => {}
+        #[cfg(feature = "unrecognized-variants")]
+        Some(MessageSendingState::Unrecognized) => {}
+    }
+
+    while let Some(update) = poll_fn(|cx| Pin::new(&mut results).poll_next(cx)).await {
+        match update {
+            Update::MessageSendSucceeded(update)
+                if update.old_message_id == message.id
+                    && update.message.chat_id == message.chat_id =>
+            {
+                return Ok(update.message.clone());
+            }
+            Update::MessageSendFailed(update)
+                if update.old_message_id == message.id
+                    && update.message.chat_id == message.chat_id =>
+            {
+                return Err(TdError::Api(update.error.clone()));
+            }
+            _ => {}
+        }
+    }
+    // The client was closed
+    Err(TdError::Canceled)
+}
+
+/// The number of messages requested at once by [`chat_history`], the maximum
+/// allowed by TDLib.
+const HISTORY_PAGE_SIZE: i32 = 100;
+
+/// The page of the history of `chat_id` older than `from_message_id`.
+fn history_page(chat_id: i64, from_message_id: i64, client_id: i32) -> NextPage<types::Message> {
+    Box::pin(async move {
+        let page = functions::get_chat_history(
+            chat_id,
+            from_message_id,
+            0,
+            HISTORY_PAGE_SIZE,
+            false,
+            client_id,
+        );
+        let messages = match page.await? {
+            enums::Messages::Messages(messages) => messages.messages,
+            #[cfg(feature = "unrecognized-variants")]
+            enums::Messages::Unrecognized => return Err(TdError::unrecognized("Messages")),
+        };
+        // The first message may be the one the page starts from
+        let messages: Vec<_> = messages
+            .into_iter()
+            .flatten()
+            .filter(|message| from_message_id == 0 || message.id < from_message_id)
+            .collect();
+        // TDLib may return fewer messages than requested, e.g. only the ones
+        // in its cache, so the history only ends with an empty page
+        let next = messages
+            .last()
+            .map(|message| history_page(chat_id, message.id, client_id));
+        Ok(Page {
+            items: messages,
+            next,
+        })
+    })
+}
+
+/// Stream the messages of the chat `chat_id`, from the last one to the first
+/// one, paging through `getChatHistory`.
+pub fn chat_history(chat_id: i64, client_id: i32) -> Paginated<types::Message> {
+    Paginated::new(history_page(chat_id, 0, client_id))
+}
+
+/// Load up to `limit` messages of the chat `chat_id`, from the last one to
+/// the first one, reporting the messages loaded so far out of `limit` to
+/// `on_progress` after every page.
+///
+/// The last progress has the number of messages loaded as total, since the
+/// history can be shorter than `limit`.
+pub async fn load_chat_history(
+    chat_id: i64,
+    limit: usize,
+    mut on_progress: impl FnMut(Progress),
+    client_id: i32,
+) -> Result<Vec<types::Message>, TdError> {
+    let progress = |done: usize, total: usize| {
+        Progress::new(done as i64, Some(total as i64), ProgressUnit::Messages)
+    };
+    let mut messages = Vec::new();
+    let mut page = Some(history_page(chat_id, 0, client_id));
+    on_progress(progress(0, limit));
+    while let Some(next) = page.take() {
+        if messages.len() >= limit {
+            break;
+        }
+        let Page { items, next } = next.await?;
+        messages.extend(items);
+        messages.truncate(limit);
+        page = next;
+        on_progress(progress(messages.len(), limit));
+    }
+    if messages.len() < limit {
+        on_progress(progress(messages.len(), messages.len()));
+    }
+    Ok(messages)
+}
+
+/// The number of messages requested at once by [`search_chat_messages`], the
+/// maximum allowed by TDLib.
+const SEARCH_PAGE_SIZE: i32 = 100;
+
+/// The found messages of a response, failing on the unrecognized
+/// constructors.
+// The found messages are only boxed by the `box-large-variants` feature
+#[allow(clippy::useless_conversion)]
+fn found_messages_of(found: enums::FoundChatMessages) -> Result<types::FoundChatMessages, TdError> {
+    match found {
+        enums::FoundChatMessages::FoundChatMessages(found) => {
+            Ok(*Box::<types::FoundChatMessages>::from(found))
+        }
+        #[cfg(feature = "unrecognized-variants")]
+        enums::FoundChatMessages::Unrecognized => Err(TdError::unrecognized("FoundChatMessages")),
+    }
+}
+
+/// The page of the messages of `chat_id` found from `from_message_id`.
+fn search_page(
+    chat_id: i64,
+    query: String,
+    sender_id: Option<MessageSender>,
+    filter: Option<SearchMessagesFilter>,
+    from_message_id: i64,
+    client_id: i32,
+) -> NextPage<types::Message> {
+    Box::pin(async move {
+        #[cfg(not(feature = "api-1.8.19"))]
+        let page = functions::search_chat_messages(
+            chat_id,
+            None,
+            query.clone(),
+            sender_id.clone(),
+            from_message_id,
+            0,
+            SEARCH_PAGE_SIZE,
+            filter.clone(),
+            client_id,
+        );
+        #[cfg(feature = "api-1.8.19")]
+        let page = functions::search_chat_messages(
+            chat_id,
+            query.clone(),
+            sender_id.clone(),
+            from_message_id,
+            0,
+            SEARCH_PAGE_SIZE,
+            filter.clone(),
+            0,
+            client_id,
+        );
+        let found = found_messages_of(page.await?)?;
+        // The search ends when TDLib returns no offset for the next page
+        let next_from_message_id = found.next_from_message_id;
+        let next = (next_from_message_id != 0).then(|| {
+            search_page(
+                chat_id,
+                query,
+                sender_id,
+                filter,
+                next_from_message_id,
+                client_id,
+            )
+        });
+        Ok(Page {
+            items: found.messages,
+            next,
+        })
+    })
+}
+
+/// Stream the messages of the chat `chat_id` matching `query`, sent by
+/// `sender_id` and matching `filter` if they are given, from the newest to
+/// the oldest, paging through `searchChatMessages`.
+pub fn search_chat_messages(
+    chat_id: i64,
+    query: impl Into<String>,
+    sender_id: Option<MessageSender>,
+    filter: Option<SearchMessagesFilter>,
+    client_id: i32,
+) -> Paginated<types::Message> {
+    Paginated::new(search_page(
+        chat_id,
+        query.into(),
+        sender_id,
+        filter,
+        0,
+        client_id,
+    ))
+}
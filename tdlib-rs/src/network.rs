@@ -0,0 +1,161 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The network data usage of TDLib, reported by `getNetworkStatistics` as
+//! entries per file type and network type, and summed up by
+//! [`network_statistics`].
+use crate::enums::{self, FileType, NetworkStatisticsEntry, NetworkType};
+use crate::{functions, types, TdError};
+use std::fmt;
+use std::time::Duration;
+
+/// The units of [`format_bytes`], each 1024 times the previous one.
+const BYTE_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// Format an amount of bytes with a binary unit, e.g. `"1.5 MiB"`.
+pub fn format_bytes(bytes: i64) -> String {
+    let mut amount = bytes as f64;
+    let mut unit = 0;
+    while amount.abs() >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+        amount /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{amount:.1} {}", BYTE_UNITS[unit])
+    }
+}
+
+/// The data sent and received through the network.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DataUsage {
+    /// The number of bytes sent.
+    pub sent_bytes: i64,
+    /// The number of bytes received.
+    pub received_bytes: i64,
+}
+
+impl DataUsage {
+    /// The number of bytes sent and received.
+    pub fn total_bytes(&self) -> i64 {
+        self.sent_bytes + self.received_bytes
+    }
+
+    fn add(&mut self, sent_bytes: i64, received_bytes: i64) {
+        self.sent_bytes += sent_bytes;
+        self.received_bytes += received_bytes;
+    }
+}
+
+/// Formats as `"<sent> sent, <received> received"`, e.g.
+/// `"1.5 MiB sent, 20.0 MiB received"`.
+impl fmt::Display for DataUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sent = format_bytes(self.sent_bytes);
+        let received = format_bytes(self.received_bytes);
+        write!(f, "{sent} sent, {received} received")
+    }
+}
+
+/// The network data usage returned by [`network_statistics`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkUsage {
+    /// The data used by the files and the calls.
+    pub total: DataUsage,
+    /// The data used by the calls.
+    pub calls: DataUsage,
+    /// The total duration of the calls.
+    pub call_duration: Duration,
+    /// The data used by the files by file type, none being the data not
+    /// related to files, from the largest to the smallest.
+    pub by_file_type: Vec<(Option<FileType>, DataUsage)>,
+    /// The data used by the files and the calls by network type, from the
+    /// largest to the smallest.
+    pub by_network_type: Vec<(NetworkType, DataUsage)>,
+    /// The statistics the usage is summed up from, e.g. for the date from
+    /// which they're collected.
+    pub statistics: types::NetworkStatistics,
+}
+
+impl NetworkUsage {
+    fn new(statistics: types::NetworkStatistics) -> Self {
+        let mut usage = Self {
+            total: DataUsage::default(),
+            calls: DataUsage::default(),
+            call_duration: Duration::ZERO,
+            by_file_type: Vec::new(),
+            by_network_type: Vec::new(),
+            statistics,
+        };
+        for entry in &usage.statistics.entries {
+            let (network_type, sent_bytes, received_bytes) = match entry {
+                NetworkStatisticsEntry::File(entry) => {
+                    entry_of(&mut usage.by_file_type, &entry.file_type)
+                        .add(entry.sent_bytes, entry.received_bytes);
+                    (&entry.network_type, entry.sent_bytes, entry.received_bytes)
+                }
+                NetworkStatisticsEntry::Call(entry) => {
+                    usage.calls.add(entry.sent_bytes, entry.received_bytes);
+                    #[cfg(feature = "duration")]
+                    let duration = entry.duration;
+                    #[cfg(not(feature = "duration"))]
+                    let duration = Duration::try_from_secs_f64(entry.duration).unwrap_or_default();
+                    usage.call_duration += duration;
+                    (&entry.network_type, entry.sent_bytes, entry.received_bytes)
+                }
+                #[cfg(feature = "unrecognized-variants")]
+                NetworkStatisticsEntry::Unrecognized => continue,
+            };
+            usage.total.add(sent_bytes, received_bytes);
+            entry_of(&mut usage.by_network_type, network_type).add(sent_bytes, received_bytes);
+        }
+        usage
+            .by_file_type
+            .sort_by_key(|(_, usage)| std::cmp::Reverse(usage.total_bytes()));
+        usage
+            .by_network_type
+            .sort_by_key(|(_, usage)| std::cmp::Reverse(usage.total_bytes()));
+        usage
+    }
+}
+
+/// The usage of `key` in `usages`, added if missing.
+fn entry_of<'a, K: Clone + PartialEq>(
+    usages: &'a mut Vec<(K, DataUsage)>,
+    key: &K,
+) -> &'a mut DataUsage {
+    let index = match usages.iter().position(|(other, _)| other == key) {
+        Some(index) => index,
+        None => {
+            usages.push((key.clone(), DataUsage::default()));
+            usages.len() - 1
+        }
+    };
+    &mut usages[index].1
+}
+
+/// The network data usage, since the last reset or only since TDLib was
+/// started if `only_current`.
+pub async fn network_statistics(
+    only_current: bool,
+    client_id: i32,
+) -> Result<NetworkUsage, TdError> {
+    match functions::get_network_statistics(only_current, client_id).await? {
+        enums::NetworkStatistics::NetworkStatistics(statistics) => {
+            Ok(NetworkUsage::new(statistics))
+        }
+        #[cfg(feature = "unrecognized-variants")]
+        enums::NetworkStatistics::Unrecognized => Err(TdError::unrecognized("NetworkStatistics")),
+    }
+}
+
+/// Reset the network data usage to zero.
+pub async fn reset_network_statistics(client_id: i32) -> Result<(), TdError> {
+    functions::reset_network_statistics(client_id).await
+}
@@ -0,0 +1,140 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The notification settings of the chats, each either set for the chat or
+//! using the default settings of its scope, i.e. the private chats, the
+//! groups or the channels.
+use crate::enums::{self, ChatType, NotificationSettingsScope};
+use crate::{chats, functions, types, TdError};
+use std::time::Duration;
+
+/// The mute duration muting a chat forever, TDLib treating the durations
+/// over a year as such.
+pub const MUTE_FOREVER: Duration = Duration::from_secs(i32::MAX as u64);
+
+/// The time left before the notifications are unmuted, as represented by the
+/// generated types.
+#[cfg(feature = "duration")]
+fn mute_for(duration: Duration) -> Duration {
+    duration
+}
+
+/// The time left before the notifications are unmuted, as represented by the
+/// generated types.
+#[cfg(not(feature = "duration"))]
+fn mute_for(duration: Duration) -> i32 {
+    duration.as_secs().min(i32::MAX as u64) as i32
+}
+
+/// The time left before the notifications are unmuted, from the generated
+/// types.
+#[cfg(feature = "duration")]
+fn mute_duration(mute_for: Duration) -> Duration {
+    mute_for
+}
+
+/// The time left before the notifications are unmuted, from the generated
+/// types.
+#[cfg(not(feature = "duration"))]
+fn mute_duration(mute_for: i32) -> Duration {
+    Duration::from_secs(mute_for.max(0) as u64)
+}
+
+/// The scope of the default notification settings of `chat`.
+pub fn scope_of(chat: &types::Chat) -> NotificationSettingsScope {
+    match &chat.r#type {
+        ChatType::Private(_) | ChatType::Secret(_) => NotificationSettingsScope::PrivateChats,
+        ChatType::BasicGroup(_) => NotificationSettingsScope::GroupChats,
+        ChatType::Supergroup(supergroup) if supergroup.is_channel => {
+            NotificationSettingsScope::ChannelChats
+        }
+        ChatType::Supergroup(_) => NotificationSettingsScope::GroupChats,
+        #[cfg(feature = "unrecognized-variants")]
+        ChatType::Unrecognized => NotificationSettingsScope::PrivateChats,
+    }
+}
+
+/// The time left before the notifications of a chat are unmuted, zero if
+/// they aren't muted, from the settings of the chat and the default settings
+/// of its scope.
+pub fn effective_mute_for(
+    chat_settings: &types::ChatNotificationSettings,
+    scope_settings: &types::ScopeNotificationSettings,
+) -> Duration {
+    if chat_settings.use_default_mute_for {
+        mute_duration(scope_settings.mute_for)
+    } else {
+        mute_duration(chat_settings.mute_for)
+    }
+}
+
+/// The default notification settings of `scope`.
+pub async fn scope_settings(
+    scope: NotificationSettingsScope,
+    client_id: i32,
+) -> Result<types::ScopeNotificationSettings, TdError> {
+    match functions::get_scope_notification_settings(scope, client_id).await? {
+        enums::ScopeNotificationSettings::ScopeNotificationSettings(settings) => Ok(settings),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::ScopeNotificationSettings::Unrecognized => {
+            Err(TdError::unrecognized("ScopeNotificationSettings"))
+        }
+    }
+}
+
+/// The time left before the notifications of the chat `chat_id` are unmuted,
+/// zero if they aren't muted.
+pub async fn chat_mute_for(chat_id: i64, client_id: i32) -> Result<Duration, TdError> {
+    let chat = chats::chat(chat_id, client_id).await?;
+    if !chat.notification_settings.use_default_mute_for {
+        return Ok(mute_duration(chat.notification_settings.mute_for));
+    }
+    let scope_settings = scope_settings(scope_of(&chat), client_id).await?;
+    Ok(effective_mute_for(
+        &chat.notification_settings,
+        &scope_settings,
+    ))
+}
+
+/// Whether the notifications of the chat `chat_id` are muted.
+pub async fn is_chat_muted(chat_id: i64, client_id: i32) -> Result<bool, TdError> {
+    Ok(!chat_mute_for(chat_id, client_id).await?.is_zero())
+}
+
+/// Set the mute settings of the chat `chat_id`, keeping its other settings.
+async fn set_chat_mute_for(
+    chat_id: i64,
+    use_default_mute_for: bool,
+    duration: Duration,
+    client_id: i32,
+) -> Result<(), TdError> {
+    let chat = chats::chat(chat_id, client_id).await?;
+    let settings = types::ChatNotificationSettings {
+        use_default_mute_for,
+        mute_for: mute_for(duration),
+        ..chat.notification_settings
+    };
+    functions::set_chat_notification_settings(chat_id, settings, client_id).await
+}
+
+/// Mute the notifications of the chat `chat_id` for `duration`, or forever
+/// with [`MUTE_FOREVER`].
+pub async fn mute_chat(chat_id: i64, duration: Duration, client_id: i32) -> Result<(), TdError> {
+    set_chat_mute_for(chat_id, false, duration, client_id).await
+}
+
+/// Unmute the notifications of the chat `chat_id`, even if its scope is
+/// muted.
+pub async fn unmute_chat(chat_id: i64, client_id: i32) -> Result<(), TdError> {
+    set_chat_mute_for(chat_id, false, Duration::ZERO, client_id).await
+}
+
+/// Make the chat `chat_id` follow the mute settings of its scope again.
+pub async fn use_default_mute(chat_id: i64, client_id: i32) -> Result<(), TdError> {
+    set_chat_mute_for(chat_id, true, Duration::ZERO, client_id).await
+}
@@ -8,30 +8,193 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 use futures_channel::oneshot;
+use serde_json::json;
+use std::cell::Cell;
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The default number of requests that can wait for their response at once.
+const DEFAULT_CAPACITY: usize = 100_000;
+
+/// The minimum interval between two sweeps of the stale requests.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The time elapsed since an arbitrary, fixed point in time.
+#[cfg(not(feature = "wasm"))]
+pub(crate) fn now() -> Duration {
+    static START: once_cell::sync::Lazy<std::time::Instant> =
+        once_cell::sync::Lazy::new(std::time::Instant::now);
+    START.elapsed()
+}
+
+/// The time elapsed since the Unix epoch, since `Instant` is not available
+/// in the browsers.
+#[cfg(feature = "wasm")]
+pub(crate) fn now() -> Duration {
+    Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
+thread_local! {
+    /// The timeout of the requests sent by the future being polled.
+    static CURRENT_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Fail the requests sent by `future` with an error with code 408 if they
+/// aren't answered within `timeout`, instead of after the expiry set with
+/// [`set_request_expiry`](crate::set_request_expiry), e.g.
+///
+/// ```ignore
+/// let chat = with_timeout(Duration::from_secs(5), functions::get_chat(chat_id, client.id())).await?;
+/// ```
+///
+/// The timeout of a request starts once it's sent to TDLib, and is checked
+/// by [`receive`](crate::receive) at most once per second, so the request
+/// can fail up to a second late.
+pub fn with_timeout<F: Future>(timeout: Duration, future: F) -> WithTimeout<F> {
+    WithTimeout {
+        timeout,
+        future: Box::pin(future),
+    }
+}
+
+/// The future returned by [`with_timeout`].
+pub struct WithTimeout<F> {
+    timeout: Duration,
+    future: Pin<Box<F>>,
+}
+
+/// Restores the timeout of the outer future once the inner one is polled.
+struct RestoreTimeout(Option<Duration>);
+
+impl Drop for RestoreTimeout {
+    fn drop(&mut self) {
+        CURRENT_TIMEOUT.with(|timeout| timeout.set(self.0));
+    }
+}
+
+impl<F: Future> Future for WithTimeout<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let previous = CURRENT_TIMEOUT.with(|timeout| timeout.replace(Some(self.timeout)));
+        let _restore = RestoreTimeout(previous);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// The timeout set with [`with_timeout`] for the requests being sent, if any.
+pub(crate) fn current_timeout() -> Option<Duration> {
+    CURRENT_TIMEOUT.with(Cell::get)
+}
+
+/// The JSON of an error answering a request on behalf of TDLib.
+pub(crate) fn error_response(code: i32, message: &str) -> String {
+    json!({ "@type": "error", "code": code, "message": message }).to_string()
+}
+
+/// Statistics of the requests waiting for their response, to debug the
+/// clients that look stuck.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PendingRequests {
+    /// The number of requests waiting for their response.
+    pub count: usize,
+    /// How long the oldest request has been waiting for its response.
+    pub oldest_age: Option<Duration>,
+}
+
+struct Pending {
+    sender: oneshot::Sender<String>,
+    since: Duration,
+    /// When the request times out, in the clock of [`now`].
+    deadline: Option<Duration>,
+}
+
+struct Settings {
+    capacity: usize,
+    expiry: Option<Duration>,
+    last_sweep: Duration,
+}
 
 pub(super) struct Observer {
-    requests: RwLock<HashMap<u32, oneshot::Sender<String>>>,
+    requests: RwLock<HashMap<u32, Pending>>,
+    settings: Mutex<Settings>,
+    /// The requests failed before their response, with the error they were
+    /// answered with, still holding their slot in the queue.
+    failed: Mutex<Vec<(u32, String)>>,
 }
 
 impl Observer {
     pub fn new() -> Self {
         Observer {
             requests: RwLock::default(),
+            settings: Mutex::new(Settings {
+                capacity: DEFAULT_CAPACITY,
+                expiry: None,
+                last_sweep: Duration::ZERO,
+            }),
+            failed: Mutex::default(),
         }
     }
 
-    pub fn subscribe(&self, extra: u32) -> oneshot::Receiver<String> {
-        let (sender, receiver) = oneshot::channel();
-        self.requests.write().unwrap().insert(extra, sender);
-        receiver
+    /// Send the response of a request to `sender`, timing out after
+    /// `timeout`, or else after the expiry set with [`Observer::set_expiry`],
+    /// if any.
+    pub fn register(&self, extra: u32, sender: oneshot::Sender<String>, timeout: Option<Duration>) {
+        let expiry = timeout.or(self.settings.lock().unwrap().expiry);
+        self.register_with_deadline(extra, sender, expiry.map(|expiry| now() + expiry));
+    }
+
+    /// Send the response of a request to `sender` until `deadline`, in the
+    /// clock of [`now`]: then, the observer answers it with an error with code
+    /// 408 and forgets it, so that the caller doesn't have to add its own
+    /// timeout.
+    pub fn register_with_deadline(
+        &self,
+        extra: u32,
+        sender: oneshot::Sender<String>,
+        deadline: Option<Duration>,
+    ) {
+        let now = now();
+        let capacity = self.settings.lock().unwrap().capacity;
+        let mut requests = self.requests.write().unwrap();
+        if requests.len() >= capacity {
+            Self::sweep_requests(&mut requests, now);
+        }
+        while requests.len() >= capacity.max(1) {
+            // Make room by failing the request waiting for the longest time
+            let Some(&oldest) = requests
+                .iter()
+                .min_by_key(|(_, pending)| pending.since)
+                .map(|(extra, _)| extra)
+            else {
+                break;
+            };
+            log::warn!("Too many pending requests, dropping the oldest one");
+            let pending = requests.remove(&oldest).unwrap();
+            let response = error_response(429, "Too many pending requests");
+            let _ = pending.sender.send(response.clone());
+            // The queue may be locked by the caller, so the request is
+            // released by the next sweep
+            self.failed.lock().unwrap().push((oldest, response));
+        }
+        requests.insert(
+            extra,
+            Pending {
+                sender,
+                since: now,
+                deadline,
+            },
+        );
     }
 
     pub fn notify(&self, extra: u32, response: String) {
         match self.requests.write().unwrap().remove(&extra) {
-            Some(sender) => {
-                if sender.send(response).is_err() {
+            Some(pending) => {
+                if pending.sender.send(response).is_err() {
                     log::warn!("Got a response of an unaccessible request");
                 }
             }
@@ -40,4 +203,109 @@ impl Observer {
             }
         }
     }
+
+    /// Remove the requests whose caller stopped waiting for the response, and
+    /// fail the ones past their deadline. It does nothing if it ran less than a second
+    /// ago, so that it can be called on every update.
+    ///
+    /// The failed requests are then released from the queue, so that they
+    /// don't hold back the other requests until TDLib answers them, and the
+    /// identical requests sharing their response fail with them.
+    pub fn sweep(&self) {
+        let now = now();
+        let is_due = {
+            let mut settings = self.settings.lock().unwrap();
+            let is_due = now.saturating_sub(settings.last_sweep) >= SWEEP_INTERVAL;
+            if is_due {
+                settings.last_sweep = now;
+            }
+            is_due
+        };
+        if is_due {
+            let timed_out = Self::sweep_requests(&mut self.requests.write().unwrap(), now);
+            self.failed.lock().unwrap().extend(timed_out);
+        }
+
+        let failed = std::mem::take(&mut *self.failed.lock().unwrap());
+        for (extra, response) in failed {
+            for follower in crate::queue::complete(extra) {
+                self.notify(follower, response.clone());
+            }
+        }
+    }
+
+    /// Remove the requests whose caller stopped waiting for the response, and
+    /// fail the ones past their deadline, returning them with their error.
+    fn sweep_requests(requests: &mut HashMap<u32, Pending>, now: Duration) -> Vec<(u32, String)> {
+        requests.retain(|_, pending| !pending.sender.is_canceled());
+
+        let timed_out: Vec<u32> = requests
+            .iter()
+            .filter(|(_, pending)| pending.deadline.is_some_and(|deadline| now >= deadline))
+            .map(|(&extra, _)| extra)
+            .collect();
+        timed_out
+            .into_iter()
+            .map(|extra| {
+                log::warn!("A request timed out without a response");
+                let pending = requests.remove(&extra).unwrap();
+                let response = error_response(408, "Request timed out");
+                let _ = pending.sender.send(response.clone());
+                (extra, response)
+            })
+            .collect()
+    }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        self.settings.lock().unwrap().capacity = capacity;
+    }
+
+    pub fn set_expiry(&self, expiry: Option<Duration>) {
+        self.settings.lock().unwrap().expiry = expiry;
+    }
+
+    pub fn pending_requests(&self) -> PendingRequests {
+        let now = now();
+        let requests = self.requests.read().unwrap();
+        PendingRequests {
+            count: requests.len(),
+            oldest_age: requests
+                .values()
+                .map(|pending| now.saturating_sub(pending.since))
+                .max(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_register_timeout() {
+        let observer = Observer::new();
+        observer.set_expiry(Some(Duration::from_secs(3600)));
+        let (sender, mut timed_out) = oneshot::channel();
+        observer.register(1, sender, Some(Duration::from_secs(5)));
+        let (sender, mut waiting) = oneshot::channel();
+        observer.register(2, sender, None);
+
+        let later = now() + Duration::from_secs(10);
+        let failed = Observer::sweep_requests(&mut observer.requests.write().unwrap(), later);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, 1);
+        let response = timed_out.try_recv().unwrap().unwrap();
+        assert!(response.contains("408"));
+        assert_eq!(waiting.try_recv(), Ok(None));
+        assert_eq!(observer.pending_requests().count, 1);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[tokio::test]
+    async fn check_with_timeout() {
+        let timeout = Duration::from_secs(3);
+        let current = with_timeout(timeout, async { current_timeout() }).await;
+        assert_eq!(current, Some(timeout));
+        assert_eq!(current_timeout(), None);
+    }
 }
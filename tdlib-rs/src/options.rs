@@ -0,0 +1,392 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The options of the clients: the [`OptionsCache`] fed by `updateOption`,
+//! the known [`OptionName`]s, and typed helpers over `getOption` and
+//! `setOption`.
+use crate::enums::OptionValue;
+use crate::{functions, types, TdError, TdString};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The current Unix time of the device.
+#[cfg(not(feature = "wasm"))]
+fn local_unix_time() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// The current Unix time of the device, since `SystemTime` is not available
+/// in the browsers.
+#[cfg(feature = "wasm")]
+fn local_unix_time() -> i64 {
+    (js_sys::Date::now() / 1000.0) as i64
+}
+
+/// Declares [`OptionName`], with the name of the option of each variant.
+macro_rules! option_names {
+    ($($(#[doc = $doc:literal])* $variant:ident => $name:literal,)*) => {
+        /// The options documented by TDLib, to use in place of their names.
+        ///
+        /// TDLib may know more options than the ones listed here, which can
+        /// still be accessed by their name.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum OptionName {
+            $($(#[doc = $doc])* $variant,)*
+        }
+
+        impl OptionName {
+            /// All the known options.
+            pub const ALL: &'static [OptionName] = &[$(OptionName::$variant,)*];
+
+            /// The name of the option, as known by TDLib.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(OptionName::$variant => $name,)*
+                }
+            }
+
+            /// The known option named `name`, if any.
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(OptionName::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+option_names! {
+    /// The TDLib version (string, read-only).
+    Version => "version",
+    /// The commit of the TDLib sources (string, read-only).
+    CommitHash => "commit_hash",
+    /// The identifier of the current user (integer, read-only).
+    MyId => "my_id",
+    /// The Unix time at which the current user was authorized (integer,
+    /// read-only).
+    AuthorizationDate => "authorization_date",
+    /// The Unix time of the server (integer, read-only).
+    UnixTime => "unix_time",
+    /// The offset of the local time zone from UTC, in seconds (integer).
+    UtcTimeOffset => "utc_time_offset",
+    /// Whether the current user has Telegram Premium (boolean, read-only).
+    IsPremium => "is_premium",
+    /// Whether Telegram Premium can be bought (boolean, read-only).
+    IsPremiumAvailable => "is_premium_available",
+    /// Whether the client uses the test data centers (boolean, read-only).
+    TestMode => "test_mode",
+    /// The maximum length of the text of a message (integer, read-only).
+    MessageTextLengthMax => "message_text_length_max",
+    /// The maximum length of the caption of a media message (integer,
+    /// read-only).
+    MessageCaptionLengthMax => "message_caption_length_max",
+    /// The maximum number of messages forwarded at once (integer, read-only).
+    ForwardedMessageCountMax => "forwarded_message_count_max",
+    /// The maximum number of members of a basic group (integer, read-only).
+    BasicGroupSizeMax => "basic_group_size_max",
+    /// The maximum number of members of a supergroup (integer, read-only).
+    SupergroupSizeMax => "supergroup_size_max",
+    /// The maximum number of pinned chats in the main chat list (integer,
+    /// read-only).
+    PinnedChatCountMax => "pinned_chat_count_max",
+    /// The URL of the Telegram links, e.g. `"https://t.me/"` (string,
+    /// read-only).
+    TMeUrl => "t_me_url",
+    /// The username of the bot searching for animations (string, read-only).
+    AnimationSearchBotUsername => "animation_search_bot_username",
+    /// The username of the bot searching for photos (string, read-only).
+    PhotoSearchBotUsername => "photo_search_bot_username",
+    /// The username of the bot searching for venues (string, read-only).
+    VenueSearchBotUsername => "venue_search_bot_username",
+    /// Whether the current user is online (boolean).
+    Online => "online",
+    /// Whether Markdown is parsed in the text of all the sent messages
+    /// (boolean).
+    AlwaysParseMarkdown => "always_parse_markdown",
+    /// Whether the updates of the chats and the messages not needed in the
+    /// background are skipped (boolean).
+    IgnoreBackgroundUpdates => "ignore_background_updates",
+    /// Whether the original names of the sent files are replaced (boolean).
+    IgnoreFileNames => "ignore_file_names",
+    /// Whether the inline thumbnails are not downloaded (boolean).
+    IgnoreInlineThumbnails => "ignore_inline_thumbnails",
+    /// Whether the content restrictions of the platform are ignored
+    /// (boolean).
+    IgnorePlatformRestrictions => "ignore_platform_restrictions",
+    /// Whether the animated emoji are disabled (boolean).
+    DisableAnimatedEmoji => "disable_animated_emoji",
+    /// Whether the notifications about the contacts joining Telegram are
+    /// disabled (boolean).
+    DisableContactRegisteredNotifications => "disable_contact_registered_notifications",
+    /// Whether the network statistics are not collected (boolean).
+    DisableNetworkStatistics => "disable_network_statistics",
+    /// Whether the network statistics are not saved between the restarts
+    /// (boolean).
+    DisablePersistentNetworkStatistics => "disable_persistent_network_statistics",
+    /// Whether the top chats are not tracked (boolean).
+    DisableTopChats => "disable_top_chats",
+    /// Whether the device is an emulator (boolean).
+    IsEmulator => "is_emulator",
+    /// Whether IPv6 is preferred over IPv4 (boolean).
+    PreferIpv6 => "prefer_ipv6",
+    /// Whether the Perfect Forward Secrecy is used for the cloud connections
+    /// (boolean).
+    UsePfs => "use_pfs",
+    /// Whether the quick acknowledgement mechanism is used (boolean).
+    UseQuickAck => "use_quick_ack",
+    /// Whether the storage optimizer runs automatically (boolean).
+    UseStorageOptimizer => "use_storage_optimizer",
+    /// The delay before unloading the unused messages, in seconds (integer).
+    MessageUnloadDelay => "message_unload_delay",
+    /// The maximum number of notification groups shown at once (integer).
+    NotificationGroupCountMax => "notification_group_count_max",
+    /// The maximum number of notifications shown in a group (integer).
+    NotificationGroupSizeMax => "notification_group_size_max",
+    /// The path to the database of the language packs (string).
+    LanguagePackDatabasePath => "language_pack_database_path",
+    /// The identifier of the current language pack (string).
+    LanguagePackId => "language_pack_id",
+    /// The localization target of the language packs (string).
+    LocalizationTarget => "localization_target",
+}
+
+impl AsRef<str> for OptionName {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for OptionName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The options of a client, as last reported by TDLib with `updateOption`.
+///
+/// TDLib sends all the options set when the client starts, and then every
+/// change, so the cache dispenses with the `getOption` calls. The names and
+/// the meaning of the options are listed in the
+/// [TDLib documentation](https://core.telegram.org/tdlib/options).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OptionsCache {
+    values: HashMap<String, OptionValue>,
+    /// The difference between the Unix time of the server and the one of
+    /// the device, when the `unix_time` option was received.
+    unix_time_offset: Option<i64>,
+}
+
+impl OptionsCache {
+    /// Record the new value of an option.
+    pub(crate) fn set(&mut self, name: &str, value: OptionValue) {
+        if name == OptionName::UnixTime.as_str() {
+            if let OptionValue::Integer(unix_time) = &value {
+                self.unix_time_offset = Some(unix_time.value - local_unix_time());
+            }
+        }
+        match value {
+            OptionValue::Empty => {
+                self.values.remove(name);
+            }
+            value => {
+                self.values.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    /// The value of the option `name`, or `None` if it's not set.
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&OptionValue> {
+        self.values.get(name.as_ref())
+    }
+
+    /// The value of the boolean option `name`, or `None` if it's not set or
+    /// it's not a boolean.
+    pub fn get_bool(&self, name: impl AsRef<str>) -> Option<bool> {
+        match self.get(name)? {
+            OptionValue::Boolean(option) => Some(option.value),
+            _ => None,
+        }
+    }
+
+    /// The value of the integer option `name`, or `None` if it's not set or
+    /// it's not an integer.
+    pub fn get_integer(&self, name: impl AsRef<str>) -> Option<i64> {
+        match self.get(name)? {
+            OptionValue::Integer(option) => Some(option.value),
+            _ => None,
+        }
+    }
+
+    /// The value of the string option `name`, or `None` if it's not set or
+    /// it's not a string.
+    pub fn get_string(&self, name: impl AsRef<str>) -> Option<&str> {
+        match self.get(name)? {
+            OptionValue::String(option) => Some(&*option.value),
+            _ => None,
+        }
+    }
+
+    /// The names of the options that are set.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// The TDLib version, e.g. `"1.8.60"`.
+    pub fn version(&self) -> Option<&str> {
+        self.get_string(OptionName::Version)
+    }
+
+    /// The commit of the TDLib sources.
+    pub fn commit_hash(&self) -> Option<&str> {
+        self.get_string(OptionName::CommitHash)
+    }
+
+    /// The identifier of the current user, once authorized.
+    pub fn my_id(&self) -> Option<i64> {
+        self.get_integer(OptionName::MyId)
+    }
+
+    /// The Unix time at which the current user was authorized.
+    pub fn authorization_date(&self) -> Option<i64> {
+        self.get_integer(OptionName::AuthorizationDate)
+    }
+
+    /// The Unix time of the server, when it was last reported.
+    pub fn unix_time(&self) -> Option<i64> {
+        self.get_integer(OptionName::UnixTime)
+    }
+
+    /// The difference in seconds between the Unix time of the server and the
+    /// one of the device, to add to the local time to get the server time.
+    pub fn unix_time_offset(&self) -> Option<i64> {
+        self.unix_time_offset
+    }
+
+    /// The offset in seconds of the local time zone of the device from UTC.
+    pub fn utc_time_offset(&self) -> Option<i64> {
+        self.get_integer(OptionName::UtcTimeOffset)
+    }
+
+    /// Whether the current user has Telegram Premium.
+    pub fn is_premium(&self) -> Option<bool> {
+        self.get_bool(OptionName::IsPremium)
+    }
+
+    /// Whether the client is connected to the test data centers.
+    pub fn test_mode(&self) -> Option<bool> {
+        self.get_bool(OptionName::TestMode)
+    }
+
+    /// The maximum length of the text of a message.
+    pub fn message_text_length_max(&self) -> Option<i64> {
+        self.get_integer(OptionName::MessageTextLengthMax)
+    }
+
+    /// The maximum length of the caption of a media message.
+    pub fn message_caption_length_max(&self) -> Option<i64> {
+        self.get_integer(OptionName::MessageCaptionLengthMax)
+    }
+
+    /// The maximum number of messages forwarded at once.
+    pub fn forwarded_message_count_max(&self) -> Option<i64> {
+        self.get_integer(OptionName::ForwardedMessageCountMax)
+    }
+
+    /// The maximum number of members of a basic group.
+    pub fn basic_group_size_max(&self) -> Option<i64> {
+        self.get_integer(OptionName::BasicGroupSizeMax)
+    }
+
+    /// The maximum number of members of a supergroup.
+    pub fn supergroup_size_max(&self) -> Option<i64> {
+        self.get_integer(OptionName::SupergroupSizeMax)
+    }
+
+    /// The URL of the Telegram links, e.g. `"https://t.me/"`.
+    pub fn t_me_url(&self) -> Option<&str> {
+        self.get_string(OptionName::TMeUrl)
+    }
+}
+
+/// The value of the boolean option `name`, or `None` if it's empty or not a
+/// boolean.
+pub async fn get_option_bool(
+    name: impl AsRef<str>,
+    client_id: i32,
+) -> Result<Option<bool>, TdError> {
+    match functions::get_option(name.as_ref().to_string(), client_id).await? {
+        OptionValue::Boolean(option) => Ok(Some(option.value)),
+        _ => Ok(None),
+    }
+}
+
+/// The value of the integer option `name`, or `None` if it's empty or not an
+/// integer.
+pub async fn get_option_integer(
+    name: impl AsRef<str>,
+    client_id: i32,
+) -> Result<Option<i64>, TdError> {
+    match functions::get_option(name.as_ref().to_string(), client_id).await? {
+        OptionValue::Integer(option) => Ok(Some(option.value)),
+        _ => Ok(None),
+    }
+}
+
+/// The value of the string option `name`, or `None` if it's empty or not a
+/// string.
+pub async fn get_option_string(
+    name: impl AsRef<str>,
+    client_id: i32,
+) -> Result<Option<String>, TdError> {
+    match functions::get_option(name.as_ref().to_string(), client_id).await? {
+        OptionValue::String(option) => Ok(Some(option.value.to_string())),
+        _ => Ok(None),
+    }
+}
+
+/// Set the boolean option `name`, e.g.
+/// `set_option_bool(OptionName::IgnoreBackgroundUpdates, true, client_id)`.
+pub async fn set_option_bool(
+    name: impl AsRef<str>,
+    value: bool,
+    client_id: i32,
+) -> Result<(), TdError> {
+    let value = OptionValue::Boolean(types::OptionValueBoolean { value });
+    functions::set_option(name.as_ref().to_string(), Some(value), client_id).await
+}
+
+/// Set the integer option `name`.
+pub async fn set_option_integer(
+    name: impl AsRef<str>,
+    value: i64,
+    client_id: i32,
+) -> Result<(), TdError> {
+    let value = OptionValue::Integer(types::OptionValueInteger { value });
+    functions::set_option(name.as_ref().to_string(), Some(value), client_id).await
+}
+
+/// Set the string option `name`.
+pub async fn set_option_string(
+    name: impl AsRef<str>,
+    value: impl Into<TdString>,
+    client_id: i32,
+) -> Result<(), TdError> {
+    let value = OptionValue::String(types::OptionValueString {
+        value: value.into(),
+    });
+    functions::set_option(name.as_ref().to_string(), Some(value), client_id).await
+}
+
+/// Reset the option `name` to its default value.
+pub async fn reset_option(name: impl AsRef<str>, client_id: i32) -> Result<(), TdError> {
+    functions::set_option(name.as_ref().to_string(), None, client_id).await
+}
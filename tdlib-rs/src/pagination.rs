@@ -0,0 +1,72 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The [`Paginated`] stream, flattening the pages of the functions returning
+//! their results in chunks.
+use crate::TdError;
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// The future of a page.
+pub(crate) type NextPage<T> = Pin<Box<dyn Future<Output = Result<Page<T>, TdError>> + Send>>;
+
+/// A page of results, and the future of the following one, if any.
+pub(crate) struct Page<T> {
+    pub(crate) items: Vec<T>,
+    pub(crate) next: Option<NextPage<T>>,
+}
+
+/// A stream of the results of a function returning them in pages, e.g. the
+/// messages returned by `getChatHistory`.
+///
+/// The next page is only requested once the results of the previous one have
+/// been consumed. The stream ends after the first error.
+pub struct Paginated<T> {
+    items: VecDeque<T>,
+    next: Option<NextPage<T>>,
+}
+
+impl<T> Paginated<T> {
+    /// A stream of the pages starting with `first`.
+    pub(crate) fn new(first: NextPage<T>) -> Self {
+        Self {
+            items: VecDeque::new(),
+            next: Some(first),
+        }
+    }
+}
+
+impl<T> Unpin for Paginated<T> {}
+
+impl<T> Stream for Paginated<T> {
+    type Item = Result<T, TdError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(item) = self.items.pop_front() {
+                return Poll::Ready(Some(Ok(item)));
+            }
+            let Some(next) = &mut self.next else {
+                return Poll::Ready(None);
+            };
+            match ready!(next.as_mut().poll(cx)) {
+                Ok(page) => {
+                    self.items.extend(page.items);
+                    self.next = page.next;
+                }
+                Err(error) => {
+                    self.next = None;
+                    return Poll::Ready(Some(Err(error)));
+                }
+            }
+        }
+    }
+}
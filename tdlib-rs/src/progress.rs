@@ -0,0 +1,99 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The [`Progress`] of the long-running operations, e.g. the transfers of
+//! the files, the loading of the histories of the chats or the optimization
+//! of the storage, so that they can be rendered by the same progress bar.
+use crate::export::ExportProgress;
+use crate::types;
+
+/// What the amounts of a [`Progress`] count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProgressUnit {
+    /// The bytes of a file.
+    Bytes,
+    /// The messages of a chat.
+    Messages,
+    /// The chats, e.g. of an export.
+    Chats,
+    /// The steps of an operation made of several requests.
+    Steps,
+}
+
+/// The progress of a long-running operation, reported to the callbacks of
+/// the operations, e.g. [`Download::completed_with_progress`] or
+/// [`StorageOptimizer::run_with_progress`].
+///
+/// [`Download::completed_with_progress`]: crate::files::Download::completed_with_progress
+/// [`StorageOptimizer::run_with_progress`]: crate::storage::StorageOptimizer::run_with_progress
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Progress {
+    /// The amount done so far.
+    pub done: i64,
+    /// The total amount, if known.
+    pub total: Option<i64>,
+    /// What the amounts count.
+    pub unit: ProgressUnit,
+}
+
+impl Progress {
+    /// The progress of `done` out of `total`.
+    pub fn new(done: i64, total: Option<i64>, unit: ProgressUnit) -> Self {
+        Self { done, total, unit }
+    }
+
+    /// The progress of the download of `file`.
+    pub fn download(file: &types::File) -> Self {
+        Self::new(
+            file.local.downloaded_size,
+            file_size(file),
+            ProgressUnit::Bytes,
+        )
+    }
+
+    /// The progress of the upload of `file`.
+    pub fn upload(file: &types::File) -> Self {
+        Self::new(
+            file.remote.uploaded_size,
+            file_size(file),
+            ProgressUnit::Bytes,
+        )
+    }
+
+    /// The fraction done, from 0 to 1, if the total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(total) if total > 0 => Some((self.done as f64 / total as f64).clamp(0.0, 1.0)),
+            Some(_) => Some(1.0),
+            None => None,
+        }
+    }
+
+    /// Whether the total is known and done.
+    pub fn is_complete(&self) -> bool {
+        self.total.is_some_and(|total| self.done >= total)
+    }
+}
+
+/// Converts to the progress of the exported chats.
+impl From<&ExportProgress> for Progress {
+    fn from(progress: &ExportProgress) -> Self {
+        Self::new(
+            progress.exported_chats as i64,
+            Some(progress.chat_count as i64),
+            ProgressUnit::Chats,
+        )
+    }
+}
+
+/// The size of `file`, or its expected size, if known.
+fn file_size(file: &types::File) -> Option<i64> {
+    [file.size, file.expected_size]
+        .into_iter()
+        .find(|&size| size > 0)
+}
@@ -0,0 +1,538 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The proxies of a client, added with `addProxy` and stored by TDLib, of
+//! which at most one is enabled at once.
+//!
+//! [`ProxyManager`] keeps a pool of proxies, and fails over to the fastest
+//! reachable one with [`ProxyManager::enable_fastest`], e.g. when the enabled
+//! one stops working.
+//!
+//! The proxies shared as links, e.g. `tg://proxy?server=...&port=...&secret=...`,
+//! are parsed with [`ProxyLink::parse`].
+use crate::enums::{self, ProxyType};
+use crate::{functions, types, TdError, TdString};
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::Duration;
+
+/// A pending `pingProxy` request.
+type PingRequest = Pin<Box<dyn Future<Output = Result<Duration, TdError>> + Send>>;
+
+/// The hosts of the `https://` links, e.g. `https://t.me/proxy?...`.
+const LINK_HOSTS: [&str; 3] = ["t.me", "telegram.me", "telegram.dog"];
+
+/// The type of a SOCKS5 proxy, authenticated with `username` and `password`
+/// unless they're empty.
+pub fn socks5(username: impl Into<TdString>, password: impl Into<TdString>) -> ProxyType {
+    ProxyType::Socks5(types::ProxyTypeSocks5 {
+        username: username.into(),
+        password: password.into(),
+    })
+}
+
+/// The type of an HTTP proxy, authenticated with `username` and `password`
+/// unless they're empty, and only used for the HTTP requests if `http_only`.
+pub fn http(
+    username: impl Into<TdString>,
+    password: impl Into<TdString>,
+    http_only: bool,
+) -> ProxyType {
+    ProxyType::Http(types::ProxyTypeHttp {
+        username: username.into(),
+        password: password.into(),
+        http_only,
+    })
+}
+
+/// The type of an MTProto proxy, with its `secret` in hexadecimal or in
+/// base64.
+pub fn mtproto(secret: impl Into<TdString>) -> ProxyType {
+    ProxyType::Mtproto(types::ProxyTypeMtproto {
+        secret: secret.into(),
+    })
+}
+
+/// A proxy shared as a link, e.g. `tg://proxy?server=...&port=...&secret=...`
+/// or `https://t.me/socks?server=...&port=...&user=...&pass=...`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProxyLink {
+    /// The address of the proxy.
+    pub server: TdString,
+    /// The port of the proxy.
+    pub port: i32,
+    /// The type of the proxy, MTProto for the `proxy` links and SOCKS5 for
+    /// the `socks` ones.
+    pub r#type: ProxyType,
+}
+
+impl ProxyLink {
+    /// Parse a `tg://proxy`, `tg://socks`, `https://t.me/proxy` or
+    /// `https://t.me/socks` link, failing like TDLib for an invalid one.
+    // The server is a `TdString`, which may be a `String`
+    #[allow(clippy::useless_conversion)]
+    pub fn parse(link: &str) -> Result<Self, TdError> {
+        let (kind, query) =
+            split_link(link.trim()).ok_or_else(|| TdError::invalid("Unsupported proxy link"))?;
+        let mut server = None;
+        let mut port = None;
+        let mut secret = None;
+        let mut user = String::new();
+        let mut pass = String::new();
+        for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            let value = percent_decode(value)
+                .ok_or_else(|| TdError::invalid("Invalid proxy link encoding"))?;
+            match name {
+                "server" => server = Some(value),
+                "port" => port = Some(value),
+                "secret" => secret = Some(value),
+                "user" => user = value,
+                "pass" => pass = value,
+                _ => {}
+            }
+        }
+        let server = server
+            .filter(|server| !server.is_empty())
+            .ok_or_else(|| TdError::invalid("Wrong server name"))?;
+        let port = port
+            .and_then(|port| port.parse::<u16>().ok())
+            .filter(|port| *port != 0)
+            .ok_or_else(|| TdError::invalid("Wrong port number"))?;
+        let r#type = match kind {
+            LinkKind::Proxy => {
+                let secret = secret
+                    .filter(|secret| !secret.is_empty())
+                    .ok_or_else(|| TdError::invalid("Wrong secret"))?;
+                mtproto(secret)
+            }
+            LinkKind::Socks => socks5(user, pass),
+        };
+        Ok(Self {
+            server: server.into(),
+            port: port.into(),
+            r#type,
+        })
+    }
+}
+
+/// The kind of a proxy link, given by its path.
+#[derive(Clone, Copy)]
+enum LinkKind {
+    Proxy,
+    Socks,
+}
+
+/// The kind and the query of a proxy link, if it's one.
+fn split_link(link: &str) -> Option<(LinkKind, &str)> {
+    let lowercase = link.to_ascii_lowercase();
+    let path = if let Some(path) = lowercase.strip_prefix("tg://") {
+        path
+    } else {
+        let url = ["https://", "http://"]
+            .iter()
+            .find_map(|scheme| lowercase.strip_prefix(scheme))
+            .unwrap_or(&lowercase);
+        let (host, path) = url.split_once('/')?;
+        if !LINK_HOSTS.contains(&host) {
+            return None;
+        }
+        path
+    };
+    let (kind, name) = if path.starts_with("proxy") {
+        (LinkKind::Proxy, "proxy")
+    } else if path.starts_with("socks") {
+        (LinkKind::Socks, "socks")
+    } else {
+        return None;
+    };
+    // The query is taken from the original link, to keep the case of the
+    // secrets and the passwords
+    let rest = &link[link.len() - path.len() + name.len()..];
+    let query = match rest.strip_prefix('?') {
+        Some(query) => query,
+        None if rest.is_empty() => "",
+        None => return None,
+    };
+    Some((kind, query.split('#').next().unwrap_or_default()))
+}
+
+/// Decode the percent-encoded `value` of a query, if valid UTF-8.
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail
+                .get(..2)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+            let hex = std::str::from_utf8(hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// The time of the response of a Telegram server, from the generated types.
+#[cfg(feature = "duration")]
+fn seconds_duration(seconds: Duration) -> Duration {
+    seconds
+}
+
+/// The time of the response of a Telegram server, from the generated types.
+#[cfg(not(feature = "duration"))]
+fn seconds_duration(seconds: f64) -> Duration {
+    Duration::try_from_secs_f64(seconds).unwrap_or_default()
+}
+
+/// The time of the response of a Telegram server, returned by `pingProxy`.
+fn latency(seconds: enums::Seconds) -> Result<Duration, TdError> {
+    match seconds {
+        enums::Seconds::Seconds(seconds) => Ok(seconds_duration(seconds.seconds)),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::Seconds::Unrecognized => Err(TdError::unrecognized("Seconds")),
+    }
+}
+
+/// The proxies of a client, e.g.
+///
+/// ```ignore
+/// let proxies = ProxyManager::new(client.id());
+/// proxies.add("proxy1.example.com", 1080, proxy::socks5("", "")).await?;
+/// proxies.add("proxy2.example.com", 443, proxy::mtproto(secret)).await?;
+/// if let Some((proxy, latency)) = proxies.enable_fastest().await? {
+///     println!("Using {}:{} ({latency:?})", proxy.server, proxy.port);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ProxyManager {
+    client_id: i32,
+}
+
+impl ProxyManager {
+    /// The manager of the proxies of `client_id`.
+    pub fn new(client_id: i32) -> Self {
+        Self { client_id }
+    }
+
+    /// The proxies of the pool, including the enabled one.
+    pub async fn proxies(&self) -> Result<Vec<types::Proxy>, TdError> {
+        match functions::get_proxies(self.client_id).await? {
+            enums::Proxies::Proxies(proxies) => Ok(proxies.proxies),
+            #[cfg(feature = "unrecognized-variants")]
+            enums::Proxies::Unrecognized => Err(TdError::unrecognized("Proxies")),
+        }
+    }
+
+    /// The enabled proxy, if any.
+    pub async fn enabled(&self) -> Result<Option<types::Proxy>, TdError> {
+        let proxies = self.proxies().await?;
+        Ok(proxies.into_iter().find(|proxy| proxy.is_enabled))
+    }
+
+    /// Add a proxy to the pool, without enabling it, returning the one
+    /// already added with the same server, port and type if any.
+    pub async fn add(
+        &self,
+        server: impl Into<TdString>,
+        port: i32,
+        r#type: ProxyType,
+    ) -> Result<types::Proxy, TdError> {
+        let server = server.into();
+        let proxies = self.proxies().await?;
+        let existing = proxies
+            .into_iter()
+            .find(|proxy| proxy.server == server && proxy.port == port && proxy.r#type == r#type);
+        if let Some(proxy) = existing {
+            return Ok(proxy);
+        }
+        match functions::add_proxy(server, port, false, r#type, self.client_id).await? {
+            enums::Proxy::Proxy(proxy) => Ok(proxy),
+            #[cfg(feature = "unrecognized-variants")]
+            enums::Proxy::Unrecognized => Err(TdError::unrecognized("Proxy")),
+        }
+    }
+
+    /// Remove the proxy `proxy_id` from the pool, disabling it if enabled.
+    pub async fn remove(&self, proxy_id: i32) -> Result<(), TdError> {
+        functions::remove_proxy(proxy_id, self.client_id).await
+    }
+
+    /// Enable the proxy `proxy_id`, disabling the enabled one.
+    pub async fn enable(&self, proxy_id: i32) -> Result<(), TdError> {
+        functions::enable_proxy(proxy_id, self.client_id).await
+    }
+
+    /// Disable the enabled proxy, connecting directly to Telegram.
+    pub async fn disable(&self) -> Result<(), TdError> {
+        functions::disable_proxy(self.client_id).await
+    }
+
+    /// The time of the response of a Telegram server through the proxy
+    /// `proxy_id`, or without a proxy if 0.
+    pub async fn ping(&self, proxy_id: i32) -> Result<Duration, TdError> {
+        functions::ping_proxy(proxy_id, self.client_id)
+            .await
+            .and_then(latency)
+    }
+
+    /// Test a proxy before adding it, connecting to the datacenter `dc_id`
+    /// through it within `timeout`.
+    pub async fn test(
+        &self,
+        server: impl Into<TdString>,
+        port: i32,
+        r#type: ProxyType,
+        dc_id: i32,
+        timeout: Duration,
+    ) -> Result<(), TdError> {
+        let timeout = timeout.as_secs_f64();
+        functions::test_proxy(server, port, r#type, dc_id, timeout, self.client_id).await
+    }
+
+    /// Add the proxy shared as `link`, without enabling it.
+    pub async fn add_link(&self, link: &str) -> Result<types::Proxy, TdError> {
+        let link = ProxyLink::parse(link)?;
+        self.add(link.server, link.port, link.r#type).await
+    }
+
+    /// Ping the proxies of the pool concurrently, returning the reachable
+    /// ones with their latency, from the fastest to the slowest.
+    pub async fn ping_all(&self) -> Result<Vec<(types::Proxy, Duration)>, TdError> {
+        let proxies = self.proxies().await?;
+        Ok(self.ping_concurrently(proxies).await)
+    }
+
+    /// Add the proxies shared as `links` to the pool, without enabling them,
+    /// and ping them concurrently, returning the reachable ones with their
+    /// latency, from the fastest to the slowest.
+    pub async fn benchmark<'a>(
+        &self,
+        links: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<(types::Proxy, Duration)>, TdError> {
+        let mut proxies = Vec::new();
+        for link in links {
+            proxies.push(self.add_link(link).await?);
+        }
+        Ok(self.ping_concurrently(proxies).await)
+    }
+
+    async fn ping_concurrently(&self, proxies: Vec<types::Proxy>) -> Vec<(types::Proxy, Duration)> {
+        let client_id = self.client_id;
+        let mut requests: Vec<Option<PingRequest>> = proxies
+            .iter()
+            .map(|proxy| {
+                let request = functions::ping_proxy(proxy.id, client_id);
+                Some(Box::pin(async move { request.await.and_then(latency) }) as PingRequest)
+            })
+            .collect();
+        let mut latencies = vec![None; proxies.len()];
+        poll_fn(|cx| {
+            let mut is_pending = false;
+            for (request, latency) in requests.iter_mut().zip(&mut latencies) {
+                let Some(future) = request else { continue };
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(result) => {
+                        // The unreachable proxies fail to be pinged
+                        *latency = result.ok();
+                        *request = None;
+                    }
+                    Poll::Pending => is_pending = true,
+                }
+            }
+            if is_pending {
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await;
+        let mut reachable: Vec<_> = proxies
+            .into_iter()
+            .zip(latencies)
+            .filter_map(|(proxy, latency)| Some((proxy, latency?)))
+            .collect();
+        reachable.sort_by_key(|(_, latency)| *latency);
+        reachable
+    }
+
+    /// Enable the fastest reachable proxy of the pool, returning it with its
+    /// latency, or leave the enabled proxy as is and return none if none is
+    /// reachable.
+    pub async fn enable_fastest(&self) -> Result<Option<(types::Proxy, Duration)>, TdError> {
+        let Some((mut proxy, latency)) = self.ping_all().await?.into_iter().next() else {
+            return Ok(None);
+        };
+        if !proxy.is_enabled {
+            self.enable(proxy.id).await?;
+            proxy.is_enabled = true;
+        }
+        Ok(Some((proxy, latency)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The server is a `TdString`, which may be a `String`
+    #[allow(clippy::useless_conversion)]
+    fn link(server: &str, port: i32, r#type: ProxyType) -> ProxyLink {
+        ProxyLink {
+            server: server.to_owned().into(),
+            port,
+            r#type,
+        }
+    }
+
+    fn error(link: &str) -> String {
+        ProxyLink::parse(link).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn check_parse_tg() {
+        assert_eq!(
+            ProxyLink::parse("tg://proxy?server=example.com&port=443&secret=dd00AB").unwrap(),
+            link("example.com", 443, mtproto("dd00AB"))
+        );
+        assert_eq!(
+            ProxyLink::parse("tg://socks?server=example.com&port=1080").unwrap(),
+            link("example.com", 1080, socks5("", ""))
+        );
+    }
+
+    #[test]
+    fn check_parse_https() {
+        assert_eq!(
+            ProxyLink::parse("https://t.me/proxy?server=example.com&port=443&secret=ab").unwrap(),
+            link("example.com", 443, mtproto("ab"))
+        );
+        assert_eq!(
+            ProxyLink::parse("telegram.me/socks?server=example.com&port=1080").unwrap(),
+            link("example.com", 1080, socks5("", ""))
+        );
+        assert_eq!(
+            error("https://example.com/proxy?server=example.com&port=443&secret=ab"),
+            "TDLib error 400: Unsupported proxy link"
+        );
+    }
+
+    #[test]
+    fn check_parse_case() {
+        // Only the scheme, the host and the path are case-insensitive
+        assert_eq!(
+            ProxyLink::parse("HTTPS://T.Me/Proxy?server=Example.com&port=443&secret=AbC").unwrap(),
+            link("Example.com", 443, mtproto("AbC"))
+        );
+        assert_eq!(
+            ProxyLink::parse("TG://SOCKS?server=example.com&port=1080&user=Me&pass=PaSs").unwrap(),
+            link("example.com", 1080, socks5("Me", "PaSs"))
+        );
+    }
+
+    #[test]
+    fn check_parse_port() {
+        let wrong_port = "TDLib error 400: Wrong port number";
+        assert_eq!(error("tg://socks?server=example.com"), wrong_port);
+        assert_eq!(error("tg://socks?server=example.com&port="), wrong_port);
+        assert_eq!(error("tg://socks?server=example.com&port=0"), wrong_port);
+        assert_eq!(
+            error("tg://socks?server=example.com&port=65536"),
+            wrong_port
+        );
+        assert_eq!(error("tg://socks?server=example.com&port=-1"), wrong_port);
+    }
+
+    #[test]
+    fn check_parse_required() {
+        assert_eq!(
+            error("tg://socks?port=1080"),
+            "TDLib error 400: Wrong server name"
+        );
+        assert_eq!(
+            error("tg://proxy?server=example.com&port=443&secret="),
+            "TDLib error 400: Wrong secret"
+        );
+        assert_eq!(
+            error("tg://proxyserver=example.com&port=443"),
+            "TDLib error 400: Unsupported proxy link"
+        );
+    }
+
+    #[test]
+    fn check_parse_socks_credentials() {
+        assert_eq!(
+            ProxyLink::parse(
+                "https://t.me/socks?server=example.com&port=1080&user=me&pass=p%40ss%26"
+            )
+            .unwrap(),
+            link("example.com", 1080, socks5("me", "p@ss&"))
+        );
+    }
+
+    #[test]
+    fn check_parse_fragment() {
+        assert_eq!(
+            ProxyLink::parse("tg://socks?server=example.com&port=1080#user=me").unwrap(),
+            link("example.com", 1080, socks5("", ""))
+        );
+        assert_eq!(
+            ProxyLink::parse("tg://proxy?server=example.com&port=443&secret=ab#fragment").unwrap(),
+            link("example.com", 443, mtproto("ab"))
+        );
+    }
+
+    #[test]
+    fn check_parse_encoding() {
+        let invalid = "TDLib error 400: Invalid proxy link encoding";
+        assert_eq!(error("tg://socks?server=%zz&port=1080"), invalid);
+        assert_eq!(
+            error("tg://socks?server=example.com&port=1080&pass=%4"),
+            invalid
+        );
+        assert_eq!(error("tg://socks?server=%e9&port=1080"), invalid);
+    }
+
+    #[test]
+    fn check_percent_decode() {
+        assert_eq!(percent_decode("").as_deref(), Some(""));
+        assert_eq!(percent_decode("a%20b").as_deref(), Some("a b"));
+        assert_eq!(percent_decode("%E2%82%ac").as_deref(), Some("€"));
+        assert_eq!(percent_decode("a+b").as_deref(), Some("a+b"));
+        assert_eq!(percent_decode("%"), None);
+        assert_eq!(percent_decode("%2"), None);
+        assert_eq!(percent_decode("%g0"), None);
+        // A truncated UTF-8 sequence
+        assert_eq!(percent_decode("%E2%82"), None);
+    }
+
+    #[test]
+    fn check_split_link() {
+        let query = |link| split_link(link).map(|(_, query)| query);
+        assert_eq!(query("tg://proxy"), Some(""));
+        assert_eq!(query("tg://proxy?a=B#c"), Some("a=B"));
+        assert_eq!(query("http://telegram.dog/socks?a=b"), Some("a=b"));
+        assert!(matches!(
+            split_link("t.me/proxy?"),
+            Some((LinkKind::Proxy, ""))
+        ));
+        assert!(matches!(
+            split_link("t.me/socks"),
+            Some((LinkKind::Socks, ""))
+        ));
+        assert_eq!(query("tg://proxies?a=b"), None);
+        assert_eq!(query("tg://resolve?domain=a"), None);
+        assert_eq!(query("https://t.me"), None);
+        assert_eq!(query("https://t.me.example.com/proxy"), None);
+    }
+}
@@ -0,0 +1,559 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The queue of the outgoing requests of every client, holding back the
+//! requests of lower [`Priority`] while the ones of higher priority wait for
+//! their response, and the requests over the limit set with
+//! [`set_max_in_flight_requests`](crate::set_max_in_flight_requests).
+//!
+//! The queued requests aren't known by the observer until they are sent, so
+//! that they don't count against its capacity and their expiry starts once
+//! they reach TDLib.
+//!
+//! The identical idempotent requests (e.g. two `getChat` of the same chat)
+//! sent shortly after each other share the response of the first one, and
+//! the requests are written to the transport in batches by a single caller
+//! at a time, while the other callers only append them to the batch.
+use crate::observer::{current_timeout, now};
+use crate::{transport, OBSERVER};
+use futures_channel::oneshot;
+use once_cell::sync::Lazy;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The number of priorities.
+const PRIORITIES: usize = 3;
+
+/// How long after an idempotent request is sent the identical requests
+/// share its response, if it's not answered yet.
+const COALESCE_WINDOW: Duration = Duration::from_millis(100);
+
+/// The requests which only read the state of TDLib, so that the identical
+/// ones can share the same response.
+const IDEMPOTENT_REQUESTS: [&str; 12] = [
+    "getMe",
+    "getUser",
+    "getUserFullInfo",
+    "getChat",
+    "getBasicGroup",
+    "getBasicGroupFullInfo",
+    "getSupergroup",
+    "getSupergroupFullInfo",
+    "getSecretChat",
+    "getMessage",
+    "getFile",
+    "getOption",
+];
+
+/// The priority of the requests, set with [`with_priority`].
+///
+/// The requests of a client are held back while the ones of higher priority
+/// wait for their response, so that e.g. the authorization and the requests
+/// rendering the UI aren't queued by TDLib behind hundreds of background
+/// requests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Background work, e.g. loading the histories of the chats, sent when
+    /// the client has no other request waiting for its response.
+    Low,
+    /// The default priority.
+    #[default]
+    Normal,
+    /// The requests the user is waiting for, only held back by the limit of
+    /// [`set_max_in_flight_requests`](crate::set_max_in_flight_requests).
+    High,
+}
+
+thread_local! {
+    /// The priority of the requests sent by the future being polled.
+    static CURRENT_PRIORITY: Cell<Priority> = const { Cell::new(Priority::Normal) };
+}
+
+/// Send the requests of `future` with `priority`, e.g.
+///
+/// ```ignore
+/// let me = with_priority(Priority::High, functions::get_me(client.id())).await?;
+/// ```
+pub fn with_priority<F: Future>(priority: Priority, future: F) -> WithPriority<F> {
+    WithPriority {
+        priority,
+        future: Box::pin(future),
+    }
+}
+
+/// The future returned by [`with_priority`].
+pub struct WithPriority<F> {
+    priority: Priority,
+    future: Pin<Box<F>>,
+}
+
+/// Restores the priority of the outer future once the inner one is polled.
+struct RestorePriority(Priority);
+
+impl Drop for RestorePriority {
+    fn drop(&mut self) {
+        CURRENT_PRIORITY.with(|priority| priority.set(self.0));
+    }
+}
+
+impl<F: Future> Future for WithPriority<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let previous = CURRENT_PRIORITY.with(|priority| priority.replace(self.priority));
+        let _restore = RestorePriority(previous);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// A request held back, with the sender of its response.
+struct Queued {
+    extra: u32,
+    request: String,
+    sender: oneshot::Sender<String>,
+    /// The timeout set with [`with_timeout`](crate::with_timeout), if any.
+    timeout: Option<Duration>,
+}
+
+/// The requests of a client, by priority.
+#[derive(Default)]
+struct ClientQueue {
+    /// The number of requests waiting for their response.
+    in_flight: [usize; PRIORITIES],
+    /// The requests held back.
+    queued: [VecDeque<Queued>; PRIORITIES],
+}
+
+impl ClientQueue {
+    /// Whether the requests of `priority` must wait, for the ones of higher
+    /// priority or because `max_in_flight` requests are waiting for their
+    /// response.
+    fn is_blocked(&self, priority: usize, max_in_flight: Option<usize>) -> bool {
+        let in_flight: usize = self.in_flight.iter().sum();
+        max_in_flight.is_some_and(|max| in_flight >= max)
+            || (priority + 1..PRIORITIES)
+                .any(|higher| self.in_flight[higher] > 0 || !self.queued[higher].is_empty())
+    }
+
+    fn is_idle(&self) -> bool {
+        self.in_flight.iter().all(|&count| count == 0) && self.queued.iter().all(VecDeque::is_empty)
+    }
+
+    /// Take the requests which can be sent, from the highest priority,
+    /// letting the observer know about them.
+    fn release(&mut self, max_in_flight: Option<usize>) -> Vec<String> {
+        let mut released = Vec::new();
+        for priority in (0..PRIORITIES).rev() {
+            while !self.is_blocked(priority, max_in_flight) {
+                let Some(queued) = self.queued[priority].pop_front() else {
+                    break;
+                };
+                self.in_flight[priority] += 1;
+                OBSERVER.register(queued.extra, queued.sender, queued.timeout);
+                released.push(queued.request);
+            }
+        }
+        released
+    }
+}
+
+/// A request waiting for its response.
+struct InFlight {
+    client_id: i32,
+    priority: usize,
+    /// The JSON of the request, if it's idempotent.
+    coalesced: Option<String>,
+    /// The `@extra` of the identical requests sharing its response.
+    followers: Vec<u32>,
+}
+
+/// An idempotent request waiting for its response, which the identical
+/// requests can share.
+struct Coalescable {
+    extra: u32,
+    sent: Duration,
+}
+
+#[derive(Default)]
+struct Queues {
+    clients: HashMap<i32, ClientQueue>,
+    /// The requests by their `@extra`.
+    requests: HashMap<u32, InFlight>,
+    /// The idempotent requests by their client and JSON.
+    coalescable: HashMap<(i32, String), Coalescable>,
+    /// The maximum number of requests of a client waiting for their response.
+    max_in_flight: Option<usize>,
+}
+
+static QUEUES: Lazy<Mutex<Queues>> = Lazy::new(Mutex::default);
+
+/// The requests to write to the transport.
+#[derive(Default)]
+struct Outbox {
+    requests: Vec<(i32, String)>,
+    /// Whether a caller is writing the requests.
+    is_writing: bool,
+}
+
+static OUTBOX: Lazy<Mutex<Outbox>> = Lazy::new(Mutex::default);
+
+/// Lets another caller write the requests if the writer panics.
+struct Writer;
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            OUTBOX.lock().unwrap().is_writing = false;
+        }
+    }
+}
+
+/// Send the JSON `request` to `client_id`, tagged with the `@extra` `extra`,
+/// or queue it until it can be sent, its response being sent to `sender`.
+///
+/// An idempotent request identical to one sent less than
+/// [`COALESCE_WINDOW`] ago and not answered yet isn't sent, and gets its
+/// response instead from [`complete`].
+pub(crate) fn send(client_id: i32, extra: u32, request: String, sender: oneshot::Sender<String>) {
+    let priority = CURRENT_PRIORITY.with(Cell::get) as usize;
+    let timeout = current_timeout();
+    let now = now();
+    let request = {
+        let mut queues = QUEUES.lock().unwrap();
+        let coalesced = is_idempotent(&request).then(|| request.clone());
+        if let Some(coalesced) = &coalesced {
+            let key = (client_id, coalesced.clone());
+            if let Some(first) = queues.coalescable.get(&key) {
+                if now.saturating_sub(first.sent) < COALESCE_WINDOW {
+                    let first = first.extra;
+                    if let Some(first) = queues.requests.get_mut(&first) {
+                        first.followers.push(extra);
+                        OBSERVER.register(extra, sender, timeout);
+                        return;
+                    }
+                }
+            }
+            queues
+                .coalescable
+                .insert(key, Coalescable { extra, sent: now });
+        }
+        queues.requests.insert(
+            extra,
+            InFlight {
+                client_id,
+                priority,
+                coalesced,
+                followers: Vec::new(),
+            },
+        );
+        let request = tag(&request, extra);
+        let max_in_flight = queues.max_in_flight;
+        let queue = queues.clients.entry(client_id).or_default();
+        if queue.is_blocked(priority, max_in_flight) {
+            let queued = Queued {
+                extra,
+                request,
+                sender,
+                timeout,
+            };
+            queue.queued[priority].push_back(queued);
+            return;
+        }
+        queue.in_flight[priority] += 1;
+        OBSERVER.register(extra, sender, timeout);
+        request
+    };
+    write([(client_id, request)]);
+}
+
+/// Let the queue know that the request with the `@extra` `extra` was
+/// answered, or failed by the observer, sending the requests it was holding
+/// back, and returning the `@extra` of the requests sharing its response.
+pub(crate) fn complete(extra: u32) -> Vec<u32> {
+    let (client_id, released, followers) = {
+        let mut queues = QUEUES.lock().unwrap();
+        let Some(request) = queues.requests.remove(&extra) else {
+            return Vec::new();
+        };
+        if let Some(coalesced) = request.coalesced {
+            let key = (request.client_id, coalesced);
+            if queues
+                .coalescable
+                .get(&key)
+                .is_some_and(|first| first.extra == extra)
+            {
+                queues.coalescable.remove(&key);
+            }
+        }
+        let max_in_flight = queues.max_in_flight;
+        let Some(queue) = queues.clients.get_mut(&request.client_id) else {
+            return request.followers;
+        };
+        queue.in_flight[request.priority] = queue.in_flight[request.priority].saturating_sub(1);
+        let released = queue.release(max_in_flight);
+        if queue.is_idle() {
+            queues.clients.remove(&request.client_id);
+        }
+        (request.client_id, released, request.followers)
+    };
+    write(released.into_iter().map(|request| (client_id, request)));
+    followers
+}
+
+/// Set the maximum number of requests of a client waiting for their
+/// response, sending the requests it was holding back if it's raised.
+pub(crate) fn set_max_in_flight(max_in_flight: Option<usize>) {
+    let released: Vec<_> = {
+        let mut queues = QUEUES.lock().unwrap();
+        queues.max_in_flight = max_in_flight;
+        queues
+            .clients
+            .iter_mut()
+            .flat_map(|(&client_id, queue)| {
+                let released = queue.release(max_in_flight);
+                released
+                    .into_iter()
+                    .map(move |request| (client_id, request))
+            })
+            .collect()
+    };
+    write(released);
+}
+
+/// Write `requests` to the transport, or let the caller already writing
+/// write them after its batch.
+///
+/// The transport is called without holding any lock, since it could answer
+/// the requests right away.
+fn write(requests: impl IntoIterator<Item = (i32, String)>) {
+    {
+        let mut outbox = OUTBOX.lock().unwrap();
+        outbox.requests.extend(requests);
+        if outbox.is_writing || outbox.requests.is_empty() {
+            return;
+        }
+        outbox.is_writing = true;
+    }
+    let _writer = Writer;
+    let transport = transport::transport();
+    loop {
+        let batch = {
+            let mut outbox = OUTBOX.lock().unwrap();
+            if outbox.requests.is_empty() {
+                outbox.is_writing = false;
+                return;
+            }
+            std::mem::take(&mut outbox.requests)
+        };
+        for (client_id, request) in batch {
+            transport.send(client_id, request);
+        }
+    }
+}
+
+/// Whether the JSON `request` is one of the [`IDEMPOTENT_REQUESTS`].
+fn is_idempotent(request: &str) -> bool {
+    // The `@type` is serialized first
+    let Some(rest) = request.strip_prefix("{\"@type\":\"") else {
+        return false;
+    };
+    let name = rest.split('"').next().unwrap_or_default();
+    IDEMPOTENT_REQUESTS.contains(&name)
+}
+
+/// Add the `@extra` identifying the response to the JSON object `request`.
+fn tag(request: &str, extra: u32) -> String {
+    match request.strip_prefix('{') {
+        Some(fields) if fields != "}" => format!("{{\"@extra\":{extra},{fields}"),
+        _ => format!("{{\"@extra\":{extra}}}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_is_blocked() {
+        let mut queue = ClientQueue::default();
+        queue.in_flight[Priority::Normal as usize] = 1;
+        assert!(queue.is_blocked(Priority::Low as usize, None));
+        assert!(!queue.is_blocked(Priority::Normal as usize, None));
+        assert!(!queue.is_blocked(Priority::High as usize, None));
+        assert!(queue.is_blocked(Priority::High as usize, Some(1)));
+        assert!(!queue.is_blocked(Priority::High as usize, Some(2)));
+    }
+
+    #[test]
+    fn check_is_idempotent() {
+        assert!(is_idempotent("{\"@type\":\"getChat\",\"chat_id\":1}"));
+        assert!(is_idempotent("{\"@type\":\"getMe\"}"));
+        assert!(!is_idempotent("{\"@type\":\"getChats\",\"limit\":1}"));
+        assert!(!is_idempotent("{\"chat_id\":1,\"@type\":\"getChat\"}"));
+    }
+
+    #[test]
+    fn check_tag() {
+        assert_eq!(
+            tag("{\"@type\":\"getMe\"}", 7),
+            "{\"@extra\":7,\"@type\":\"getMe\"}"
+        );
+        assert_eq!(tag("{}", 7), "{\"@extra\":7}");
+    }
+
+    #[cfg(feature = "testing")]
+    mod mock {
+        use super::*;
+        use crate::testing::MockClient;
+        use crate::{Transport, EXTRA_COUNTER};
+        use std::sync::atomic::Ordering;
+        use std::sync::PoisonError;
+
+        /// The mock installed as the transport of the process, shared by the
+        /// tests.
+        static MOCK: Lazy<MockClient> = Lazy::new(|| {
+            let mock = MockClient::new();
+            assert!(mock.install());
+            mock
+        });
+
+        /// Serializes the tests, since the limit of the requests in flight
+        /// and the outbox are global.
+        static SERIAL: Mutex<()> = Mutex::new(());
+
+        /// Send the JSON `request` to `client_id` with `priority`, returning
+        /// the receiver of its response.
+        fn send_with(
+            priority: Priority,
+            client_id: i32,
+            request: &str,
+        ) -> oneshot::Receiver<String> {
+            let previous = CURRENT_PRIORITY.with(|current| current.replace(priority));
+            let _restore = RestorePriority(previous);
+            let extra = EXTRA_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let (sender, receiver) = oneshot::channel();
+            send(client_id, extra, request.to_string(), sender);
+            receiver
+        }
+
+        /// The `@type` of the requests written to the mock for `client_id`.
+        fn sent(client_id: i32) -> Vec<String> {
+            MOCK.requests(client_id)
+                .iter()
+                .map(|request| request["@type"].as_str().unwrap().to_string())
+                .collect()
+        }
+
+        /// Deliver the next `count` responses of the mock.
+        fn deliver(count: usize) {
+            for _ in 0..count {
+                crate::receive();
+            }
+        }
+
+        #[test]
+        fn check_priorities() {
+            let _serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+            let client_id = MOCK.create_client();
+
+            let mut high = send_with(Priority::High, client_id, "{\"@type\":\"high\"}");
+            let mut low = send_with(Priority::Low, client_id, "{\"@type\":\"low\"}");
+            let mut normal = send_with(Priority::Normal, client_id, "{\"@type\":\"normal\"}");
+            assert_eq!(sent(client_id), ["high"]);
+
+            deliver(1);
+            assert!(high.try_recv().unwrap().is_some());
+            assert_eq!(sent(client_id), ["high", "normal"]);
+
+            deliver(1);
+            assert!(normal.try_recv().unwrap().is_some());
+            assert_eq!(sent(client_id), ["high", "normal", "low"]);
+
+            deliver(1);
+            assert!(low.try_recv().unwrap().is_some());
+        }
+
+        #[test]
+        fn check_coalescing() {
+            let _serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+            let client_id = MOCK.create_client();
+            MOCK.respond(client_id, "getChat", serde_json::json!({"@type": "ok"}));
+
+            let request = "{\"@type\":\"getChat\",\"chat_id\":1}";
+            let mut first = send_with(Priority::Normal, client_id, request);
+            let mut second = send_with(Priority::Normal, client_id, request);
+            let mut other = send_with(
+                Priority::Normal,
+                client_id,
+                "{\"@type\":\"getChat\",\"chat_id\":2}",
+            );
+            assert_eq!(sent(client_id), ["getChat", "getChat"]);
+
+            deliver(2);
+            let first = first.try_recv().unwrap().unwrap();
+            assert!(first.contains("\"ok\""));
+            assert_eq!(second.try_recv().unwrap(), Some(first));
+            assert!(other.try_recv().unwrap().unwrap().contains("500"));
+        }
+
+        #[test]
+        fn check_max_in_flight() {
+            let _serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+            let client_id = MOCK.create_client();
+            set_max_in_flight(Some(1));
+
+            let mut responses: Vec<_> = ["first", "second", "third"]
+                .iter()
+                .map(|name| {
+                    let request = format!("{{\"@type\":\"{name}\"}}");
+                    send_with(Priority::High, client_id, &request)
+                })
+                .collect();
+            assert_eq!(sent(client_id), ["first"]);
+
+            deliver(1);
+            assert_eq!(sent(client_id), ["first", "second"]);
+
+            set_max_in_flight(None);
+            assert_eq!(sent(client_id), ["first", "second", "third"]);
+
+            deliver(2);
+            for response in &mut responses {
+                assert!(response.try_recv().unwrap().is_some());
+            }
+        }
+
+        #[test]
+        fn check_batching() {
+            let _serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+            let client_id = MOCK.create_client();
+
+            // Another caller is writing, so the requests are only appended
+            OUTBOX.lock().unwrap().is_writing = true;
+            let mut responses = vec![
+                send_with(Priority::Normal, client_id, "{\"@type\":\"first\"}"),
+                send_with(Priority::Normal, client_id, "{\"@type\":\"second\"}"),
+            ];
+            assert!(sent(client_id).is_empty());
+
+            OUTBOX.lock().unwrap().is_writing = false;
+            write([]);
+            assert_eq!(sent(client_id), ["first", "second"]);
+
+            deliver(2);
+            for response in &mut responses {
+                assert!(response.try_recv().unwrap().is_some());
+            }
+        }
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The rate limits of the messages sent by the clients, e.g. to keep the
+//! bots broadcasting messages below the limits of Telegram, which answers
+//! with errors with code 429 otherwise.
+//!
+//! Once enabled with [`set_rate_limits`], the functions sending messages
+//! (e.g. `functions::send_message` or `functions::forward_messages`) wait for
+//! the budgets of their client and of their chat before sending the request.
+use crate::observer::now;
+use crate::updates;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The number of buckets above which the full ones are forgotten, since they
+/// are the same as new ones.
+const MAX_BUCKETS: usize = 1024;
+
+/// The offset of the ids of the secret chats, which are negative like the
+/// ones of the groups.
+const ZERO_SECRET_CHAT_ID: i64 = -2_000_000_000_000;
+
+/// A budget of messages, refilled at a steady rate up to a burst.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Budget {
+    /// The messages added to the budget per second.
+    rate: f64,
+    /// The messages which can be sent at once when the budget is full.
+    burst: u32,
+}
+
+impl Budget {
+    /// A budget of `messages` per second, without bursts.
+    ///
+    /// # Panics
+    ///
+    /// If `messages` is 0.
+    pub fn per_second(messages: u32) -> Self {
+        assert!(messages > 0, "the budget must allow some messages");
+        Self {
+            rate: messages.into(),
+            burst: 1,
+        }
+    }
+
+    /// A budget of `messages` per minute, without bursts.
+    ///
+    /// # Panics
+    ///
+    /// If `messages` is 0.
+    pub fn per_minute(messages: u32) -> Self {
+        assert!(messages > 0, "the budget must allow some messages");
+        Self {
+            rate: f64::from(messages) / 60.0,
+            burst: 1,
+        }
+    }
+
+    /// Allow sending up to `burst` messages at once after a pause, still at
+    /// the same rate in the long run.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+/// The budgets of the messages sent by every client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimits {
+    /// The budget of all the messages of a client.
+    pub global: Budget,
+    /// The budget of the messages of a client to a private or a secret chat.
+    pub private_chat: Budget,
+    /// The budget of the messages of a client to a group or a channel.
+    pub group_chat: Budget,
+}
+
+/// The limits documented by Telegram for the bots: 30 messages per second,
+/// 1 message per second in a private chat and 20 messages per minute in a
+/// group, with short bursts.
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            global: Budget::per_second(30).with_burst(30),
+            private_chat: Budget::per_second(1).with_burst(3),
+            group_chat: Budget::per_minute(20).with_burst(3),
+        }
+    }
+}
+
+/// The messages left in a budget.
+struct Bucket {
+    budget: Budget,
+    /// Negative when the messages sent at once exceeded the budget.
+    messages: f64,
+    updated: Duration,
+}
+
+impl Bucket {
+    fn new(budget: Budget, now: Duration) -> Self {
+        Self {
+            budget,
+            messages: budget.burst.into(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Duration) {
+        let elapsed = now.saturating_sub(self.updated).as_secs_f64();
+        let burst = f64::from(self.budget.burst);
+        self.messages = (self.messages + elapsed * self.budget.rate).min(burst);
+        self.updated = now;
+    }
+
+    fn is_full(&self) -> bool {
+        self.messages >= f64::from(self.budget.burst)
+    }
+
+    /// How long until a message can be sent.
+    fn delay(&self) -> Duration {
+        if self.messages >= 1.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.messages) / self.budget.rate)
+    }
+}
+
+#[derive(Default)]
+struct Limiter {
+    limits: Option<RateLimits>,
+    /// The buckets by client id, and chat id except for the global ones.
+    buckets: HashMap<(i32, Option<i64>), Bucket>,
+}
+
+static LIMITER: Lazy<Mutex<Limiter>> = Lazy::new(Mutex::default);
+
+/// Limit the rate of the messages sent from now on by every client, e.g. to
+/// `RateLimits::default()`, or stop limiting it with `None`, which is the
+/// default.
+///
+/// The waits are timed by [`receive`](crate::receive), so the messages can be
+/// sent up to the timeout of `receive` later than the limits allow.
+pub fn set_rate_limits(limits: Option<RateLimits>) {
+    let mut limiter = LIMITER.lock().unwrap();
+    limiter.limits = limits;
+    limiter.buckets.clear();
+}
+
+/// Wait until `client_id` can send `count` messages to `chat_id`, taking them
+/// from its budgets.
+pub(crate) async fn acquire(chat_id: i64, count: usize, client_id: i32) {
+    while let Some(delay) = reserve(chat_id, count, client_id) {
+        updates::sleep(delay, client_id).await;
+    }
+}
+
+/// Take `count` messages from the budgets of `client_id` and `chat_id`, or
+/// return how long to wait if either is exhausted.
+fn reserve(chat_id: i64, count: usize, client_id: i32) -> Option<Duration> {
+    LIMITER
+        .lock()
+        .unwrap()
+        .reserve(chat_id, count, client_id, now())
+}
+
+impl Limiter {
+    /// Take `count` messages from the budgets of `client_id` and `chat_id` at
+    /// `now`, or return how long to wait if either is exhausted.
+    ///
+    /// The messages sent at once can exceed the budgets, which then delay the
+    /// next messages accordingly.
+    fn reserve(
+        &mut self,
+        chat_id: i64,
+        count: usize,
+        client_id: i32,
+        now: Duration,
+    ) -> Option<Duration> {
+        let limits = self.limits?;
+        if self.buckets.len() >= MAX_BUCKETS {
+            self.buckets.retain(|_, bucket| {
+                bucket.refill(now);
+                !bucket.is_full()
+            });
+        }
+
+        let chat_budget = if is_group(chat_id) {
+            limits.group_chat
+        } else {
+            limits.private_chat
+        };
+        let budgets = [
+            ((client_id, None), limits.global),
+            ((client_id, Some(chat_id)), chat_budget),
+        ];
+        let mut delay = Duration::ZERO;
+        for (key, budget) in budgets {
+            let bucket = self
+                .buckets
+                .entry(key)
+                .or_insert_with(|| Bucket::new(budget, now));
+            bucket.refill(now);
+            delay = delay.max(bucket.delay());
+        }
+        if !delay.is_zero() {
+            return Some(delay);
+        }
+        for (key, _) in budgets {
+            if let Some(bucket) = self.buckets.get_mut(&key) {
+                bucket.messages -= count as f64;
+            }
+        }
+        None
+    }
+}
+
+/// Whether `chat_id` is a group or a channel, rather than a private or a
+/// secret chat.
+fn is_group(chat_id: i64) -> bool {
+    let is_secret = chat_id.abs_diff(ZERO_SECRET_CHAT_ID) <= i32::MAX as u64;
+    chat_id < 0 && !is_secret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_refill() {
+        let start = Duration::from_secs(100);
+        let mut bucket = Bucket::new(Budget::per_second(2).with_burst(4), start);
+        bucket.messages = -1.0;
+        assert_eq!(bucket.delay(), Duration::from_secs(1));
+
+        bucket.refill(start + Duration::from_millis(500));
+        assert_eq!(bucket.messages, 0.0);
+        assert_eq!(bucket.delay(), Duration::from_millis(500));
+
+        bucket.refill(start + Duration::from_secs(1));
+        assert_eq!(bucket.messages, 1.0);
+        assert_eq!(bucket.delay(), Duration::ZERO);
+
+        bucket.refill(start + Duration::from_secs(60));
+        assert_eq!(bucket.messages, 4.0);
+        assert!(bucket.is_full());
+
+        // The clock going backwards doesn't drain the bucket
+        bucket.refill(start);
+        assert_eq!(bucket.messages, 4.0);
+    }
+
+    #[test]
+    fn check_reserve() {
+        let mut limiter = Limiter {
+            limits: Some(RateLimits {
+                global: Budget::per_second(30).with_burst(30),
+                private_chat: Budget::per_second(1).with_burst(2),
+                group_chat: Budget::per_minute(20),
+            }),
+            buckets: HashMap::new(),
+        };
+        let start = Duration::from_secs(100);
+
+        assert_eq!(limiter.reserve(1, 1, 1, start), None);
+        assert_eq!(limiter.reserve(1, 1, 1, start), None);
+        assert_eq!(
+            limiter.reserve(1, 1, 1, start),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            limiter.reserve(1, 1, 1, start + Duration::from_millis(250)),
+            Some(Duration::from_millis(750))
+        );
+        assert_eq!(
+            limiter.reserve(1, 1, 1, start + Duration::from_secs(1)),
+            None
+        );
+
+        // The other chats and clients have their own budgets
+        assert_eq!(limiter.reserve(2, 1, 1, start), None);
+        assert_eq!(limiter.reserve(1, 1, 2, start), None);
+
+        // Sending several messages at once delays the next ones
+        assert_eq!(limiter.reserve(-100, 4, 1, start), None);
+        assert_eq!(
+            limiter.reserve(-100, 1, 1, start),
+            Some(Duration::from_secs(12))
+        );
+    }
+
+    #[test]
+    fn check_without_limits() {
+        let mut limiter = Limiter::default();
+        for _ in 0..100 {
+            assert_eq!(limiter.reserve(1, 1, 1, Duration::ZERO), None);
+        }
+    }
+
+    #[test]
+    fn check_is_group() {
+        assert!(!is_group(777));
+        assert!(is_group(-100));
+        assert!(is_group(-1_001_234_567_890));
+        assert!(!is_group(ZERO_SECRET_CHAT_ID + 5));
+    }
+}
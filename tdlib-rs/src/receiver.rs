@@ -0,0 +1,93 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The receive loop running on its own thread, so that the futures of the
+//! library can be driven by any executor, e.g. smol or async-std, without
+//! blocking it.
+use crate::enums::Update;
+use futures_channel::mpsc;
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+use std::thread;
+
+/// Whether a receive thread is running, since TDLib must be received from
+/// only one thread at a time.
+static IS_RECEIVING: AtomicBool = AtomicBool::new(false);
+
+/// A stream of the updates received by the thread started with
+/// [`spawn_receiver`].
+///
+/// The thread stops shortly after the stream is dropped, since
+/// [`receive`](crate::receive) returns at least every 2 seconds.
+pub struct ReceivedUpdates {
+    updates: mpsc::UnboundedReceiver<(Update, i32)>,
+}
+
+/// Start a thread calling [`receive`](crate::receive) in a loop, which
+/// delivers the responses to the futures of the library and streams the
+/// updates with their client id, e.g.
+///
+/// ```ignore
+/// let mut updates = tdlib_rs::spawn_receiver()?;
+/// smol::block_on(async {
+///     let client = Client::create();
+///     functions::set_log_verbosity_level(1, client.id()).await?;
+///     while let Some((update, client_id)) = updates.next().await {
+///         // Handle the update
+///     }
+/// });
+/// ```
+///
+/// The application mustn't call `receive` itself while the thread is
+/// running, and starting a second thread fails with
+/// [`io::ErrorKind::AlreadyExists`].
+pub fn spawn_receiver() -> io::Result<ReceivedUpdates> {
+    if IS_RECEIVING.swap(true, Ordering::AcqRel) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "TDLib is already received by another thread",
+        ));
+    }
+    let (sender, updates) = mpsc::unbounded();
+    let receive = move || {
+        while !sender.is_closed() {
+            if let Some(update) = crate::receive() {
+                let _ = sender.unbounded_send(update);
+            }
+        }
+        IS_RECEIVING.store(false, Ordering::Release);
+    };
+    let spawned = thread::Builder::new()
+        .name("tdlib-receive".to_string())
+        .spawn(receive);
+    if let Err(error) = spawned {
+        IS_RECEIVING.store(false, Ordering::Release);
+        return Err(error);
+    }
+    Ok(ReceivedUpdates { updates })
+}
+
+impl ReceivedUpdates {
+    /// Block the current thread until the next update is received, e.g. in
+    /// the applications without an async runtime.
+    pub fn next_blocking(&mut self) -> Option<(Update, i32)> {
+        crate::block_on(poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)))
+    }
+}
+
+impl Stream for ReceivedUpdates {
+    type Item = (Update, i32);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.updates).poll_next(cx)
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The secret chats, end-to-end encrypted between two devices, created with
+//! `createNewSecretChat` and followed through the `updateSecretChat` updates.
+//!
+//! The secret chats of the other users are accepted by TDLib once they're
+//! received, and become ready like the created ones once the key exchange
+//! completes, which [`wait_until_ready`] waits for.
+//!
+//! The secret chats support fewer features than the cloud chats: their
+//! messages can't be edited, scheduled or forwarded, have no reply markup,
+//! and the contents rejected by [`supports_content`] can't be sent to them.
+use crate::enums::{self, ChatType, InputMessageContent, SecretChatState, Update};
+use crate::{chats, functions, types, updates, TdError};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// The secret chat of a response, failing on the unrecognized constructors.
+fn secret_chat_of(secret_chat: enums::SecretChat) -> Result<types::SecretChat, TdError> {
+    match secret_chat {
+        enums::SecretChat::SecretChat(secret_chat) => Ok(secret_chat),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::SecretChat::Unrecognized => Err(TdError::unrecognized("SecretChat")),
+    }
+}
+
+/// The id of the secret chat of `chat`, if it's a secret chat.
+pub fn secret_chat_id(chat: &types::Chat) -> Option<i32> {
+    match &chat.r#type {
+        ChatType::Secret(secret) => Some(secret.secret_chat_id),
+        _ => None,
+    }
+}
+
+/// Whether `content` can be sent to a secret chat, e.g. not a poll, an
+/// invoice, a game or a story.
+pub fn supports_content(content: &InputMessageContent) -> bool {
+    match content {
+        InputMessageContent::InputMessagePoll(_)
+        | InputMessageContent::InputMessageInvoice(_)
+        | InputMessageContent::InputMessageGame(_)
+        | InputMessageContent::InputMessageStory(_) => false,
+        #[cfg(not(feature = "api-1.8.19"))]
+        InputMessageContent::InputMessagePaidMedia(_)
+        | InputMessageContent::InputMessageChecklist(_) => false,
+        _ => true,
+    }
+}
+
+/// Create a secret chat with the user `user_id`, returning the chat, which
+/// is pending until the user accepts it.
+pub async fn create_secret_chat(user_id: i64, client_id: i32) -> Result<types::Chat, TdError> {
+    functions::create_new_secret_chat(user_id, client_id)
+        .await
+        .and_then(chats::chat_of)
+}
+
+/// The current state of the secret chat `secret_chat_id`.
+pub async fn secret_chat(
+    secret_chat_id: i32,
+    client_id: i32,
+) -> Result<types::SecretChat, TdError> {
+    functions::get_secret_chat(secret_chat_id, client_id)
+        .await
+        .and_then(secret_chat_of)
+}
+
+/// Close the secret chat `secret_chat_id`, which can't be used anymore by
+/// both users.
+pub async fn close_secret_chat(secret_chat_id: i32, client_id: i32) -> Result<(), TdError> {
+    functions::close_secret_chat(secret_chat_id, client_id).await
+}
+
+/// Wait until the secret chat `secret_chat_id` is ready to send messages,
+/// failing with [`TdError::Canceled`] if it's closed before, e.g. when the
+/// other user doesn't accept it.
+pub async fn wait_until_ready(
+    secret_chat_id: i32,
+    client_id: i32,
+) -> Result<types::SecretChat, TdError> {
+    // Subscribe before getting the state, so that no change is missed
+    let mut changes = watch_secret_chat(secret_chat_id, client_id);
+    let mut secret_chat = self::secret_chat(secret_chat_id, client_id).await?;
+    loop {
+        match secret_chat.state {
+            SecretChatState::Ready => return Ok(secret_chat),
+            SecretChatState::Closed => return Err(TdError::Canceled),
+            SecretChatState::Pending => {}
+            #[cfg(feature = "unrecognized-variants")]
+            SecretChatState::Unrecognized => {}
+        }
+        secret_chat = poll_fn(|cx| Pin::new(&mut changes).poll_next(cx))
+            .await
+            .ok_or(TdError::Canceled)?;
+    }
+}
+
+/// A stream of the states of a secret chat returned by [`watch_secret_chat`].
+pub struct SecretChatChanges {
+    updates: mpsc::UnboundedReceiver<Update>,
+}
+
+/// Stream the new states of the secret chat `secret_chat_id`, e.g. once it's
+/// accepted or closed, from the `updateSecretChat` updates.
+pub fn watch_secret_chat(secret_chat_id: i32, client_id: i32) -> SecretChatChanges {
+    let updates = updates::subscribe(
+        client_id,
+        move |update| matches!(update, Update::SecretChat(update) if update.secret_chat.id == secret_chat_id),
+    );
+    SecretChatChanges { updates }
+}
+
+impl Stream for SecretChatChanges {
+    type Item = types::SecretChat;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        while let Some(update) = ready!(Pin::new(&mut self.updates).poll_next(cx)) {
+            if let Update::SecretChat(update) = update {
+                return Poll::Ready(Some(update.secret_chat.clone()));
+            }
+        }
+        // The client was closed
+        Poll::Ready(None)
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The storage used by the files of TDLib, reported by
+//! `getStorageStatistics` and reduced by `optimizeStorage`.
+use crate::enums::{self, FileType};
+use crate::progress::{Progress, ProgressUnit};
+use crate::{functions, types, TdError};
+use std::time::Duration;
+
+/// The steps of [`StorageOptimizer::run_with_progress`]: the optimization
+/// and the statistics of the remaining storage.
+const OPTIMIZATION_STEPS: i64 = 2;
+
+/// The number of chats with their own statistics in the reports, the others
+/// being grouped with the chat id 0.
+const REPORT_CHAT_LIMIT: i32 = 10;
+
+/// The files considered as media by [`StorageOptimizer::keep_recent_media`].
+const MEDIA_FILE_TYPES: [FileType; 7] = [
+    FileType::Animation,
+    FileType::Audio,
+    FileType::Document,
+    FileType::Photo,
+    FileType::Video,
+    FileType::VideoNote,
+    FileType::VoiceNote,
+];
+
+/// The result of [`StorageOptimizer::run`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StorageReport {
+    /// The statistics of the deleted files, split by chats and file types.
+    pub deleted: types::StorageStatistics,
+    /// The approximate storage usage after the optimization.
+    pub remaining: types::StorageStatisticsFast,
+}
+
+impl StorageReport {
+    /// The total size of the deleted files, in bytes.
+    pub fn deleted_size(&self) -> i64 {
+        self.deleted.size
+    }
+
+    /// The number of the deleted files.
+    pub fn deleted_count(&self) -> i32 {
+        self.deleted.count
+    }
+
+    /// The statistics of the deleted files by file type, summed over the
+    /// chats, from the largest to the smallest.
+    pub fn deleted_by_file_type(&self) -> Vec<types::StorageStatisticsByFileType> {
+        let mut by_file_type: Vec<types::StorageStatisticsByFileType> = Vec::new();
+        let chats = self.deleted.by_chat.iter();
+        for statistics in chats.flat_map(|chat| &chat.by_file_type) {
+            match by_file_type
+                .iter_mut()
+                .find(|total| total.file_type == statistics.file_type)
+            {
+                Some(total) => {
+                    total.size += statistics.size;
+                    total.count += statistics.count;
+                }
+                None => by_file_type.push(statistics.clone()),
+            }
+        }
+        by_file_type.sort_by_key(|statistics| std::cmp::Reverse(statistics.size));
+        by_file_type
+    }
+}
+
+/// The parameters of `optimizeStorage`, each limit being TDLib's default
+/// unless set, e.g.
+///
+/// ```ignore
+/// let report = StorageOptimizer::keep_recent_media().run(client.id()).await?;
+/// println!("{} bytes freed", report.deleted_size());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StorageOptimizer {
+    max_size: Option<i64>,
+    max_age: Option<Duration>,
+    max_count: Option<i32>,
+    immunity_delay: Option<Duration>,
+    file_types: Vec<FileType>,
+    chat_ids: Vec<i64>,
+    exclude_chat_ids: Vec<i64>,
+}
+
+impl StorageOptimizer {
+    /// The optimizer using the default limits of TDLib, deleting all the
+    /// files except the thumbnails, the profile photos, the stickers and the
+    /// wallpapers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The preset keeping the media accessed in the last 7 days, up to
+    /// 2 GiB.
+    pub fn keep_recent_media() -> Self {
+        Self::new()
+            .max_age(Duration::from_secs(7 * 24 * 60 * 60))
+            .max_size(2 << 30)
+            .file_types(MEDIA_FILE_TYPES)
+    }
+
+    /// The preset deleting all the files it can, e.g. to clear the cache on
+    /// the user's request.
+    pub fn clear_all() -> Self {
+        Self::new()
+            .max_age(Duration::ZERO)
+            .max_size(0)
+            .max_count(0)
+            .immunity_delay(Duration::ZERO)
+    }
+
+    /// Set the maximum total size of the files kept, in bytes.
+    pub fn max_size(mut self, max_size: i64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Set the maximum time since the files kept were last accessed.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Set the maximum number of files kept.
+    pub fn max_count(mut self, max_count: i32) -> Self {
+        self.max_count = Some(max_count);
+        self
+    }
+
+    /// Set the time after the creation of a file during which it's kept.
+    pub fn immunity_delay(mut self, immunity_delay: Duration) -> Self {
+        self.immunity_delay = Some(immunity_delay);
+        self
+    }
+
+    /// Only delete the files of these types.
+    pub fn file_types(mut self, file_types: impl IntoIterator<Item = FileType>) -> Self {
+        self.file_types = file_types.into_iter().collect();
+        self
+    }
+
+    /// Only delete the files of these chats, the chat id 0 being the files
+    /// not belonging to any chat, e.g. the profile photos.
+    pub fn chats(mut self, chat_ids: impl IntoIterator<Item = i64>) -> Self {
+        self.chat_ids = chat_ids.into_iter().collect();
+        self
+    }
+
+    /// Keep the files of these chats, the chat id 0 being the files not
+    /// belonging to any chat, e.g. the profile photos.
+    pub fn exclude_chats(mut self, chat_ids: impl IntoIterator<Item = i64>) -> Self {
+        self.exclude_chat_ids = chat_ids.into_iter().collect();
+        self
+    }
+
+    /// Delete the files exceeding the limits, reporting the deleted files
+    /// and the remaining storage usage.
+    pub async fn run(&self, client_id: i32) -> Result<StorageReport, TdError> {
+        self.run_with_progress(|_| {}, client_id).await
+    }
+
+    /// Like [`run`](Self::run), reporting the steps done to `on_progress`,
+    /// since TDLib doesn't report the progress of the optimization itself.
+    pub async fn run_with_progress(
+        &self,
+        mut on_progress: impl FnMut(Progress),
+        client_id: i32,
+    ) -> Result<StorageReport, TdError> {
+        let step = |done| Progress::new(done, Some(OPTIMIZATION_STEPS), ProgressUnit::Steps);
+        on_progress(step(0));
+        let size = self.max_size.unwrap_or(-1);
+        let ttl = self.max_age.map_or(-1, |age| age.as_secs() as i32);
+        let count = self.max_count.unwrap_or(-1);
+        let immunity_delay = self
+            .immunity_delay
+            .map_or(-1, |delay| delay.as_secs() as i32);
+        let deleted = functions::optimize_storage(
+            size,
+            ttl,
+            count,
+            immunity_delay,
+            self.file_types.iter().cloned(),
+            self.chat_ids.iter().copied(),
+            self.exclude_chat_ids.iter().copied(),
+            true,
+            REPORT_CHAT_LIMIT,
+            client_id,
+        )
+        .await?;
+        on_progress(step(1));
+        let report = StorageReport {
+            deleted: statistics(deleted)?,
+            remaining: storage_statistics_fast(client_id).await?,
+        };
+        on_progress(step(2));
+        Ok(report)
+    }
+}
+
+/// The statistics of a response, failing on the unrecognized constructors.
+fn statistics(statistics: enums::StorageStatistics) -> Result<types::StorageStatistics, TdError> {
+    match statistics {
+        enums::StorageStatistics::StorageStatistics(statistics) => Ok(statistics),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::StorageStatistics::Unrecognized => Err(TdError::unrecognized("StorageStatistics")),
+    }
+}
+
+/// The exact storage usage, split by chats and file types, with their own
+/// statistics for the `chat_limit` chats using the most storage.
+pub async fn storage_statistics(
+    chat_limit: i32,
+    client_id: i32,
+) -> Result<types::StorageStatistics, TdError> {
+    functions::get_storage_statistics(chat_limit, client_id)
+        .await
+        .and_then(statistics)
+}
+
+/// The approximate storage usage, returned quickly.
+pub async fn storage_statistics_fast(
+    client_id: i32,
+) -> Result<types::StorageStatisticsFast, TdError> {
+    match functions::get_storage_statistics_fast(client_id).await? {
+        enums::StorageStatisticsFast::StorageStatisticsFast(statistics) => Ok(statistics),
+        #[cfg(feature = "unrecognized-variants")]
+        enums::StorageStatisticsFast::Unrecognized => {
+            Err(TdError::unrecognized("StorageStatisticsFast"))
+        }
+    }
+}
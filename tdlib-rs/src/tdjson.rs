@@ -7,25 +7,255 @@
 // <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
+#[cfg(not(feature = "wasm"))]
 use std::ffi::{CStr, CString};
+#[cfg(not(feature = "wasm"))]
 use std::os::raw::{c_char, c_double, c_int};
 
-#[link(name = "tdjson")]
+// The `vendored` feature links the static library from the build script, and
+// the `tdlight` feature renames it when `TDJSON_LIB_NAME` is set
+#[cfg(not(any(feature = "dynamic-loading", feature = "wasm", tdjson_stub)))]
+#[cfg_attr(not(feature = "vendored"), link(name = "tdjson"))]
 extern "C" {
     fn td_create_client_id() -> c_int;
     fn td_send(client_id: c_int, request: *const c_char);
     fn td_receive(timeout: c_double) -> *const c_char;
+    fn td_execute(request: *const c_char) -> *const c_char;
 }
 
+#[cfg(feature = "dynamic-loading")]
+use dynamic::{td_create_client_id, td_execute, td_receive, td_send};
+
+#[cfg(tdjson_stub)]
+use stub::{td_create_client_id, td_execute, td_receive, td_send};
+
+/// Stands in for the tdjson functions when TDLib is not available, i.e. with
+/// the `stub` feature or on docs.rs, so that the crate can be checked and
+/// documented without linking it. Calling any of them panics.
+#[cfg(tdjson_stub)]
+mod stub {
+    use super::{c_char, c_double, c_int};
+
+    const MESSAGE: &str = "tdjson is not linked: tdlib-rs was built with the stub backend";
+
+    pub(super) unsafe fn td_create_client_id() -> c_int {
+        panic!("{MESSAGE}")
+    }
+
+    pub(super) unsafe fn td_send(_client_id: c_int, _request: *const c_char) {
+        panic!("{MESSAGE}")
+    }
+
+    pub(super) unsafe fn td_receive(_timeout: c_double) -> *const c_char {
+        panic!("{MESSAGE}")
+    }
+
+    pub(super) unsafe fn td_execute(_request: *const c_char) -> *const c_char {
+        panic!("{MESSAGE}")
+    }
+}
+
+/// Loads the tdjson functions from the shared library at runtime, instead of
+/// binding them at link time.
+#[cfg(feature = "dynamic-loading")]
+pub(crate) mod dynamic {
+    use super::{c_char, c_double, c_int};
+    use libloading::Library;
+    use once_cell::sync::OnceCell;
+    use std::ffi::OsString;
+
+    /// The environment variable overriding the path of the tdjson library.
+    pub(crate) const TDJSON_PATH_VAR: &str = "TDJSON_PATH";
+
+    struct TdJson {
+        td_create_client_id: unsafe extern "C" fn() -> c_int,
+        td_send: unsafe extern "C" fn(c_int, *const c_char),
+        td_receive: unsafe extern "C" fn(c_double) -> *const c_char,
+        td_execute: unsafe extern "C" fn(*const c_char) -> *const c_char,
+        // Keeps the functions above valid
+        _library: Library,
+    }
+
+    static TDJSON: OnceCell<TdJson> = OnceCell::new();
+
+    fn open(path: OsString) -> Result<TdJson, libloading::Error> {
+        unsafe {
+            let library = Library::new(path)?;
+            Ok(TdJson {
+                td_create_client_id: *library.get(b"td_create_client_id\0")?,
+                td_send: *library.get(b"td_send\0")?,
+                td_receive: *library.get(b"td_receive\0")?,
+                td_execute: *library.get(b"td_execute\0")?,
+                _library: library,
+            })
+        }
+    }
+
+    /// The path of the library when none is given: the `TDJSON_PATH`
+    /// environment variable, or the platform-specific name of the library
+    /// (e.g. `libtdjson.so`) looked up in the default search paths.
+    fn default_path() -> OsString {
+        std::env::var_os(TDJSON_PATH_VAR)
+            .unwrap_or_else(|| libloading::library_filename(env!("TDJSON_LIB_NAME")))
+    }
+
+    pub(crate) fn load(path: Option<OsString>) -> Result<(), libloading::Error> {
+        TDJSON
+            .get_or_try_init(|| open(path.unwrap_or_else(default_path)))
+            .map(|_| ())
+    }
+
+    fn tdjson() -> &'static TdJson {
+        TDJSON
+            .get_or_try_init(|| open(default_path()))
+            .unwrap_or_else(|e| panic!("failed to load the tdjson library: {e}"))
+    }
+
+    pub(super) unsafe fn td_create_client_id() -> c_int {
+        (tdjson().td_create_client_id)()
+    }
+
+    pub(super) unsafe fn td_send(client_id: c_int, request: *const c_char) {
+        (tdjson().td_send)(client_id, request)
+    }
+
+    pub(super) unsafe fn td_receive(timeout: c_double) -> *const c_char {
+        (tdjson().td_receive)(timeout)
+    }
+
+    pub(super) unsafe fn td_execute(request: *const c_char) -> *const c_char {
+        (tdjson().td_execute)(request)
+    }
+}
+
+/// Talks to the WebAssembly build of TDLib through the `TdClient` of tdweb,
+/// whose responses and updates are delivered asynchronously by the browser
+/// event loop and queued until they are received.
+#[cfg(feature = "wasm")]
+pub(crate) mod wasm {
+    use serde_json::Value;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::task::{Poll, Waker};
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen_futures::JsFuture;
+
+    #[wasm_bindgen(module = "tdweb")]
+    extern "C" {
+        #[wasm_bindgen(js_name = default)]
+        type TdClient;
+
+        #[wasm_bindgen(constructor, js_class = "default")]
+        fn new(options: &JsValue) -> TdClient;
+
+        #[wasm_bindgen(method, js_class = "default")]
+        fn send(this: &TdClient, query: &JsValue) -> js_sys::Promise;
+    }
+
+    struct Client {
+        td_client: TdClient,
+        // Keeps the `onUpdate` callback alive as long as the client
+        _on_update: Closure<dyn FnMut(JsValue)>,
+    }
+
+    thread_local! {
+        static CLIENTS: RefCell<Vec<Client>> = const { RefCell::new(Vec::new()) };
+        static RESPONSES: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+        static WAKER: RefCell<Option<Waker>> = const { RefCell::new(None) };
+    }
+
+    /// Queue a response or update of `client_id`, waking up the pending
+    /// [`next_response`].
+    fn push(client_id: i32, response: JsValue) {
+        let response = js_sys::JSON::stringify(&response)
+            .ok()
+            .and_then(|response| serde_json::from_str::<Value>(&String::from(response)).ok());
+        let Some(mut response) = response else {
+            log::warn!("Received a response that is not a JSON object");
+            return;
+        };
+        response["@client_id"] = client_id.into();
+
+        RESPONSES.with_borrow_mut(|responses| responses.push_back(response.to_string()));
+        if let Some(waker) = WAKER.take() {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn create_client() -> i32 {
+        CLIENTS.with_borrow_mut(|clients| {
+            let client_id = clients.len() as i32 + 1;
+            let on_update =
+                Closure::<dyn FnMut(JsValue)>::new(move |update| push(client_id, update));
+
+            let options = js_sys::Object::new();
+            js_sys::Reflect::set(&options, &"onUpdate".into(), on_update.as_ref()).unwrap();
+            js_sys::Reflect::set(
+                &options,
+                &"instanceName".into(),
+                &format!("tdlib{client_id}").into(),
+            )
+            .unwrap();
+
+            clients.push(Client {
+                td_client: TdClient::new(&options),
+                _on_update: on_update,
+            });
+            client_id
+        })
+    }
+
+    pub(crate) fn send(client_id: i32, request: String) {
+        let extra = serde_json::from_str::<Value>(&request).unwrap()["@extra"].clone();
+        let query = js_sys::JSON::parse(&request).unwrap();
+        let promise =
+            CLIENTS.with_borrow(|clients| clients[client_id as usize - 1].td_client.send(&query));
+
+        wasm_bindgen_futures::spawn_local(async move {
+            // tdweb rejects the promise with the error returned by TDLib, and
+            // rewrites the `@extra` field, which is restored here
+            let response = JsFuture::from(promise).await.unwrap_or_else(|error| error);
+            let extra = js_sys::JSON::parse(&extra.to_string()).unwrap();
+            js_sys::Reflect::set(&response, &"@extra".into(), &extra).unwrap();
+            push(client_id, response);
+        });
+    }
+
+    /// Never waits for a response, since blocking would freeze the browser:
+    /// use [`next_response`] instead.
+    pub(crate) fn receive(_timeout: f64) -> Option<String> {
+        RESPONSES.with_borrow_mut(|responses| responses.pop_front())
+    }
+
+    /// Wait for the next response or update, letting the browser event loop
+    /// run in the meantime.
+    pub(crate) async fn next_response() -> String {
+        std::future::poll_fn(|cx| match receive(0.0) {
+            Some(response) => Poll::Ready(response),
+            None => {
+                WAKER.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "wasm")]
+pub(crate) use wasm::{create_client, receive, send};
+
+#[cfg(not(feature = "wasm"))]
 pub(crate) fn create_client() -> i32 {
     unsafe { td_create_client_id() }
 }
 
+#[cfg(not(feature = "wasm"))]
 pub(crate) fn send(client_id: i32, request: String) {
     let cstring = CString::new(request).unwrap();
     unsafe { td_send(client_id, cstring.as_ptr()) }
 }
 
+#[cfg(not(feature = "wasm"))]
 pub(crate) fn receive(timeout: f64) -> Option<String> {
     unsafe {
         td_receive(timeout)
@@ -33,3 +263,13 @@ pub(crate) fn receive(timeout: f64) -> Option<String> {
             .map(|response| CStr::from_ptr(response).to_string_lossy().into_owned())
     }
 }
+
+#[cfg(not(feature = "wasm"))]
+pub(crate) fn execute(request: String) -> Option<String> {
+    let cstring = CString::new(request).unwrap();
+    unsafe {
+        td_execute(cstring.as_ptr())
+            .as_ref()
+            .map(|response| CStr::from_ptr(response).to_string_lossy().into_owned())
+    }
+}
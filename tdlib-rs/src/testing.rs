@@ -0,0 +1,337 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers to unit-test the applications without a Telegram account or a
+//! linked TDLib, by installing a [`MockClient`] as the [`Transport`], or to
+//! regression-test them against real-world payloads, by recording a live
+//! session with [`Recorder`] and replaying it with [`Replay`].
+use crate::Transport;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct State {
+    last_client_id: i32,
+    /// The requests sent by each client, in order.
+    requests: HashMap<i32, Vec<Value>>,
+    /// The scripted responses of each client, by the `@type` of the request.
+    responses: HashMap<(i32, String), VecDeque<Value>>,
+    /// The responses and updates ready to be received.
+    queue: VecDeque<String>,
+}
+
+/// A [`Transport`] that records the requests and answers them with scripted
+/// JSON responses, while the updates are pushed by the test.
+///
+/// The requests without a scripted response get an error with code 500, so
+/// that the tests never hang waiting for them. The mock is cheap to clone, and
+/// all the clones share the same state, so a clone can be installed while the
+/// test keeps another one.
+///
+/// Since the transport is shared by the whole process, each test should create
+/// its own client and script the responses of that client only.
+#[derive(Clone, Default)]
+pub struct MockClient {
+    state: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl MockClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Install a clone of the mock as the [`Transport`] of the process,
+    /// returning `false` if a transport is already in use.
+    pub fn install(&self) -> bool {
+        crate::set_transport(self.clone())
+    }
+
+    /// Answer the next request of `client_id` with the given `@type` (e.g.
+    /// `"getMe"`) with `response`. The responses scripted for the same type
+    /// are returned in order.
+    pub fn respond(&self, client_id: i32, request_type: &str, response: Value) {
+        let (state, _) = &*self.state;
+        state
+            .lock()
+            .unwrap()
+            .responses
+            .entry((client_id, request_type.to_string()))
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Deliver `update` to `client_id` on the next call of `receive`.
+    pub fn push_update(&self, client_id: i32, mut update: Value) {
+        update["@client_id"] = client_id.into();
+        self.push(update.to_string());
+    }
+
+    /// The requests sent by `client_id` so far, in order, without the
+    /// `@extra` field added by the library.
+    pub fn requests(&self, client_id: i32) -> Vec<Value> {
+        let (state, _) = &*self.state;
+        state
+            .lock()
+            .unwrap()
+            .requests
+            .get(&client_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn push(&self, response: String) {
+        let (state, condvar) = &*self.state;
+        state.lock().unwrap().queue.push_back(response);
+        condvar.notify_all();
+    }
+}
+
+/// The error answering the requests without a scripted response.
+fn no_response(request_type: &str) -> Value {
+    json!({
+        "@type": "error",
+        "code": 500,
+        "message": format!("No response scripted for {request_type}"),
+    })
+}
+
+impl Transport for MockClient {
+    fn create_client(&self) -> i32 {
+        let (state, _) = &*self.state;
+        let mut state = state.lock().unwrap();
+        state.last_client_id += 1;
+        state.last_client_id
+    }
+
+    fn send(&self, client_id: i32, request: String) {
+        let mut request: Value = serde_json::from_str(&request).unwrap();
+        let extra = request
+            .as_object_mut()
+            .and_then(|request| request.remove("@extra"));
+        let request_type = request["@type"].as_str().unwrap_or_default().to_string();
+
+        let (state, _) = &*self.state;
+        let response = {
+            let mut state = state.lock().unwrap();
+            state.requests.entry(client_id).or_default().push(request);
+            state
+                .responses
+                .get_mut(&(client_id, request_type.clone()))
+                .and_then(VecDeque::pop_front)
+        };
+
+        let mut response = response.unwrap_or_else(|| no_response(&request_type));
+        if let Some(extra) = extra {
+            response["@extra"] = extra;
+        }
+        response["@client_id"] = client_id.into();
+        self.push(response.to_string());
+    }
+
+    /// Answer with the response scripted for the client id 0, since the
+    /// requests executed synchronously don't belong to a client.
+    fn execute(&self, request: String) -> String {
+        let request: Value = serde_json::from_str(&request).unwrap();
+        let request_type = request["@type"].as_str().unwrap_or_default().to_string();
+
+        let (state, _) = &*self.state;
+        let mut state = state.lock().unwrap();
+        state.requests.entry(0).or_default().push(request);
+        state
+            .responses
+            .get_mut(&(0, request_type.clone()))
+            .and_then(VecDeque::pop_front)
+            .unwrap_or_else(|| no_response(&request_type))
+            .to_string()
+    }
+
+    fn receive(&self, timeout: f64) -> Option<String> {
+        let (state, condvar) = &*self.state;
+        let state = state.lock().unwrap();
+        let (mut state, _) = condvar
+            .wait_timeout_while(state, Duration::from_secs_f64(timeout), |state| {
+                state.queue.is_empty()
+            })
+            .unwrap();
+        state.queue.pop_front()
+    }
+}
+
+/// A [`Transport`] that records to a file the clients created and the JSON
+/// requests, responses and updates going through `transport`, so that a live
+/// session can be replayed later with [`Replay`].
+///
+/// The file has a JSON object per line. Note that it holds everything sent to
+/// and received from TDLib, including phone numbers, codes and messages.
+pub struct Recorder<T> {
+    transport: T,
+    file: Mutex<BufWriter<File>>,
+}
+
+impl<T: Transport> Recorder<T> {
+    /// Record the traffic of `transport` to the file at `path`, truncating it.
+    pub fn new(transport: T, path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            transport,
+            file: Mutex::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    fn record(&self, event: Value) {
+        let mut file = self.file.lock().unwrap();
+        // The file is flushed on every event to keep the recording of sessions
+        // that end with a crash
+        if let Err(e) = writeln!(file, "{event}").and_then(|_| file.flush()) {
+            log::warn!("Failed to record the TDLib traffic: {e}");
+        }
+    }
+}
+
+impl<T: Transport> Transport for Recorder<T> {
+    fn create_client(&self) -> i32 {
+        let client_id = self.transport.create_client();
+        self.record(json!({ "event": "create_client", "client_id": client_id }));
+        client_id
+    }
+
+    fn send(&self, client_id: i32, request: String) {
+        let value = serde_json::from_str::<Value>(&request).unwrap_or(Value::Null);
+        self.record(json!({ "event": "send", "client_id": client_id, "request": value }));
+        self.transport.send(client_id, request);
+    }
+
+    fn receive(&self, timeout: f64) -> Option<String> {
+        let response = self.transport.receive(timeout)?;
+        let value = serde_json::from_str::<Value>(&response).unwrap_or(Value::Null);
+        self.record(json!({ "event": "receive", "response": value }));
+        Some(response)
+    }
+
+    fn execute(&self, request: String) -> String {
+        self.transport.execute(request)
+    }
+}
+
+enum Event {
+    CreateClient(i32),
+    Send,
+    Receive(String),
+}
+
+struct ReplayState {
+    events: VecDeque<Event>,
+    /// The requests sent and not yet matched with the recorded ones.
+    unmatched_sends: usize,
+}
+
+/// A [`Transport`] that replays a session recorded by [`Recorder`].
+///
+/// The recorded responses and updates are received in the same order as in the
+/// recording, each one only after the requests sent before it in the recording
+/// have been sent again, so the replay is deterministic as long as the
+/// application sends the same requests in the same order. The clients get the
+/// recorded ids, and the requests are not sent anywhere.
+pub struct Replay {
+    state: Mutex<ReplayState>,
+    condvar: Condvar,
+}
+
+impl Replay {
+    /// Load the recording from the file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut events = VecDeque::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: Value = serde_json::from_str(&line)?;
+            events.push_back(match event["event"].as_str() {
+                Some("create_client") => {
+                    Event::CreateClient(event["client_id"].as_i64().unwrap_or_default() as i32)
+                }
+                Some("send") => Event::Send,
+                Some("receive") => Event::Receive(event["response"].to_string()),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Unknown recorded event: {line}"),
+                    ))
+                }
+            });
+        }
+
+        Ok(Self {
+            state: Mutex::new(ReplayState {
+                events,
+                unmatched_sends: 0,
+            }),
+            condvar: Condvar::new(),
+        })
+    }
+
+    /// Whether all the recorded events have been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().events.is_empty()
+    }
+}
+
+impl Transport for Replay {
+    fn create_client(&self) -> i32 {
+        let mut state = self.state.lock().unwrap();
+        let index = state
+            .events
+            .iter()
+            .position(|event| matches!(event, Event::CreateClient(_)))
+            .expect("No more clients in the recording");
+        let Some(Event::CreateClient(client_id)) = state.events.remove(index) else {
+            unreachable!()
+        };
+        self.condvar.notify_all();
+        client_id
+    }
+
+    fn send(&self, _client_id: i32, _request: String) {
+        self.state.lock().unwrap().unmatched_sends += 1;
+        self.condvar.notify_all();
+    }
+
+    fn receive(&self, timeout: f64) -> Option<String> {
+        let deadline = Instant::now() + Duration::from_secs_f64(timeout);
+        let mut state = self.state.lock().unwrap();
+        loop {
+            match state.events.front() {
+                Some(Event::Receive(_)) => {
+                    let Some(Event::Receive(response)) = state.events.pop_front() else {
+                        unreachable!()
+                    };
+                    return Some(response);
+                }
+                Some(Event::Send) if state.unmatched_sends > 0 => {
+                    state.unmatched_sends -= 1;
+                    state.events.pop_front();
+                    continue;
+                }
+                // Wait for the application to create the client or to send
+                // the request, or for the end of a recording that is over
+                _ => {}
+            }
+
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                return None;
+            }
+            state = self.condvar.wait_timeout(state, timeout).unwrap().0;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The [`Transport`] abstracting how the requests reach TDLib, so that it can
+//! be swapped without touching the generated code.
+use crate::{observer, tdjson};
+use once_cell::sync::OnceCell;
+
+/// The channel through which the JSON requests are sent to TDLib and the JSON
+/// responses and updates are received from it.
+///
+/// The default transport is [`TdJson`], which calls the tdjson library. Another
+/// transport can be installed with [`set_transport`], e.g. to mock TDLib in
+/// tests or to forward the requests to a TDLib running in another process.
+pub trait Transport: Send + Sync {
+    /// Create a client, returning its id.
+    fn create_client(&self) -> i32;
+
+    /// Send a JSON-serialized request to the client with id `client_id`.
+    fn send(&self, client_id: i32, request: String);
+
+    /// Receive a JSON-serialized response or update, waiting for it at most
+    /// `timeout` seconds. The `@client_id` field of the JSON object holds the
+    /// id of the client it belongs to.
+    fn receive(&self, timeout: f64) -> Option<String>;
+
+    /// Execute a JSON-serialized request synchronously, returning its
+    /// JSON-serialized response. TDLib can only execute some requests
+    /// synchronously, e.g. `parseTextEntities`.
+    ///
+    /// The default implementation answers with an error, for the transports
+    /// which can't execute the requests synchronously.
+    fn execute(&self, request: String) -> String {
+        let _ = request;
+        observer::error_response(400, "The transport can't execute requests synchronously")
+    }
+}
+
+/// The transport calling the tdjson library, linked or loaded according to
+/// the enabled features.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TdJson;
+
+impl Transport for TdJson {
+    fn create_client(&self) -> i32 {
+        tdjson::create_client()
+    }
+
+    fn send(&self, client_id: i32, request: String) {
+        tdjson::send(client_id, request)
+    }
+
+    fn receive(&self, timeout: f64) -> Option<String> {
+        tdjson::receive(timeout)
+    }
+
+    // tdweb can't execute the requests synchronously
+    #[cfg(not(feature = "wasm"))]
+    fn execute(&self, request: String) -> String {
+        tdjson::execute(request).unwrap_or_else(|| observer::error_response(400, "Invalid request"))
+    }
+}
+
+static TRANSPORT: OnceCell<Box<dyn Transport>> = OnceCell::new();
+
+/// Install the transport used by all the clients, in place of [`TdJson`].
+///
+/// It must be called before creating the first client: once the transport has
+/// been used, it can't be replaced anymore and `false` is returned.
+pub fn set_transport(transport: impl Transport + 'static) -> bool {
+    TRANSPORT.set(Box::new(transport)).is_ok()
+}
+
+/// The installed transport, or [`TdJson`] if none has been installed.
+pub(crate) fn transport() -> &'static dyn Transport {
+    TRANSPORT.get_or_init(|| Box::new(TdJson)).as_ref()
+}
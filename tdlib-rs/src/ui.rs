@@ -0,0 +1,76 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers to render the types with gpui, enabled by the `gpui` feature.
+//!
+//! The text of the types converts to [`SharedString`] without copying it,
+//! e.g. to pass it to the elements of a view, and the colors of TDLib, packed
+//! as `0xRRGGBB` integers, convert to [`Rgba`].
+use crate::types;
+use gpui::{Rgba, SharedString};
+
+/// The built-in accent colors, by their identifier from 0 to 6: red, orange,
+/// violet, green, cyan, blue and pink.
+const BUILT_IN_ACCENT_COLORS: [u32; 7] = [
+    0xcc5049, 0xd67722, 0x955cdb, 0x40a920, 0x309eba, 0x368ad1, 0xc7508b,
+];
+
+/// The color of a `0xRRGGBB` integer of TDLib, e.g. of
+/// `AccentColor::light_theme_colors`.
+pub fn color(rgb: i32) -> Rgba {
+    gpui::rgb(rgb as u32 & 0xff_ffff)
+}
+
+/// The built-in accent color `accent_color_id`, from 0 to 6, e.g. the
+/// `built_in_accent_color_id` of an accent color, or the `accent_color_id` of
+/// a user or a chat if it's a built-in one.
+pub fn built_in_accent_color(accent_color_id: i32) -> Option<Rgba> {
+    let index = usize::try_from(accent_color_id).ok()?;
+    BUILT_IN_ACCENT_COLORS.get(index).copied().map(gpui::rgb)
+}
+
+/// The colors of the accent color `accent_color`, one to three, for the dark
+/// theme if `dark` or else the light theme.
+#[cfg(not(feature = "api-1.8.19"))]
+pub fn accent_colors(accent_color: &types::AccentColor, dark: bool) -> Vec<Rgba> {
+    let colors = if dark {
+        &accent_color.dark_theme_colors
+    } else {
+        &accent_color.light_theme_colors
+    };
+    colors.iter().copied().map(color).collect()
+}
+
+/// Converts to the plain text, without the entities.
+impl From<types::FormattedText> for SharedString {
+    fn from(text: types::FormattedText) -> Self {
+        text.text
+    }
+}
+
+/// Converts to the plain text, without the entities.
+impl From<&types::FormattedText> for SharedString {
+    fn from(text: &types::FormattedText) -> Self {
+        text.text.clone()
+    }
+}
+
+/// Converts to the name formatted by the `Display` implementation of the
+/// user.
+impl From<&types::User> for SharedString {
+    fn from(user: &types::User) -> Self {
+        user.to_string().into()
+    }
+}
+
+/// Converts to the title of the chat.
+impl From<&types::Chat> for SharedString {
+    fn from(chat: &types::Chat) -> Self {
+        chat.title.clone()
+    }
+}
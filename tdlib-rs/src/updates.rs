@@ -0,0 +1,148 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The hook of the updates received by [`receive`](crate::receive), which
+//! keeps the state tracked by the library up to date, wakes the futures
+//! waiting for an update and feeds the streams of updates.
+use crate::enums::{AuthorizationState, Update};
+use crate::observer::now;
+use crate::{cache, client, TdError};
+use futures_channel::{mpsc, oneshot};
+use once_cell::sync::Lazy;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// A future waiting for an update matching a predicate.
+struct Waiter {
+    client_id: i32,
+    matches: Box<dyn FnMut(&Update) -> bool + Send>,
+    /// Sent `None` when the deadline is reached.
+    sender: oneshot::Sender<Option<Update>>,
+    deadline: Option<Duration>,
+}
+
+static WAITERS: Lazy<Mutex<Vec<Waiter>>> = Lazy::new(Mutex::default);
+
+/// A stream of the updates matching a predicate.
+struct Subscriber {
+    client_id: i32,
+    matches: Box<dyn FnMut(&Update) -> bool + Send>,
+    sender: mpsc::UnboundedSender<Update>,
+}
+
+static SUBSCRIBERS: Lazy<Mutex<Vec<Subscriber>>> = Lazy::new(Mutex::default);
+
+/// Let the library see an update of `client_id` before it's returned to the
+/// application.
+pub(crate) fn handle_update(update: &Update, client_id: i32) {
+    client::handle_update(update, client_id);
+    cache::handle_update(update, client_id);
+
+    WAITERS.lock().unwrap().retain_mut(|waiter| {
+        if waiter.sender.is_canceled() {
+            return false;
+        }
+        if waiter.client_id != client_id || !(waiter.matches)(update) {
+            return true;
+        }
+        let sender = std::mem::replace(&mut waiter.sender, oneshot::channel().0);
+        let _ = sender.send(Some(update.clone()));
+        false
+    });
+
+    // The streams of a closed client end after its last update
+    let is_closed = matches!(
+        update,
+        Update::AuthorizationState(update)
+            if matches!(update.authorization_state, AuthorizationState::Closed)
+    );
+    SUBSCRIBERS.lock().unwrap().retain_mut(|subscriber| {
+        if subscriber.client_id != client_id {
+            return !subscriber.sender.is_closed();
+        }
+        if (subscriber.matches)(update) {
+            let _ = subscriber.sender.unbounded_send(update.clone());
+        }
+        !is_closed && !subscriber.sender.is_closed()
+    });
+}
+
+/// Time out the waiters past their deadline, and forget the ones whose future
+/// has been dropped.
+pub(crate) fn sweep() {
+    let now = now();
+    WAITERS.lock().unwrap().retain_mut(|waiter| {
+        if waiter.sender.is_canceled() {
+            return false;
+        }
+        if waiter.deadline.is_some_and(|deadline| now >= deadline) {
+            let sender = std::mem::replace(&mut waiter.sender, oneshot::channel().0);
+            let _ = sender.send(None);
+            return false;
+        }
+        true
+    });
+}
+
+/// The future returned by [`wait_for_update`].
+pub(crate) struct WaitForUpdate(oneshot::Receiver<Option<Update>>);
+
+impl Future for WaitForUpdate {
+    type Output = Result<Update, TdError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0)
+            .poll(cx)
+            .map(|update| update.ok().flatten().ok_or(TdError::Timeout))
+    }
+}
+
+/// Wait for the next update of `client_id` for which `matches` returns `true`,
+/// failing with [`TdError::Timeout`] after `timeout`.
+///
+/// The deadline is checked by [`receive`](crate::receive), so the future can
+/// time out up to the timeout of `receive` late.
+pub(crate) fn wait_for_update(
+    client_id: i32,
+    matches: impl FnMut(&Update) -> bool + Send + 'static,
+    timeout: Option<Duration>,
+) -> WaitForUpdate {
+    let (sender, receiver) = oneshot::channel();
+    WAITERS.lock().unwrap().push(Waiter {
+        client_id,
+        matches: Box::new(matches),
+        sender,
+        deadline: timeout.map(|timeout| now() + timeout),
+    });
+    WaitForUpdate(receiver)
+}
+
+/// Wait for `duration`, as timed by [`receive`](crate::receive) while it
+/// receives the updates of `client_id`.
+pub(crate) async fn sleep(duration: Duration, client_id: i32) {
+    // No update matches, so the wait always times out
+    let _ = wait_for_update(client_id, |_| false, Some(duration)).await;
+}
+
+/// Stream the updates of `client_id` for which `matches` returns `true`,
+/// until the receiver is dropped or the client is closed.
+pub(crate) fn subscribe(
+    client_id: i32,
+    matches: impl FnMut(&Update) -> bool + Send + 'static,
+) -> mpsc::UnboundedReceiver<Update> {
+    let (sender, receiver) = mpsc::unbounded();
+    SUBSCRIBERS.lock().unwrap().push(Subscriber {
+        client_id,
+        matches: Box::new(matches),
+        sender,
+    });
+    receiver
+}
@@ -0,0 +1,154 @@
+// Copyright 2024 - developers of the `tdlib-rs` project.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions between the offsets in UTF-16 code units used by TDLib, e.g.
+//! in the entities of a [`types::FormattedText`], and the offsets in bytes
+//! used to slice the Rust strings.
+//!
+//! The offsets out of the text are clamped to its end, and the ones in the
+//! middle of a character are rounded down to its start, so that the ranges
+//! returned can always be used to slice the text.
+use crate::types;
+use std::ops::Range;
+
+/// The length of `text` in UTF-16 code units.
+pub fn len(text: &str) -> i32 {
+    text.chars().map(|c| c.len_utf16() as i32).sum()
+}
+
+/// The byte offset in `text` of the UTF-16 code unit `offset`.
+pub fn byte_offset(text: &str, offset: i32) -> usize {
+    let mut utf16_offset = 0;
+    for (byte_offset, c) in text.char_indices() {
+        utf16_offset += c.len_utf16() as i32;
+        if utf16_offset > offset {
+            return byte_offset;
+        }
+    }
+    text.len()
+}
+
+/// The byte range in `text` of the `length` UTF-16 code units from `offset`.
+pub fn byte_range(text: &str, offset: i32, length: i32) -> Range<usize> {
+    let start = byte_offset(text, offset);
+    let end = byte_offset(text, offset.saturating_add(length)).max(start);
+    start..end
+}
+
+/// The range of the characters in `text` of the `length` UTF-16 code units
+/// from `offset`, e.g. to move a cursor in a text input.
+pub fn char_range(text: &str, offset: i32, length: i32) -> Range<usize> {
+    let bytes = byte_range(text, offset, length);
+    let start = text[..bytes.start].chars().count();
+    start..start + text[bytes].chars().count()
+}
+
+/// The offset in UTF-16 code units of the byte `offset` of `text`.
+pub fn utf16_offset(text: &str, offset: usize) -> i32 {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    len(&text[..offset])
+}
+
+/// The offset and the length in UTF-16 code units of the byte range `bytes`
+/// of `text`, e.g. to build a [`types::TextEntity`].
+pub fn utf16_range(text: &str, bytes: Range<usize>) -> (i32, i32) {
+    let start = utf16_offset(text, bytes.start);
+    let end = utf16_offset(text, bytes.end).max(start);
+    (start, end - start)
+}
+
+/// The `length` UTF-16 code units of `text` from `offset`, never panicking.
+pub fn substring(text: &str, offset: i32, length: i32) -> &str {
+    &text[byte_range(text, offset, length)]
+}
+
+/// The part of `text` covered by `entity`, e.g. the URL of a
+/// `textEntityTypeUrl`.
+pub fn entity_text<'a>(text: &'a types::FormattedText, entity: &types::TextEntity) -> &'a str {
+    substring(&text.text, entity.offset, entity.length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One byte and one code unit, then a surrogate pair of four bytes.
+    const TEXT: &str = "a😀b";
+
+    #[test]
+    fn check_len() {
+        assert_eq!(len(""), 0);
+        assert_eq!(len(TEXT), 4);
+        assert_eq!(len("é"), 1);
+    }
+
+    #[test]
+    fn check_byte_offset() {
+        assert_eq!(byte_offset(TEXT, 0), 0);
+        assert_eq!(byte_offset(TEXT, 1), 1);
+        // Inside the surrogate pair
+        assert_eq!(byte_offset(TEXT, 2), 1);
+        assert_eq!(byte_offset(TEXT, 3), 5);
+        assert_eq!(byte_offset(TEXT, 4), 6);
+        // Past the end
+        assert_eq!(byte_offset(TEXT, 5), 6);
+        assert_eq!(byte_offset(TEXT, i32::MAX), 6);
+        assert_eq!(byte_offset("", 3), 0);
+    }
+
+    #[test]
+    fn check_byte_range() {
+        assert_eq!(byte_range(TEXT, 1, 2), 1..5);
+        assert_eq!(byte_range(TEXT, 0, 0), 0..0);
+        assert_eq!(byte_range(TEXT, 3, i32::MAX), 5..6);
+        assert_eq!(byte_range(TEXT, 8, 2), 6..6);
+        assert_eq!(byte_range(TEXT, 3, -2), 5..5);
+    }
+
+    #[test]
+    fn check_char_range() {
+        assert_eq!(char_range(TEXT, 1, 2), 1..2);
+        assert_eq!(char_range(TEXT, 3, 1), 2..3);
+        assert_eq!(char_range(TEXT, 4, 1), 3..3);
+    }
+
+    #[test]
+    fn check_utf16_offset() {
+        assert_eq!(utf16_offset(TEXT, 0), 0);
+        assert_eq!(utf16_offset(TEXT, 1), 1);
+        // Inside the emoji
+        assert_eq!(utf16_offset(TEXT, 3), 1);
+        assert_eq!(utf16_offset(TEXT, 5), 3);
+        assert_eq!(utf16_offset(TEXT, 100), 4);
+    }
+
+    #[test]
+    fn check_utf16_range() {
+        assert_eq!(utf16_range(TEXT, 1..5), (1, 2));
+        assert_eq!(utf16_range(TEXT, 5..6), (3, 1));
+        assert_eq!(utf16_range(TEXT, 2..4), (1, 0));
+        // A reversed range
+        assert_eq!(utf16_range(TEXT, Range { start: 5, end: 1 }), (3, 0));
+        assert_eq!(utf16_range(TEXT, 6..6), (4, 0));
+    }
+
+    #[test]
+    fn check_substring() {
+        assert_eq!(substring(TEXT, 1, 2), "😀");
+        // The offsets inside the pair are rounded down to its start
+        assert_eq!(substring(TEXT, 2, 1), "😀");
+        assert_eq!(substring(TEXT, 1, 1), "");
+        assert_eq!(substring(TEXT, 0, 0), "");
+        assert_eq!(substring(TEXT, 3, 10), "b");
+        assert_eq!(substring(TEXT, 10, 5), "");
+        assert_eq!(substring("", 0, 1), "");
+    }
+}